@@ -3,12 +3,16 @@
 //!
 //! Exercises real operations: board manipulation, piece placement and removal,
 //! spatial queries, board validation, level verification, and SMT generation.
+//! The large-board group compares the spatial index against a plain scan of
+//! the piece list on 1000-piece boards.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
 
+use proof_of_work::game::graph::ConnectionGraph;
 use proof_of_work::game::validation;
-use proof_of_work::verification;
-use proof_of_work::{BoardState, GoalCondition, Level, LogicPiece};
+use proof_of_work::verification::{self, VerifierOptions};
+use proof_of_work::{BoardState, Direction, GoalCondition, Level, LogicPiece};
 
 // ---------------------------------------------------------------------------
 // Helpers: build test fixtures once, clone per iteration
@@ -37,10 +41,22 @@ fn populated_board() -> BoardState {
             formula: "T".into(),
             position: (15, 10),
         },
-        LogicPiece::AndIntro { position: (5, 4) },
-        LogicPiece::OrIntro { position: (8, 6) },
-        LogicPiece::ImpliesIntro { position: (10, 4) },
-        LogicPiece::NotIntro { position: (12, 8) },
+        LogicPiece::AndIntro {
+            position: (5, 4),
+            orientation: Direction::East,
+        },
+        LogicPiece::OrIntro {
+            position: (8, 6),
+            orientation: Direction::East,
+        },
+        LogicPiece::ImpliesIntro {
+            position: (10, 4),
+            orientation: Direction::East,
+        },
+        LogicPiece::NotIntro {
+            position: (12, 8),
+            orientation: Direction::East,
+        },
         LogicPiece::Wire {
             from: (3, 5),
             to: (5, 4),
@@ -65,6 +81,44 @@ fn populated_board() -> BoardState {
     BoardState::with_pieces(20, 20, pieces)
 }
 
+/// Side of the square filled by `large_board` (1024 pieces).
+const LARGE_SIDE: u32 = 32;
+
+/// A 64x64 board with a 32x32 block of pieces: each row is an assumption
+/// feeding a chain of NOT gates into a goal, ports facing each other, so
+/// every goal is reachable and every piece is linked to its neighbours.
+fn large_board() -> BoardState {
+    let mut board = BoardState::new(2 * LARGE_SIDE, 2 * LARGE_SIDE);
+    for y in 0..LARGE_SIDE {
+        for x in 0..LARGE_SIDE {
+            let position = (x, 2 * y);
+            let piece = match x {
+                0 => LogicPiece::Assumption {
+                    formula: format!("P{}", y),
+                    position,
+                },
+                x if x == LARGE_SIDE - 1 => LogicPiece::Goal {
+                    formula: format!("G{}", y),
+                    position,
+                },
+                _ => LogicPiece::NotIntro {
+                    position,
+                    orientation: Direction::East,
+                },
+            };
+            board.place_piece(piece);
+        }
+    }
+    board
+}
+
+/// Query cells spread over the large board, half of them empty.
+fn large_board_queries() -> Vec<(u32, u32)> {
+    (0..256u32)
+        .map(|i| ((i * 7) % (2 * LARGE_SIDE), (i * 13) % (2 * LARGE_SIDE)))
+        .collect()
+}
+
 /// Create a level suitable for verification benchmarking.
 fn verifiable_level() -> Level {
     Level {
@@ -76,26 +130,31 @@ fn verifiable_level() -> Level {
         goal_state: GoalCondition::ProveFormula {
             formula: "R".into(),
         },
+        witness: None,
+        inventory: Default::default(),
     }
 }
 
-/// Pieces that form a valid proof for the verifiable level (AND gate adjacent to
-/// both assumptions and the goal).
+/// Pieces that form a valid proof for the verifiable level (both assumptions
+/// face the AND gate's inputs, and its output faces the goal).
 fn valid_proof_pieces() -> Vec<LogicPiece> {
     vec![
         LogicPiece::Assumption {
             formula: "P".into(),
-            position: (2, 5),
+            position: (3, 5),
         },
         LogicPiece::Assumption {
             formula: "Q".into(),
-            position: (2, 3),
+            position: (3, 4),
         },
         LogicPiece::Goal {
             formula: "R".into(),
             position: (5, 4),
         },
-        LogicPiece::AndIntro { position: (3, 4) },
+        LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        },
     ]
 }
 
@@ -110,7 +169,10 @@ fn bench_board_place_pieces(c: &mut Criterion) {
             let mut board = BoardState::new(20, 20);
             for i in 0..10u32 {
                 for j in 0..10u32 {
-                    board.place_piece(LogicPiece::AndIntro { position: (i, j) });
+                    board.place_piece(LogicPiece::AndIntro {
+                        position: (i, j),
+                        orientation: Direction::East,
+                    });
                 }
             }
             black_box(board.piece_count())
@@ -125,7 +187,7 @@ fn bench_board_remove_pieces(c: &mut Criterion) {
     c.bench_function("board_remove_all_pieces", |b| {
         b.iter(|| {
             let mut board = template.clone();
-            let positions: Vec<(u32, u32)> = board.pieces.iter().map(|p| p.position()).collect();
+            let positions: Vec<(u32, u32)> = board.pieces().iter().map(|p| p.position()).collect();
             for (x, y) in &positions {
                 board.remove_piece(*x, *y);
             }
@@ -166,7 +228,10 @@ fn bench_board_move_piece(c: &mut Criterion) {
     c.bench_function("board_move_piece_round_trip", |b| {
         b.iter(|| {
             let mut board = BoardState::new(20, 20);
-            board.place_piece(LogicPiece::AndIntro { position: (5, 5) });
+            board.place_piece(LogicPiece::AndIntro {
+                position: (5, 5),
+                orientation: Direction::East,
+            });
             board.move_piece((5, 5), (10, 10));
             board.move_piece((10, 10), (15, 15));
             board.move_piece((15, 15), (0, 0));
@@ -175,6 +240,111 @@ fn bench_board_move_piece(c: &mut Criterion) {
     });
 }
 
+// ---------------------------------------------------------------------------
+// Large-board benchmarks
+// ---------------------------------------------------------------------------
+
+/// Point lookups on a 1024-piece board: the index against a linear scan.
+fn bench_large_board_lookups(c: &mut Criterion) {
+    let board = large_board();
+    let queries = large_board_queries();
+    // Build the index outside the timed loop, as a long-lived board would
+    board.spatial();
+
+    let mut group = c.benchmark_group("large_board_lookup_256_cells");
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            let hits = queries
+                .iter()
+                .filter(|&&(x, y)| board.piece_at(x, y).is_some())
+                .count();
+            black_box(hits)
+        });
+    });
+    group.bench_function("linear_scan", |b| {
+        b.iter(|| {
+            let hits = queries
+                .iter()
+                .filter(|&&cell| board.pieces().iter().any(|p| p.position() == cell))
+                .count();
+            black_box(hits)
+        });
+    });
+    group.finish();
+}
+
+/// Radius queries on a 1024-piece board.
+fn bench_large_board_pieces_near(c: &mut Criterion) {
+    let board = large_board();
+    let queries = large_board_queries();
+    board.spatial();
+
+    let mut group = c.benchmark_group("large_board_pieces_near_radius_2");
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            let total: usize = queries
+                .iter()
+                .map(|&(x, y)| board.pieces_near(x, y, 2).len())
+                .sum();
+            black_box(total)
+        });
+    });
+    group.bench_function("linear_scan", |b| {
+        b.iter(|| {
+            let total: usize = queries
+                .iter()
+                .map(|&(x, y)| {
+                    board
+                        .pieces()
+                        .iter()
+                        .filter(|p| {
+                            let (px, py) = p.position();
+                            px.abs_diff(x) <= 2 && py.abs_diff(y) <= 2
+                        })
+                        .count()
+                })
+                .sum();
+            black_box(total)
+        });
+    });
+    group.finish();
+}
+
+/// Filling an empty board with 1024 pieces, each placement checking
+/// occupancy.
+fn bench_large_board_place(c: &mut Criterion) {
+    c.bench_function("large_board_place_1024_pieces", |b| {
+        b.iter(|| black_box(large_board().piece_count()));
+    });
+}
+
+/// Full validation (overlaps and connectivity) of a 1024-piece board.
+fn bench_large_board_validate(c: &mut Criterion) {
+    let board = large_board();
+
+    c.bench_function("validate_board_1024_pieces", |b| {
+        b.iter(|| {
+            let result = validation::validate_board(black_box(&board));
+            black_box(result.is_valid)
+        });
+    });
+}
+
+/// The reachability search behind verification: build the connection
+/// graph of a 1024-piece board and flood it from assumptions and goals.
+fn bench_large_board_search(c: &mut Criterion) {
+    let board = large_board();
+
+    c.bench_function("connection_search_1024_pieces", |b| {
+        b.iter(|| {
+            let graph = ConnectionGraph::from_board(black_box(&board));
+            let fed = graph.reachable_from_assumptions();
+            let feeds = graph.reaches_goal();
+            black_box(fed.iter().zip(&feeds).filter(|(a, b)| **a && **b).count())
+        });
+    });
+}
+
 // ---------------------------------------------------------------------------
 // Validation benchmarks
 // ---------------------------------------------------------------------------
@@ -203,7 +373,10 @@ fn bench_validate_piece_placement(c: &mut Criterion) {
             from: (3, 3),
             to: (7, 7),
         },
-        LogicPiece::AndIntro { position: (99, 99) }, // out of bounds
+        LogicPiece::AndIntro {
+            position: (99, 99),
+            orientation: Direction::East,
+        }, // out of bounds
         LogicPiece::Goal {
             formula: "".into(),
             position: (4, 4),
@@ -244,6 +417,8 @@ fn bench_validate_level(c: &mut Criterion) {
             start: (2, 5),
             end: (15, 5),
         },
+        witness: None,
+        inventory: Default::default(),
     };
 
     c.bench_function("validate_level_definition", |b| {
@@ -292,6 +467,7 @@ fn bench_verify_level_solution(c: &mut Criterion) {
             black_box(verification::verify_level_solution(
                 black_box(&level),
                 black_box(&pieces),
+                &VerifierOptions::default(),
             ))
         });
     });
@@ -313,7 +489,10 @@ fn bench_verify_level_solution_invalid(c: &mut Criterion) {
             formula: "R".into(),
             position: (19, 19),
         },
-        LogicPiece::AndIntro { position: (10, 10) }, // too far from everything
+        LogicPiece::AndIntro {
+            position: (10, 10),
+            orientation: Direction::East,
+        }, // too far from everything
     ];
 
     c.bench_function("verify_level_solution_invalid", |b| {
@@ -321,6 +500,7 @@ fn bench_verify_level_solution_invalid(c: &mut Criterion) {
             black_box(verification::verify_level_solution(
                 black_box(&level),
                 black_box(&pieces),
+                &VerifierOptions::default(),
             ))
         });
     });
@@ -337,11 +517,18 @@ fn bench_piece_to_smt(c: &mut Criterion) {
             formula: "Q".into(),
             position: (8, 4),
         },
-        LogicPiece::AndIntro { position: (5, 5) },
-        LogicPiece::ImpliesIntro { position: (3, 3) },
+        LogicPiece::AndIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        },
+        LogicPiece::ImpliesIntro {
+            position: (3, 3),
+            orientation: Direction::East,
+        },
         LogicPiece::ForallIntro {
             position: (1, 1),
             variable: "x".into(),
+            orientation: Direction::East,
         },
     ];
 
@@ -369,6 +556,15 @@ fn bench_piece_to_smt(c: &mut Criterion) {
     bench_board_move_piece,
 );
 
+criterion_group!(
+    large_board_benches,
+    bench_large_board_lookups,
+    bench_large_board_pieces_near,
+    bench_large_board_place,
+    bench_large_board_validate,
+    bench_large_board_search,
+);
+
 criterion_group!(
     validation_benches,
     bench_validate_board,
@@ -386,4 +582,9 @@ fn bench_piece_to_smt(c: &mut Criterion) {
     bench_piece_to_smt,
 );
 
-criterion_main!(board_benches, validation_benches, verification_benches);
+criterion_main!(
+    board_benches,
+    large_board_benches,
+    validation_benches,
+    verification_benches
+);
@@ -9,8 +9,8 @@
 use libfuzzer_sys::fuzz_target;
 
 // Import from the main crate
-use proof_of_work::game::{BoardState, GoalCondition, Level, LogicPiece};
 use proof_of_work::game::validation::{validate_board, validate_piece_placement};
+use proof_of_work::game::{BoardState, Direction, GoalCondition, Level, LogicPiece};
 use proof_of_work::verification::verify_level_solution;
 
 /// Generate a LogicPiece from fuzz input bytes.
@@ -23,6 +23,12 @@ fn piece_from_bytes(data: &[u8], offset: usize) -> Option<LogicPiece> {
     let x = *data.get(offset + 1)? as u32 % 20;
     let y = *data.get(offset + 2)? as u32 % 20;
     let extra = *data.get(offset + 3)?;
+    let orientation = match (extra / 4) % 4 {
+        0 => Direction::East,
+        1 => Direction::South,
+        2 => Direction::West,
+        _ => Direction::North,
+    };
 
     Some(match piece_type {
         0 => LogicPiece::Assumption {
@@ -43,13 +49,26 @@ fn piece_from_bytes(data: &[u8], offset: usize) -> Option<LogicPiece> {
             },
             position: (x, y),
         },
-        2 => LogicPiece::AndIntro { position: (x, y) },
-        3 => LogicPiece::OrIntro { position: (x, y) },
-        4 => LogicPiece::ImpliesIntro { position: (x, y) },
-        5 => LogicPiece::NotIntro { position: (x, y) },
+        2 => LogicPiece::AndIntro {
+            position: (x, y),
+            orientation,
+        },
+        3 => LogicPiece::OrIntro {
+            position: (x, y),
+            orientation,
+        },
+        4 => LogicPiece::ImpliesIntro {
+            position: (x, y),
+            orientation,
+        },
+        5 => LogicPiece::NotIntro {
+            position: (x, y),
+            orientation,
+        },
         6 => LogicPiece::ForallIntro {
             position: (x, y),
             variable: "x".to_string(),
+            orientation,
         },
         _ => LogicPiece::Wire {
             from: (x, y),
@@ -61,11 +80,7 @@ fn piece_from_bytes(data: &[u8], offset: usize) -> Option<LogicPiece> {
 /// Generate a BoardState from fuzz input.
 fn board_from_bytes(data: &[u8]) -> BoardState {
     if data.len() < 2 {
-        return BoardState {
-            width: 10,
-            height: 10,
-            pieces: Vec::new(),
-        };
+        return BoardState::new(10, 10);
     }
 
     let width = (data[0] as u32 % 30).max(5);
@@ -81,11 +96,7 @@ fn board_from_bytes(data: &[u8]) -> BoardState {
         offset += 4;
     }
 
-    BoardState {
-        width,
-        height,
-        pieces,
-    }
+    BoardState::with_pieces(width, height, pieces)
 }
 
 /// Generate a Level from fuzz input.
@@ -111,6 +122,8 @@ fn level_from_bytes(data: &[u8]) -> Level {
         theorem: "(assert (=> (and P Q) R))".to_string(),
         initial_state: board,
         goal_state,
+        witness: None,
+        inventory: Default::default(),
     }
 }
 
@@ -122,7 +135,7 @@ fn level_from_bytes(data: &[u8]) -> Level {
     let _ = validate_board(&board);
 
     // Test piece placement validation
-    for piece in &board.pieces {
+    for piece in board.pieces() {
         let _ = validate_piece_placement(&board, piece);
     }
 
@@ -131,5 +144,5 @@ fn level_from_bytes(data: &[u8]) -> Level {
 
     // Test verification doesn't panic
     // (verification may return true or false, we just want no crashes)
-    let _ = verify_level_solution(&level, &level.initial_state.pieces);
+    let _ = verify_level_solution(&level, level.initial_state.pieces());
 });
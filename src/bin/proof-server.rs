@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Reference proof server. Run `proof-server --help` for its options.
+
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use proof_of_work::levels::LevelPackManager;
+use proof_of_work::server::keys::KeyStore;
+use proof_of_work::server::store::ScoreStore;
+use proof_of_work::server::{ProofServer, ServerArgs, USAGE};
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args = match ServerArgs::parse(std::env::args().skip(1), |name| std::env::var(name).ok()) {
+        Ok(args) if args.help => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let scores = match ScoreStore::open(&args.data_dir) {
+        Ok(scores) => scores,
+        Err(e) => {
+            eprintln!("error: failed to open scores: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let keys = match KeyStore::open(&args.data_dir) {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("error: failed to open API keys: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // The tutorial, the --packs directory, then the data directory's packs
+    let mut levels =
+        LevelPackManager::new(args.data_dir.join("packs")).with_builtin_dir(args.packs_dir.clone());
+    if let Err(e) = levels.load_all() {
+        eprintln!("error: failed to load level packs: {}", e);
+        std::process::exit(1);
+    }
+    if !cfg!(feature = "z3-verify") {
+        tracing::warn!("Built without z3-verify: every proof will be rejected as unverifiable");
+    }
+
+    let listener = match TcpListener::bind(&args.addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("error: failed to listen on {}: {}", args.addr, e);
+            std::process::exit(1);
+        }
+    };
+
+    tracing::info!(
+        "Serving {} proofs for {} packs from {} on http://{}",
+        scores.records().len(),
+        levels.packs.len(),
+        args.data_dir.display(),
+        args.addr
+    );
+    let server = ProofServer::new(scores, levels.packs).with_keys(keys);
+    if let Err(e) = Arc::new(server).serve(listener) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Periodic autosave of the level being edited, so unsaved work survives a
+//! crash and can be offered back the next time the editor opens.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::EditorState;
+use crate::game::Level;
+use crate::levels::LevelPackError;
+
+/// Seconds between autosaves of a dirty level.
+pub const AUTOSAVE_INTERVAL_SECS: f32 = 30.0;
+
+/// Snapshot of an unsaved level, written to the recovery file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorRecovery {
+    pub level: Level,
+    pub pack_id: Option<String>,
+    pub is_new_level: bool,
+}
+
+impl EditorRecovery {
+    /// Snapshot the level currently in the editor
+    pub fn of(editor: &EditorState) -> Self {
+        Self {
+            level: editor.level.clone(),
+            pack_id: editor.pack_id.clone(),
+            is_new_level: editor.is_new_level,
+        }
+    }
+
+    /// Rebuild an editor around the recovered level. The result is dirty,
+    /// since the recovered work has never been saved to a pack.
+    pub fn restore(self) -> EditorState {
+        let mut editor = EditorState::from_level(self.level, self.pack_id);
+        editor.is_new_level = self.is_new_level;
+        editor.dirty = true;
+        editor.status_message = "Recovered unsaved level".to_string();
+        editor
+    }
+
+    /// Write the snapshot to disk
+    pub fn save(&self, path: &Path) -> Result<(), LevelPackError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| LevelPackError::SerializationError(e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+        }
+        fs::write(path, json).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Read a snapshot from disk, if one exists
+    pub fn load(path: &Path) -> Result<Option<Self>, LevelPackError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| LevelPackError::DeserializationError(e.to_string()))
+    }
+}
+
+/// Autosave bookkeeping for the editor
+#[derive(Debug, Resource)]
+pub struct EditorAutosave {
+    /// Where the recovery file lives
+    pub path: PathBuf,
+    /// Time until the next autosave
+    pub timer: Timer,
+    /// Whether the recovery file has already been checked this session
+    pub recovery_checked: bool,
+    /// Last snapshot written, to skip rewriting an unchanged level
+    last_written: Option<EditorRecovery>,
+}
+
+impl EditorAutosave {
+    /// Autosave to `path` every [`AUTOSAVE_INTERVAL_SECS`]
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            timer: Timer::from_seconds(AUTOSAVE_INTERVAL_SECS, TimerMode::Repeating),
+            recovery_checked: false,
+            last_written: None,
+        }
+    }
+
+    /// Write the editor's level if it is dirty and changed since the last
+    /// autosave. Returns true if the file was written.
+    pub fn write(&mut self, editor: &EditorState) -> Result<bool, LevelPackError> {
+        if !editor.dirty {
+            return Ok(false);
+        }
+        let snapshot = EditorRecovery::of(editor);
+        if self.last_written.as_ref() == Some(&snapshot) {
+            return Ok(false);
+        }
+        snapshot.save(&self.path)?;
+        self.last_written = Some(snapshot);
+        Ok(true)
+    }
+
+    /// Remove the recovery file once its contents are saved or discarded
+    pub fn clear(&mut self) {
+        self.last_written = None;
+        if self.path.exists() {
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!("Failed to remove editor recovery file: {}", e);
+            }
+        }
+    }
+
+    /// Take the recovery file left by a previous session, at most once
+    pub fn take_recovery(&mut self) -> Option<EditorRecovery> {
+        if self.recovery_checked {
+            return None;
+        }
+        self.recovery_checked = true;
+        match EditorRecovery::load(&self.path) {
+            Ok(recovery) => recovery,
+            Err(e) => {
+                warn!("Ignoring unreadable editor recovery file: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, LogicPiece};
+
+    fn temp_path(tag: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("pow-autosave-{}-{}.json", tag, nanos))
+    }
+
+    #[test]
+    fn test_autosave_only_writes_dirty_changes() {
+        let path = temp_path("dirty");
+        let mut autosave = EditorAutosave::new(path.clone());
+        let mut editor = EditorState::default();
+
+        assert!(!autosave.write(&editor).unwrap());
+        assert!(!path.exists());
+
+        editor.add_piece(LogicPiece::AndIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        });
+        assert!(autosave.write(&editor).unwrap());
+        assert!(!autosave.write(&editor).unwrap());
+
+        autosave.clear();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_recovery_is_offered_once() {
+        let path = temp_path("recover");
+        let mut editor = EditorState {
+            pack_id: Some("mine".to_string()),
+            ..Default::default()
+        };
+        editor.add_piece(LogicPiece::OrIntro {
+            position: (2, 3),
+            orientation: Direction::East,
+        });
+        EditorAutosave::new(path.clone()).write(&editor).unwrap();
+
+        let mut next_session = EditorAutosave::new(path.clone());
+        let recovered = next_session.take_recovery().expect("recovery file");
+        assert!(next_session.take_recovery().is_none());
+
+        let restored = recovered.restore();
+        assert!(restored.dirty);
+        assert_eq!(restored.pack_id.as_deref(), Some("mine"));
+        assert!(restored.is_position_occupied((2, 3)));
+
+        next_session.clear();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Undo/redo command history for the level editor.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::game::{
+    BoardState, FlaggedPiece, GoalCondition, Inventory, Level, LogicPiece, PieceFlags,
+};
+
+/// Default number of commands kept for undo.
+pub const DEFAULT_EDITOR_HISTORY_CAPACITY: usize = 200;
+
+/// Level fields edited through the properties panel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelMetadata {
+    pub name: String,
+    pub description: String,
+    pub theorem: String,
+    pub goal_state: GoalCondition,
+    pub inventory: Inventory,
+}
+
+impl LevelMetadata {
+    /// Snapshot the metadata of a level
+    pub fn of(level: &Level) -> Self {
+        Self {
+            name: level.name.clone(),
+            description: level.description.clone(),
+            theorem: level.theorem.clone(),
+            goal_state: level.goal_state.clone(),
+            inventory: level.inventory.clone(),
+        }
+    }
+
+    /// Write this metadata back into a level
+    pub fn apply_to(&self, level: &mut Level) {
+        level.name = self.name.clone();
+        level.description = self.description.clone();
+        level.theorem = self.theorem.clone();
+        level.goal_state = self.goal_state.clone();
+        level.inventory = self.inventory.clone();
+    }
+}
+
+/// A reversible edit to the level being edited
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EditorCommand {
+    /// Pieces were added to the board
+    AddPieces { pieces: Vec<LogicPiece> },
+    /// Pieces were removed from the board, along with the flags of those
+    /// that had any
+    RemovePieces {
+        pieces: Vec<LogicPiece>,
+        #[serde(default)]
+        flags: Vec<FlaggedPiece>,
+    },
+    /// Pieces were swapped for others in one step (e.g. a rerouted wire)
+    ReplacePieces {
+        old: Vec<LogicPiece>,
+        new: Vec<LogicPiece>,
+    },
+    /// The pieces at `positions` were translated by `delta`
+    MovePieces {
+        positions: Vec<(u32, u32)>,
+        delta: (i32, i32),
+    },
+    /// The grid was resized; `clipped` holds the pieces that fell outside,
+    /// `clipped_flags` their flags and `clipped_obstacles` the obstacles
+    ResizeGrid {
+        old_size: (u32, u32),
+        new_size: (u32, u32),
+        clipped: Vec<LogicPiece>,
+        #[serde(default)]
+        clipped_flags: Vec<FlaggedPiece>,
+        #[serde(default)]
+        clipped_obstacles: Vec<(u32, u32)>,
+    },
+    /// A cell was marked or cleared as blocked terrain
+    SetObstacle { cell: (u32, u32), blocked: bool },
+    /// The flags of the pre-placed piece at `position` changed
+    SetFlags {
+        position: (u32, u32),
+        old: PieceFlags,
+        new: PieceFlags,
+    },
+    /// Name, description, theorem, goal or inventory changed
+    EditMetadata {
+        old: LevelMetadata,
+        new: LevelMetadata,
+    },
+    /// The level's solvability witness was replaced
+    SetWitness {
+        old: Option<Vec<LogicPiece>>,
+        new: Option<Vec<LogicPiece>>,
+    },
+}
+
+impl EditorCommand {
+    /// Remove `pieces` from `board`, taking their flags with them
+    pub fn remove_pieces(board: &BoardState, pieces: Vec<LogicPiece>) -> Self {
+        let flags = flags_of(board, &pieces);
+        Self::RemovePieces { pieces, flags }
+    }
+
+    /// Resize `board` to `new_size`, removing the pieces, flags and
+    /// obstacles that no longer fit
+    pub fn resize_grid(board: &BoardState, new_size: (u32, u32)) -> Self {
+        let outside = |&(x, y): &(u32, u32)| x >= new_size.0 || y >= new_size.1;
+        let clipped: Vec<LogicPiece> = board
+            .pieces()
+            .iter()
+            .filter(|p| p.footprint().iter().any(outside))
+            .cloned()
+            .collect();
+        Self::ResizeGrid {
+            old_size: (board.width, board.height),
+            new_size,
+            clipped_flags: flags_of(board, &clipped),
+            clipped_obstacles: board.obstacles.iter().copied().filter(outside).collect(),
+            clipped,
+        }
+    }
+
+    /// Apply the command to a level
+    pub fn apply(&self, level: &mut Level) {
+        let pieces = level.initial_state.pieces_mut();
+        match self {
+            Self::AddPieces { pieces: added } => pieces.extend(added.iter().cloned()),
+            Self::RemovePieces {
+                pieces: removed,
+                flags,
+            } => {
+                pieces.retain(|p| !removed.contains(p));
+                clear_flags(&mut level.initial_state, flags);
+            }
+            Self::ReplacePieces { old, new } => {
+                pieces.retain(|p| !old.contains(p));
+                pieces.extend(new.iter().cloned());
+            }
+            Self::MovePieces { positions, delta } => {
+                translate_at(pieces, positions, *delta);
+                translate_flags(&mut level.initial_state, positions, *delta);
+            }
+            Self::SetObstacle { cell, blocked } => {
+                level.initial_state.set_obstacle(*cell, *blocked)
+            }
+            Self::SetFlags { position, new, .. } => level.initial_state.set_flags(*position, *new),
+            Self::ResizeGrid {
+                new_size,
+                clipped_flags,
+                clipped_obstacles,
+                ..
+            } => {
+                let board = &mut level.initial_state;
+                board.width = new_size.0;
+                board.height = new_size.1;
+                board.pieces_mut().retain(|p| {
+                    p.footprint()
+                        .iter()
+                        .all(|&(x, y)| x < new_size.0 && y < new_size.1)
+                });
+                clear_flags(board, clipped_flags);
+                board.obstacles.retain(|c| !clipped_obstacles.contains(c));
+            }
+            Self::EditMetadata { new, .. } => new.apply_to(level),
+            Self::SetWitness { new, .. } => level.witness = new.clone(),
+        }
+    }
+
+    /// Undo the command on a level it was previously applied to
+    pub fn revert(&self, level: &mut Level) {
+        let pieces = level.initial_state.pieces_mut();
+        match self {
+            Self::AddPieces { pieces: added } => pieces.retain(|p| !added.contains(p)),
+            Self::RemovePieces {
+                pieces: removed,
+                flags,
+            } => {
+                pieces.extend(removed.iter().cloned());
+                restore_flags(&mut level.initial_state, flags);
+            }
+            Self::ReplacePieces { old, new } => {
+                pieces.retain(|p| !new.contains(p));
+                pieces.extend(old.iter().cloned());
+            }
+            Self::MovePieces { positions, delta } => {
+                let moved: Vec<(u32, u32)> = positions
+                    .iter()
+                    .map(|&(x, y)| {
+                        (
+                            (x as i64 + delta.0 as i64) as u32,
+                            (y as i64 + delta.1 as i64) as u32,
+                        )
+                    })
+                    .collect();
+                translate_at(pieces, &moved, (-delta.0, -delta.1));
+                translate_flags(&mut level.initial_state, &moved, (-delta.0, -delta.1));
+            }
+            Self::SetObstacle { cell, blocked } => {
+                level.initial_state.set_obstacle(*cell, !*blocked)
+            }
+            Self::SetFlags { position, old, .. } => level.initial_state.set_flags(*position, *old),
+            Self::ResizeGrid {
+                old_size,
+                clipped,
+                clipped_flags,
+                clipped_obstacles,
+                ..
+            } => {
+                let board = &mut level.initial_state;
+                board.width = old_size.0;
+                board.height = old_size.1;
+                board.pieces_mut().extend(clipped.iter().cloned());
+                restore_flags(board, clipped_flags);
+                board.obstacles.extend(clipped_obstacles.iter().copied());
+            }
+            Self::EditMetadata { old, .. } => old.apply_to(level),
+            Self::SetWitness { old, .. } => level.witness = old.clone(),
+        }
+    }
+
+    /// Short description for the status bar
+    pub fn describe(&self) -> String {
+        match self {
+            Self::AddPieces { pieces } => format!("add {} piece(s)", pieces.len()),
+            Self::RemovePieces { pieces, .. } => format!("remove {} piece(s)", pieces.len()),
+            Self::ReplacePieces { new, .. } => format!("replace {} piece(s)", new.len()),
+            Self::MovePieces { positions, .. } => format!("move {} piece(s)", positions.len()),
+            Self::ResizeGrid { new_size, .. } => {
+                format!("resize grid to {}x{}", new_size.0, new_size.1)
+            }
+            Self::SetObstacle { cell, blocked } => format!(
+                "{} obstacle at ({}, {})",
+                if *blocked { "add" } else { "clear" },
+                cell.0,
+                cell.1
+            ),
+            Self::SetFlags { position, .. } => {
+                format!("change flags at ({}, {})", position.0, position.1)
+            }
+            Self::EditMetadata { .. } => "edit level properties".to_string(),
+            Self::SetWitness { .. } => "save the witness".to_string(),
+        }
+    }
+}
+
+/// Translate every piece whose position is in `positions` by `delta`.
+/// Pieces are matched before any of them move, so overlapping source and
+/// target cells are handled correctly.
+fn translate_at(pieces: &mut [LogicPiece], positions: &[(u32, u32)], delta: (i32, i32)) {
+    let targets: Vec<usize> = pieces
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| positions.contains(&p.position()))
+        .map(|(i, _)| i)
+        .collect();
+    for i in targets {
+        if let Some(moved) = pieces[i].translated(delta.0, delta.1) {
+            pieces[i] = moved;
+        }
+    }
+}
+
+/// The flags `board` holds for any of `pieces`.
+fn flags_of(board: &BoardState, pieces: &[LogicPiece]) -> Vec<FlaggedPiece> {
+    board
+        .flags
+        .iter()
+        .filter(|f| pieces.iter().any(|p| p.position() == f.position))
+        .copied()
+        .collect()
+}
+
+/// Drop `flags` from the board, so a piece added later at one of their
+/// positions starts out locked.
+fn clear_flags(board: &mut BoardState, flags: &[FlaggedPiece]) {
+    board.flags.retain(|f| !flags.contains(f));
+}
+
+/// Put `flags` back on the board.
+fn restore_flags(board: &mut BoardState, flags: &[FlaggedPiece]) {
+    for flagged in flags {
+        board.set_flags(flagged.position, flagged.flags);
+    }
+}
+
+/// Move the flags of pieces at `positions` along with them.
+fn translate_flags(board: &mut BoardState, positions: &[(u32, u32)], delta: (i32, i32)) {
+    for flagged in &mut board.flags {
+        if positions.contains(&flagged.position) {
+            let (x, y) = flagged.position;
+            if let (Some(x), Some(y)) =
+                (x.checked_add_signed(delta.0), y.checked_add_signed(delta.1))
+            {
+                flagged.position = (x, y);
+            }
+        }
+    }
+}
+
+/// Bounded undo/redo stacks of editor commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorHistory {
+    undo_stack: VecDeque<EditorCommand>,
+    redo_stack: Vec<EditorCommand>,
+    capacity: usize,
+}
+
+impl Default for EditorHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_EDITOR_HISTORY_CAPACITY)
+    }
+}
+
+impl EditorHistory {
+    /// Create an empty history that keeps at most `capacity` undo steps
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record a command that has already been applied. Consecutive
+    /// metadata edits (e.g. typing a name) are merged into one step.
+    pub fn push(&mut self, command: EditorCommand) {
+        self.redo_stack.clear();
+
+        if let EditorCommand::EditMetadata { new, .. } = &command {
+            if let Some(EditorCommand::EditMetadata { old, new: last_new }) =
+                self.undo_stack.back_mut()
+            {
+                *last_new = new.clone();
+                if old == last_new {
+                    self.undo_stack.pop_back();
+                }
+                return;
+            }
+        }
+
+        self.undo_stack.push_back(command);
+        while self.undo_stack.len() > self.capacity {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Pop the most recent command for reverting
+    pub fn undo(&mut self) -> Option<EditorCommand> {
+        let command = self.undo_stack.pop_back()?;
+        self.redo_stack.push(command.clone());
+        Some(command)
+    }
+
+    /// Pop the most recently undone command for re-applying
+    pub fn redo(&mut self) -> Option<EditorCommand> {
+        let command = self.redo_stack.pop()?;
+        self.undo_stack.push_back(command.clone());
+        Some(command)
+    }
+
+    /// Whether there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Whether there is anything to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Forget all recorded commands
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
@@ -1,12 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 //! Level editor for creating and modifying puzzles.
 
+pub mod autosave;
+pub mod history;
 pub mod ui;
 
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::game::{BoardState, GoalCondition, Level, LogicPiece};
+pub use autosave::{EditorAutosave, EditorRecovery};
+pub use history::{EditorCommand, EditorHistory, LevelMetadata};
+
+use crate::game::validation::{validate_level, ValidationResult};
+use crate::game::wiring;
+use crate::game::{BoardGrid, BoardState, Direction, GoalCondition, Level, LogicPiece, PieceFlags};
 use crate::levels::LevelPack;
 
 /// The state of the level being edited
@@ -32,10 +39,26 @@ pub struct EditorState {
     pub formula_input: String,
     /// Variable input for quantifiers
     pub variable_input: String,
+    /// Way newly placed gates face
+    pub orientation: Direction,
     /// Status message
     pub status_message: String,
     /// Whether level has unsaved changes
     pub dirty: bool,
+    /// Undo/redo history of edits to `level`
+    pub history: EditorHistory,
+    /// Positions of the currently selected pieces
+    pub selection: Vec<(u32, u32)>,
+    /// Copied pieces, positioned relative to the copied area's corner
+    pub clipboard: Vec<LogicPiece>,
+    /// Cell where the current mouse drag started (select or move tool)
+    pub drag_start: Option<(u32, u32)>,
+    /// Cell currently under the mouse while dragging
+    pub drag_current: Option<(u32, u32)>,
+    /// Action waiting on the unsaved-changes dialog
+    pub pending_confirm: Option<EditorConfirm>,
+    /// Autosaved level from a previous session, waiting to be restored
+    pub recovery: Option<EditorRecovery>,
 }
 
 impl Default for EditorState {
@@ -46,14 +69,12 @@ fn default() -> Self {
                 name: "New Level".to_string(),
                 description: "Enter description here".to_string(),
                 theorem: "".to_string(),
-                initial_state: BoardState {
-                    width: 10,
-                    height: 10,
-                    pieces: vec![],
-                },
+                initial_state: BoardState::new(10, 10),
                 goal_state: GoalCondition::ProveFormula {
                     formula: "Goal".to_string(),
                 },
+                witness: None,
+                inventory: Default::default(),
             },
             pack_id: None,
             is_new_level: true,
@@ -64,8 +85,16 @@ fn default() -> Self {
             selected_piece: None,
             formula_input: String::new(),
             variable_input: "x".to_string(),
+            orientation: Direction::East,
             status_message: "Ready".to_string(),
             dirty: false,
+            history: EditorHistory::default(),
+            selection: vec![],
+            clipboard: vec![],
+            drag_start: None,
+            drag_current: None,
+            pending_confirm: None,
+            recovery: None,
         }
     }
 }
@@ -91,29 +120,148 @@ pub fn from_level(level: Level, pack_id: Option<String>) -> Self {
         }
     }
 
+    /// Apply an edit and record it for undo
+    pub fn execute(&mut self, command: EditorCommand) {
+        command.apply(&mut self.level);
+        self.sync_grid_size();
+        self.history.push(command);
+        self.dirty = true;
+    }
+
+    /// Undo the most recent edit
+    pub fn undo(&mut self) -> bool {
+        let Some(command) = self.history.undo() else {
+            self.status_message = "Nothing to undo".to_string();
+            return false;
+        };
+        command.revert(&mut self.level);
+        self.sync_grid_size();
+        self.selection.clear();
+        self.dirty = true;
+        self.status_message = format!("Undid {}", command.describe());
+        true
+    }
+
+    /// Redo the most recently undone edit
+    pub fn redo(&mut self) -> bool {
+        let Some(command) = self.history.redo() else {
+            self.status_message = "Nothing to redo".to_string();
+            return false;
+        };
+        command.apply(&mut self.level);
+        self.sync_grid_size();
+        self.selection.clear();
+        self.dirty = true;
+        self.status_message = format!("Redid {}", command.describe());
+        true
+    }
+
+    /// Keep the cached grid size in step with the level's board
+    fn sync_grid_size(&mut self) {
+        self.grid_width = self.level.initial_state.width;
+        self.grid_height = self.level.initial_state.height;
+    }
+
+    /// Snapshot of the level properties, taken before the properties panel
+    /// edits them in place
+    pub fn metadata(&self) -> LevelMetadata {
+        LevelMetadata::of(&self.level)
+    }
+
+    /// Record in-place edits to the level properties made since `before`
+    pub fn commit_metadata(&mut self, before: LevelMetadata) {
+        let after = self.metadata();
+        if after != before {
+            self.history.push(EditorCommand::EditMetadata {
+                old: before,
+                new: after,
+            });
+            self.dirty = true;
+        }
+    }
+
     /// Add a piece at the specified position
     pub fn add_piece(&mut self, piece: LogicPiece) {
-        // Check if position is already occupied
+        // Check every cell the piece would cover
         let pos = piece.position();
-        if !self.is_position_occupied(pos) {
-            self.level.initial_state.pieces.push(piece);
-            self.dirty = true;
+        let board = &self.level.initial_state;
+        let footprint = piece.footprint();
+        if footprint.iter().any(|&(x, y)| board.is_obstacle(x, y)) {
+            self.status_message = format!("Position ({}, {}) is an obstacle", pos.0, pos.1);
+        } else if !footprint.iter().all(|&(x, y)| board.in_bounds(x, y)) {
+            self.status_message = format!("Piece at ({}, {}) would leave the grid", pos.0, pos.1);
+        } else if !footprint
+            .iter()
+            .any(|&cell| self.is_position_occupied(cell))
+        {
+            self.execute(EditorCommand::AddPieces {
+                pieces: vec![piece],
+            });
             self.status_message = format!("Added piece at ({}, {})", pos.0, pos.1);
         } else {
             self.status_message = format!("Position ({}, {}) is occupied", pos.0, pos.1);
         }
     }
 
-    /// Remove a piece at the specified position
-    pub fn remove_piece_at(&mut self, pos: (u32, u32)) -> bool {
-        let initial_len = self.level.initial_state.pieces.len();
-        self.level
-            .initial_state
-            .pieces
-            .retain(|p| p.position() != pos);
+    /// Turn the gate anchored at `pos` a quarter turn, or back the other
+    /// way. Fails if there is no gate there or its turned footprint
+    /// doesn't fit.
+    pub fn rotate_piece_at(&mut self, pos: (u32, u32), clockwise: bool) -> bool {
+        let board = &self.level.initial_state;
+        let Some(old) = board
+            .piece_at(pos.0, pos.1)
+            .filter(|p| p.position() == pos && p.orientation().is_some())
+            .cloned()
+        else {
+            self.status_message = format!("No gate to turn at ({}, {})", pos.0, pos.1);
+            return false;
+        };
+        let facing = old.orientation().unwrap_or_default();
+        let mut new = old.clone();
+        new.set_orientation(if clockwise {
+            facing.clockwise()
+        } else {
+            facing.counter_clockwise()
+        });
+        if !board.fits(&new, &[pos]) {
+            self.status_message = format!("No room to turn the gate at ({}, {})", pos.0, pos.1);
+            return false;
+        }
 
-        if self.level.initial_state.pieces.len() < initial_len {
-            self.dirty = true;
+        self.status_message = format!(
+            "Turned gate to face {:?}",
+            new.orientation().unwrap_or_default()
+        );
+        self.execute(EditorCommand::ReplacePieces {
+            old: vec![old],
+            new: vec![new],
+        });
+        true
+    }
+
+    /// Turn the way newly placed gates face
+    pub fn rotate_placement(&mut self, clockwise: bool) {
+        self.orientation = if clockwise {
+            self.orientation.clockwise()
+        } else {
+            self.orientation.counter_clockwise()
+        };
+        self.status_message = format!("New gates face {:?}", self.orientation);
+    }
+
+    /// Remove a piece at the specified position, or failing that a wire
+    /// running through it
+    pub fn remove_piece_at(&mut self, pos: (u32, u32)) -> bool {
+        let piece = self
+            .get_piece_at(pos)
+            .or_else(|| wiring::wire_at(&self.level.initial_state, pos))
+            .cloned();
+        if let Some(piece) = piece {
+            self.execute(EditorCommand::remove_pieces(
+                &self.level.initial_state,
+                vec![piece],
+            ));
+            self.selection.retain(|p| *p != pos);
             self.status_message = format!("Removed piece at ({}, {})", pos.0, pos.1);
             true
         } else {
@@ -122,37 +270,225 @@ pub fn remove_piece_at(&mut self, pos: (u32, u32)) -> bool {
         }
     }
 
-    /// Check if a position is occupied
-    pub fn is_position_occupied(&self, pos: (u32, u32)) -> bool {
+    /// Finish a wire-tool drag from `start` to `end`. Dragging from the end
+    /// of an existing wire reroutes that end; otherwise a new wire is drawn
+    /// between the nearest ports.
+    pub fn draw_wire(&mut self, start: (u32, u32), end: (u32, u32)) -> bool {
+        let board = &self.level.initial_state;
+        let result = match wiring::wire_end_at(board, start).cloned() {
+            Some(old) => {
+                wiring::reroute(board, &old, start, end).map(|new| EditorCommand::ReplacePieces {
+                    old: vec![old],
+                    new: vec![new],
+                })
+            }
+            None => wiring::snap_wire(board, start, end)
+                .map(|wire| EditorCommand::AddPieces { pieces: vec![wire] }),
+        };
+        match result {
+            Ok(command) => {
+                self.status_message = format!("Wire: {}", command.describe());
+                self.execute(command);
+                true
+            }
+            Err(reason) => {
+                self.status_message = reason;
+                false
+            }
+        }
+    }
+
+    /// Toggle whether a cell is blocked terrain. Occupied cells can't
+    /// become obstacles.
+    pub fn toggle_obstacle(&mut self, cell: (u32, u32)) -> bool {
+        let blocked = !self.level.initial_state.is_obstacle(cell.0, cell.1);
+        if blocked && self.is_position_occupied(cell) {
+            self.status_message = format!("Position ({}, {}) is occupied", cell.0, cell.1);
+            return false;
+        }
+        self.execute(EditorCommand::SetObstacle { cell, blocked });
+        self.status_message = format!(
+            "{} obstacle at ({}, {})",
+            if blocked { "Added" } else { "Cleared" },
+            cell.0,
+            cell.1
+        );
+        true
+    }
+
+    /// Change whether the player may move or delete the piece at `position`
+    pub fn set_piece_flags(&mut self, position: (u32, u32), flags: PieceFlags) {
+        let old = self.level.initial_state.flags_at(position);
+        if old == flags || self.get_piece_at(position).is_none() {
+            return;
+        }
+        self.execute(EditorCommand::SetFlags {
+            position,
+            old,
+            new: flags,
+        });
+    }
+
+    /// Select every piece inside the rectangle spanned by two corners
+    pub fn select_rect(&mut self, a: (u32, u32), b: (u32, u32), extend: bool) {
+        let (min_x, max_x) = (a.0.min(b.0), a.0.max(b.0));
+        let (min_y, max_y) = (a.1.min(b.1), a.1.max(b.1));
+        if !extend {
+            self.selection.clear();
+        }
+        for piece in self.level.initial_state.pieces() {
+            let (x, y) = piece.position();
+            let inside = (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y);
+            if inside && !self.selection.contains(&(x, y)) {
+                self.selection.push((x, y));
+            }
+        }
+        self.status_message = format!("{} piece(s) selected", self.selection.len());
+    }
+
+    /// Cell-to-world mapping for the grid being edited
+    pub fn board_grid(&self) -> BoardGrid {
+        BoardGrid::new(self.grid_width, self.grid_height)
+    }
+
+    /// Select every piece on the board
+    pub fn select_all(&mut self) {
+        let (w, h) = (self.grid_width, self.grid_height);
+        self.select_rect((0, 0), (w.saturating_sub(1), h.saturating_sub(1)), false);
+    }
+
+    /// Currently selected pieces
+    pub fn selected_pieces(&self) -> Vec<LogicPiece> {
         self.level
             .initial_state
-            .pieces
+            .pieces()
+            .iter()
+            .filter(|p| self.selection.contains(&p.position()))
+            .cloned()
+            .collect()
+    }
+
+    /// Copy the selection to the clipboard
+    pub fn copy_selection(&mut self) {
+        let pieces = self.selected_pieces();
+        // A wire may run back past its anchor, so both ends count towards
+        // the corner the clipboard is measured from
+        let cells: Vec<(u32, u32)> = pieces
+            .iter()
+            .flat_map(|p| match p {
+                LogicPiece::Wire { from, to } => vec![*from, *to],
+                _ => vec![p.position()],
+            })
+            .collect();
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        self.clipboard = pieces
+            .iter()
+            .filter_map(|p| p.translated(-(min_x as i32), -(min_y as i32)))
+            .collect();
+        self.status_message = format!("Copied {} piece(s)", self.clipboard.len());
+    }
+
+    /// Paste the clipboard with its corner at `anchor`. Fails without
+    /// changes if any pasted piece would be out of bounds or overlap.
+    pub fn paste_at(&mut self, anchor: (u32, u32)) -> bool {
+        if self.clipboard.is_empty() {
+            self.status_message = "Clipboard is empty".to_string();
+            return false;
+        }
+        let pieces: Option<Vec<LogicPiece>> = self
+            .clipboard
+            .iter()
+            .map(|p| p.translated(anchor.0 as i32, anchor.1 as i32))
+            .collect();
+        let fits = pieces
+            .as_ref()
+            .is_some_and(|pieces| pieces.iter().all(|p| self.level.initial_state.fits(p, &[])));
+        let Some(pieces) = pieces.filter(|_| fits) else {
+            self.status_message = "Not enough room to paste here".to_string();
+            return false;
+        };
+
+        self.selection = pieces.iter().map(|p| p.position()).collect();
+        let count = pieces.len();
+        self.execute(EditorCommand::AddPieces { pieces });
+        self.status_message = format!("Pasted {} piece(s)", count);
+        true
+    }
+
+    /// Move the selection by `delta`. Fails without changes if any piece
+    /// would leave the board or land on an unselected piece.
+    pub fn move_selection(&mut self, delta: (i32, i32)) -> bool {
+        if self.selection.is_empty() || delta == (0, 0) {
+            return false;
+        }
+        let targets: Option<Vec<(u32, u32)>> = self
+            .selection
             .iter()
-            .any(|p| p.position() == pos)
+            .map(|&(x, y)| {
+                Some((
+                    x.checked_add_signed(delta.0)?,
+                    y.checked_add_signed(delta.1)?,
+                ))
+            })
+            .collect();
+        // Selected pieces may move into cells the selection is leaving
+        let fits = targets.is_some()
+            && self.selected_pieces().iter().all(|piece| {
+                piece
+                    .translated(delta.0, delta.1)
+                    .is_some_and(|moved| self.level.initial_state.fits(&moved, &self.selection))
+            });
+        let Some(targets) = targets.filter(|_| fits) else {
+            self.status_message = "Cannot move selection there".to_string();
+            return false;
+        };
+
+        let positions = std::mem::replace(&mut self.selection, targets);
+        let count = positions.len();
+        self.execute(EditorCommand::MovePieces { positions, delta });
+        self.status_message = format!("Moved {} piece(s)", count);
+        true
+    }
+
+    /// Remove every selected piece
+    pub fn delete_selection(&mut self) -> bool {
+        let pieces = self.selected_pieces();
+        self.selection.clear();
+        if pieces.is_empty() {
+            return false;
+        }
+        let count = pieces.len();
+        self.execute(EditorCommand::remove_pieces(
+            &self.level.initial_state,
+            pieces,
+        ));
+        self.status_message = format!("Removed {} piece(s)", count);
+        true
+    }
+
+    /// Check if a position is occupied
+    pub fn is_position_occupied(&self, pos: (u32, u32)) -> bool {
+        self.level.initial_state.is_occupied(pos.0, pos.1)
     }
 
     /// Get piece at position
     pub fn get_piece_at(&self, pos: (u32, u32)) -> Option<&LogicPiece> {
-        self.level
-            .initial_state
-            .pieces
-            .iter()
-            .find(|p| p.position() == pos)
+        self.level.initial_state.piece_at(pos.0, pos.1)
     }
 
     /// Update grid size
     pub fn set_grid_size(&mut self, width: u32, height: u32) {
-        self.grid_width = width;
-        self.grid_height = height;
-        self.level.initial_state.width = width;
-        self.level.initial_state.height = height;
-        self.dirty = true;
+        if (self.grid_width, self.grid_height) == (width, height) {
+            return;
+        }
 
-        // Remove pieces outside the new grid
-        self.level.initial_state.pieces.retain(|p| {
-            let (x, y) = p.position();
-            x < width && y < height
-        });
+        // Pieces outside the new grid are removed (and restored on undo)
+        self.selection.retain(|&(x, y)| x < width && y < height);
+        self.execute(EditorCommand::resize_grid(
+            &self.level.initial_state,
+            (width, height),
+        ));
     }
 
     /// Validate the level for playability
@@ -163,7 +499,7 @@ pub fn validate(&self) -> Result<(), Vec<String>> {
         let has_assumption = self
             .level
             .initial_state
-            .pieces
+            .pieces()
             .iter()
             .any(|p| matches!(p, LogicPiece::Assumption { .. }));
         if !has_assumption {
@@ -174,7 +510,7 @@ pub fn validate(&self) -> Result<(), Vec<String>> {
         let goal_count = self
             .level
             .initial_state
-            .pieces
+            .pieces()
             .iter()
             .filter(|p| matches!(p, LogicPiece::Goal { .. }))
             .count();
@@ -196,12 +532,31 @@ pub fn validate(&self) -> Result<(), Vec<String>> {
         }
     }
 
+    /// Store a verified solution as the level's solvability witness
+    pub fn set_witness(&mut self, pieces: Vec<LogicPiece>) {
+        self.execute(EditorCommand::SetWitness {
+            old: self.level.witness.clone(),
+            new: Some(pieces),
+        });
+        self.status_message = "Saved test solution as witness".to_string();
+    }
+
     /// Build the final level
     pub fn build_level(&self) -> Level {
         self.level.clone()
     }
 }
 
+/// Actions that discard the current level and so need confirming when it
+/// has unsaved changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorConfirm {
+    /// Leave the editor for the main menu
+    Exit,
+    /// Start a new blank level
+    New,
+}
+
 /// Editor tools
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -211,6 +566,8 @@ pub enum EditorTool {
     Place,
     Delete,
     Move,
+    Wire,
+    Obstacle,
 }
 
 /// Piece types that can be placed in the editor
@@ -252,7 +609,7 @@ pub fn needs_variable(&self) -> bool {
     }
 
     /// Create a LogicPiece from this type at the given position
-    pub fn to_logic_piece(&self, pos: (u32, u32), formula: &str, variable: &str) -> LogicPiece {
+    pub fn to_logic_piece(self, pos: (u32, u32), formula: &str, variable: &str) -> LogicPiece {
         match self {
             Self::Assumption => LogicPiece::Assumption {
                 formula: formula.to_string(),
@@ -262,17 +619,31 @@ pub fn to_logic_piece(&self, pos: (u32, u32), formula: &str, variable: &str) ->
                 formula: formula.to_string(),
                 position: pos,
             },
-            Self::AndIntro => LogicPiece::AndIntro { position: pos },
-            Self::OrIntro => LogicPiece::OrIntro { position: pos },
-            Self::ImpliesIntro => LogicPiece::ImpliesIntro { position: pos },
-            Self::NotIntro => LogicPiece::NotIntro { position: pos },
+            Self::AndIntro => LogicPiece::AndIntro {
+                position: pos,
+                orientation: Direction::East,
+            },
+            Self::OrIntro => LogicPiece::OrIntro {
+                position: pos,
+                orientation: Direction::East,
+            },
+            Self::ImpliesIntro => LogicPiece::ImpliesIntro {
+                position: pos,
+                orientation: Direction::East,
+            },
+            Self::NotIntro => LogicPiece::NotIntro {
+                position: pos,
+                orientation: Direction::East,
+            },
             Self::ForallIntro => LogicPiece::ForallIntro {
                 position: pos,
                 variable: variable.to_string(),
+                orientation: Direction::East,
             },
             Self::ExistsIntro => LogicPiece::ExistsIntro {
                 position: pos,
                 variable: variable.to_string(),
+                orientation: Direction::East,
             },
         }
     }
@@ -282,6 +653,59 @@ pub fn to_logic_piece(&self, pos: (u32, u32), formula: &str, variable: &str) ->
 #[derive(Component)]
 pub struct EditorEntity;
 
+/// Marker component for the editor's background grid cells
+#[derive(Component)]
+pub struct EditorGridCell;
+
+/// Marker component for selection highlights drawn over editor cells
+#[derive(Component)]
+pub struct EditorSelectionMarker;
+
+/// Marker component for obstacle cells drawn in the editor
+#[derive(Component)]
+pub struct EditorObstacle;
+
+/// Marker component for diagnostic overlays drawn over editor cells
+#[derive(Component)]
+pub struct EditorDiagnostic;
+
+/// Full validation of the level being edited, for the diagnostics panel
+/// and overlay. Validating traces the whole circuit, so the result is kept
+/// until the level changes.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct EditorDiagnostics {
+    /// The level last validated, and what validation found
+    checked: Option<(Level, ValidationResult)>,
+}
+
+impl EditorDiagnostics {
+    /// Validate `level`.
+    pub fn refresh(&mut self, level: &Level) {
+        self.checked = Some((level.clone(), validate_level(level)));
+    }
+
+    /// Whether the result is for `level`.
+    pub fn is_current(&self, level: &Level) -> bool {
+        self.checked
+            .as_ref()
+            .is_some_and(|(checked, _)| checked == level)
+    }
+
+    /// What the last validation found, if anything has been validated.
+    pub fn result(&self) -> Option<&ValidationResult> {
+        self.checked.as_ref().map(|(_, result)| result)
+    }
+}
+
+/// An in-progress test play of the level being edited
+#[derive(Debug, Clone, Default, Resource)]
+pub struct TestPlaySession {
+    /// Whether the Playing state is running the editor's level
+    pub active: bool,
+    /// Board the verifier accepted during this test play, if any
+    pub solution: Option<Vec<LogicPiece>>,
+}
+
 /// Event for testing a level from the editor
 #[derive(bevy::prelude::Message, Clone)]
 pub struct TestLevelEvent;
@@ -292,8 +716,10 @@ pub struct SaveLevelEvent {
     pub to_pack_id: Option<String>,
 }
 
+/// Pack the editor offers to create for the player's levels
+pub const NEW_PACK_NAME: &str = "My Levels";
+
 /// Create a new level pack for user-created levels
-#[allow(dead_code)]
 pub fn create_user_pack(name: &str, author: &str) -> LevelPack {
     let id = name
         .to_lowercase()
@@ -307,6 +733,7 @@ pub fn create_user_pack(name: &str, author: &str) -> LevelPack {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::validation::ValidationError;
 
     #[test]
     fn test_editor_state_default() {
@@ -326,11 +753,37 @@ fn test_add_remove_piece() {
         };
         state.add_piece(piece);
 
-        assert_eq!(state.level.initial_state.pieces.len(), 1);
+        assert_eq!(state.level.initial_state.pieces().len(), 1);
         assert!(state.dirty);
 
         assert!(state.remove_piece_at((5, 5)));
-        assert_eq!(state.level.initial_state.pieces.len(), 0);
+        assert_eq!(state.level.initial_state.pieces().len(), 0);
+    }
+
+    #[test]
+    fn test_diagnostics_are_kept_until_the_level_changes() {
+        let mut state = EditorState::default();
+        let mut diagnostics = EditorDiagnostics::default();
+        assert!(!diagnostics.is_current(&state.level));
+        assert!(diagnostics.result().is_none());
+
+        diagnostics.refresh(&state.level);
+        assert!(diagnostics.is_current(&state.level));
+        let no_goals = |d: &EditorDiagnostics| {
+            d.result()
+                .unwrap()
+                .errors
+                .contains(&ValidationError::NoGoals)
+        };
+        assert!(no_goals(&diagnostics));
+
+        state.add_piece(LogicPiece::Goal {
+            formula: "P".to_string(),
+            position: (5, 5),
+        });
+        assert!(!diagnostics.is_current(&state.level));
+        diagnostics.refresh(&state.level);
+        assert!(!no_goals(&diagnostics));
     }
 
     #[test]
@@ -354,4 +807,277 @@ fn test_validate_level() {
         });
         assert!(state.validate().is_ok());
     }
+
+    #[test]
+    fn test_undo_redo_add_remove() {
+        let mut state = EditorState::default();
+        state.add_piece(LogicPiece::AndIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        });
+        state.remove_piece_at((1, 1));
+        assert!(!state.is_position_occupied((1, 1)));
+
+        assert!(state.undo());
+        assert!(state.is_position_occupied((1, 1)));
+        assert!(state.undo());
+        assert!(!state.is_position_occupied((1, 1)));
+        assert!(!state.undo());
+
+        assert!(state.redo());
+        assert!(state.is_position_occupied((1, 1)));
+    }
+
+    #[test]
+    fn test_undo_resize_restores_clipped_pieces() {
+        let mut state = EditorState::default();
+        state.add_piece(LogicPiece::OrIntro {
+            position: (8, 8),
+            orientation: Direction::East,
+        });
+        state.set_piece_flags((8, 8), PieceFlags::FREE);
+        state.toggle_obstacle((7, 7));
+        state.set_grid_size(5, 5);
+        assert_eq!(state.level.initial_state.pieces().len(), 0);
+        assert!(state.level.initial_state.flags.is_empty());
+        assert!(state.level.initial_state.obstacles.is_empty());
+
+        assert!(state.undo());
+        assert_eq!((state.grid_width, state.grid_height), (10, 10));
+        assert_eq!(state.level.initial_state.width, 10);
+        assert!(state.is_position_occupied((8, 8)));
+        assert_eq!(state.level.initial_state.flags_at((8, 8)), PieceFlags::FREE);
+        assert!(state.level.initial_state.is_obstacle(7, 7));
+    }
+
+    #[test]
+    fn test_removed_piece_takes_its_flags() {
+        let mut state = EditorState::default();
+        let gate = LogicPiece::NotIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        state.add_piece(gate.clone());
+        state.set_piece_flags((4, 4), PieceFlags::FREE);
+        assert!(state.remove_piece_at((4, 4)));
+
+        // A piece added on the same cell starts out locked
+        state.add_piece(gate);
+        assert!(state.level.initial_state.flags_at((4, 4)).is_locked());
+
+        state.undo();
+        state.undo();
+        assert_eq!(state.level.initial_state.flags_at((4, 4)), PieceFlags::FREE);
+    }
+
+    #[test]
+    fn test_metadata_edits_merge_into_one_step() {
+        let mut state = EditorState::default();
+        let original = state.level.name.clone();
+        for name in ["A", "Ab", "Abc"] {
+            let before = state.metadata();
+            state.level.name = name.to_string();
+            state.commit_metadata(before);
+        }
+
+        assert!(state.undo());
+        assert_eq!(state.level.name, original);
+        assert!(!state.history.can_undo());
+    }
+
+    #[test]
+    fn test_move_selection_as_group() {
+        let mut state = EditorState::default();
+        state.add_piece(LogicPiece::AndIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        });
+        state.add_piece(LogicPiece::OrIntro {
+            position: (2, 1),
+            orientation: Direction::East,
+        });
+        state.add_piece(LogicPiece::NotIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        });
+
+        state.select_rect((0, 0), (3, 3), false);
+        assert_eq!(state.selection.len(), 2);
+
+        // Shifting right overlaps the group's own cells, which is allowed.
+        assert!(state.move_selection((1, 0)));
+        assert!(matches!(
+            state.get_piece_at((2, 1)),
+            Some(LogicPiece::AndIntro { .. })
+        ));
+        assert!(matches!(
+            state.get_piece_at((3, 1)),
+            Some(LogicPiece::OrIntro { .. })
+        ));
+
+        // Blocked by the unselected NOT gate.
+        state.selection = vec![(3, 1)];
+        assert!(!state.move_selection((2, 4)));
+
+        state.undo();
+        assert!(state.is_position_occupied((1, 1)));
+        assert!(state.is_position_occupied((2, 1)));
+        assert!(!state.is_position_occupied((3, 1)));
+    }
+
+    #[test]
+    fn test_rotate_gate_is_undoable() {
+        let mut state = EditorState::default();
+        state.add_piece(LogicPiece::AndIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        });
+        assert!(state.rotate_piece_at((1, 1), true));
+        assert!(state.is_position_occupied((2, 1)));
+        assert!(!state.is_position_occupied((1, 2)));
+
+        // No room to turn back once the cell above is taken
+        state.add_piece(LogicPiece::NotIntro {
+            position: (1, 2),
+            orientation: Direction::East,
+        });
+        assert!(!state.rotate_piece_at((1, 1), false));
+        assert!(!state.rotate_piece_at((5, 5), true));
+
+        state.undo();
+        state.undo();
+        assert_eq!(
+            state.get_piece_at((1, 1)).and_then(LogicPiece::orientation),
+            Some(Direction::East)
+        );
+
+        state.rotate_placement(false);
+        assert_eq!(state.orientation, Direction::North);
+    }
+
+    #[test]
+    fn test_draw_reroute_and_delete_wire() {
+        let mut state = EditorState::new();
+        state.add_piece(LogicPiece::Assumption {
+            formula: "P".to_string(),
+            position: (1, 1),
+        });
+        state.add_piece(LogicPiece::Goal {
+            formula: "P".to_string(),
+            position: (6, 1),
+        });
+        state.add_piece(LogicPiece::AndIntro {
+            position: (6, 6),
+            orientation: Direction::East,
+        });
+
+        assert!(state.draw_wire((1, 1), (6, 1)));
+        let drawn = LogicPiece::Wire {
+            from: (2, 1),
+            to: (5, 1),
+        };
+        assert!(state.level.initial_state.pieces().contains(&drawn));
+
+        // Drag the far end onto the gate
+        assert!(state.draw_wire((5, 1), (6, 6)));
+        assert!(!state.level.initial_state.pieces().contains(&drawn));
+        state.undo();
+        assert!(state.level.initial_state.pieces().contains(&drawn));
+
+        // Clicking anywhere along the wire deletes it
+        assert!(state.remove_piece_at((3, 1)));
+        assert!(!state.level.initial_state.pieces().contains(&drawn));
+    }
+
+    #[test]
+    fn test_copy_paste_and_delete_selection() {
+        let mut state = EditorState::default();
+        state.add_piece(LogicPiece::AndIntro {
+            position: (3, 3),
+            orientation: Direction::East,
+        });
+        state.add_piece(LogicPiece::Wire {
+            from: (4, 3),
+            to: (6, 3),
+        });
+
+        state.select_all();
+        state.copy_selection();
+        assert!(state.paste_at((0, 6)));
+        assert!(state.is_position_occupied((0, 6)));
+        assert!(state
+            .level
+            .initial_state
+            .pieces()
+            .contains(&LogicPiece::Wire {
+                from: (1, 6),
+                to: (3, 6),
+            }));
+
+        // Pasting on top of existing pieces is refused.
+        assert!(!state.paste_at((3, 3)));
+
+        assert!(state.delete_selection());
+        assert_eq!(state.level.initial_state.pieces().len(), 2);
+        state.undo();
+        assert_eq!(state.level.initial_state.pieces().len(), 4);
+    }
+
+    #[test]
+    fn test_copy_keeps_wires_drawn_backwards() {
+        let mut state = EditorState::default();
+        state.add_piece(LogicPiece::AndIntro {
+            position: (3, 3),
+            orientation: Direction::East,
+        });
+        state.add_piece(LogicPiece::Wire {
+            from: (6, 5),
+            to: (2, 5),
+        });
+
+        state.select_all();
+        state.copy_selection();
+        assert_eq!(state.clipboard.len(), 2);
+        assert!(state.paste_at((0, 7)));
+        assert!(state
+            .level
+            .initial_state
+            .pieces()
+            .contains(&LogicPiece::Wire {
+                from: (4, 9),
+                to: (0, 9),
+            }));
+    }
+
+    #[test]
+    fn test_obstacles_and_piece_flags_are_undoable() {
+        let mut state = EditorState::default();
+        assert!(state.toggle_obstacle((2, 2)));
+        state.add_piece(LogicPiece::AndIntro {
+            position: (2, 2),
+            orientation: Direction::East,
+        });
+        assert!(!state.is_position_occupied((2, 2)));
+
+        state.add_piece(LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        });
+        assert!(!state.toggle_obstacle((4, 4)));
+        state.set_piece_flags((4, 4), PieceFlags::FREE);
+        assert_eq!(state.level.initial_state.flags_at((4, 4)), PieceFlags::FREE);
+
+        // Flags travel with a moved piece
+        state.selection = vec![(4, 4)];
+        assert!(state.move_selection((1, 0)));
+        assert_eq!(state.level.initial_state.flags_at((5, 4)), PieceFlags::FREE);
+        state.undo();
+        assert_eq!(state.level.initial_state.flags_at((4, 4)), PieceFlags::FREE);
+
+        state.undo();
+        assert!(state.level.initial_state.flags_at((4, 4)).is_locked());
+        state.undo();
+        state.undo();
+        assert!(!state.level.initial_state.is_obstacle(2, 2));
+    }
 }
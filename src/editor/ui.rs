@@ -1,34 +1,60 @@
 // SPDX-License-Identifier: MPL-2.0
 //! Editor UI systems using egui.
 
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::AccumulatedMouseScroll;
 use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
 use bevy_egui::{egui, EguiContexts};
 
 use super::{
-    EditorEntity, EditorPieceType, EditorState, EditorTool, SaveLevelEvent, TestLevelEvent,
+    create_user_pack, EditorAutosave, EditorConfirm, EditorDiagnostic, EditorDiagnostics,
+    EditorEntity, EditorGridCell, EditorObstacle, EditorPieceType, EditorSelectionMarker,
+    EditorState, EditorTool, SaveLevelEvent, TestLevelEvent, TestPlaySession, NEW_PACK_NAME,
+};
+use crate::game::wiring;
+use crate::game::{
+    rotation_input, Action, ActionState, BoardGrid, GoalCondition, Inventory, LogicPiece,
+    PlaceablePiece, CELL_SIZE,
 };
-use crate::game::{GoalCondition, LogicPiece};
 use crate::levels::LevelPackManager;
 use crate::states::GameState;
 
+/// What the editor UI's buttons and dialogs act on besides the level
+#[derive(SystemParam)]
+pub struct EditorUiTargets<'w> {
+    next_state: ResMut<'w, NextState<GameState>>,
+    test_events: MessageWriter<'w, TestLevelEvent>,
+    save_events: MessageWriter<'w, SaveLevelEvent>,
+    pack_manager: Option<ResMut<'w, LevelPackManager>>,
+    autosave: ResMut<'w, EditorAutosave>,
+}
+
 /// Render the editor UI
 pub fn editor_ui_system(
     mut contexts: EguiContexts,
     mut editor: ResMut<EditorState>,
-    mut next_state: ResMut<NextState<GameState>>,
+    diagnostics: Res<EditorDiagnostics>,
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut test_events: MessageWriter<TestLevelEvent>,
-    mut save_events: MessageWriter<SaveLevelEvent>,
-    pack_manager: Option<Res<LevelPackManager>>,
+    targets: EditorUiTargets,
 ) {
-    // ESC to exit editor
+    let EditorUiTargets {
+        mut next_state,
+        mut test_events,
+        mut save_events,
+        mut pack_manager,
+        mut autosave,
+    } = targets;
+
+    // ESC to exit editor (or dismiss the open dialog)
     if keyboard.just_pressed(KeyCode::Escape) {
-        if editor.dirty {
-            editor.status_message = "Unsaved changes! Press ESC again to discard.".to_string();
-            // TODO: Add confirmation dialog
-        } else {
-            next_state.set(GameState::MainMenu);
-            return;
+        if editor.pending_confirm.is_some() {
+            editor.pending_confirm = None;
+        } else if editor.recovery.is_none() {
+            request_confirm(&mut editor, EditorConfirm::Exit, &mut next_state);
+            if editor.pending_confirm.is_none() {
+                return;
+            }
         }
     }
 
@@ -36,6 +62,15 @@ pub fn editor_ui_system(
         return;
     };
 
+    show_recovery_dialog(ctx, &mut editor, &mut autosave);
+    show_confirm_dialog(
+        ctx,
+        &mut editor,
+        pack_manager.as_deref_mut(),
+        &mut autosave,
+        &mut next_state,
+    );
+
     // egui 0.35 panels render into a `Ui`, not a `Context`. Build a root viewport
     // `Ui` covering the whole window and show all panels into it.
     let mut viewport_ui = egui::Ui::new(
@@ -54,7 +89,7 @@ pub fn editor_ui_system(
 
             // File operations
             if ui.button("New").clicked() {
-                *editor = EditorState::default();
+                request_confirm(&mut editor, EditorConfirm::New, &mut next_state);
             }
 
             if ui.button("Test").clicked() {
@@ -76,8 +111,23 @@ pub fn editor_ui_system(
 
             ui.separator();
 
+            if ui
+                .add_enabled(editor.history.can_undo(), egui::Button::new("Undo"))
+                .clicked()
+            {
+                editor.undo();
+            }
+            if ui
+                .add_enabled(editor.history.can_redo(), egui::Button::new("Redo"))
+                .clicked()
+            {
+                editor.redo();
+            }
+
+            ui.separator();
+
             if ui.button("Exit").clicked() {
-                next_state.set(GameState::MainMenu);
+                request_confirm(&mut editor, EditorConfirm::Exit, &mut next_state);
             }
 
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -110,6 +160,24 @@ pub fn editor_ui_system(
                 {
                     editor.tool = EditorTool::Place;
                 }
+                if ui
+                    .selectable_label(editor.tool == EditorTool::Move, "Move")
+                    .clicked()
+                {
+                    editor.tool = EditorTool::Move;
+                }
+                if ui
+                    .selectable_label(editor.tool == EditorTool::Wire, "Wire")
+                    .clicked()
+                {
+                    editor.tool = EditorTool::Wire;
+                }
+                if ui
+                    .selectable_label(editor.tool == EditorTool::Obstacle, "Obstacle")
+                    .clicked()
+                {
+                    editor.tool = EditorTool::Obstacle;
+                }
                 if ui
                     .selectable_label(editor.tool == EditorTool::Delete, "Delete")
                     .clicked()
@@ -118,6 +186,17 @@ pub fn editor_ui_system(
                 }
             });
 
+            if editor.tool == EditorTool::Wire {
+                ui.label("Drag between pieces to wire them; drag a wire end to reroute it");
+            }
+            if editor.tool == EditorTool::Obstacle {
+                ui.label("Click a cell to block or unblock it");
+            }
+
+            if !editor.selection.is_empty() {
+                ui.label(format!("{} selected", editor.selection.len()));
+            }
+
             ui.add_space(10.0);
             ui.heading("Pieces");
             ui.separator();
@@ -154,6 +233,17 @@ pub fn editor_ui_system(
                     ui.label("Variable:");
                     ui.text_edit_singleline(&mut editor.variable_input);
                 }
+                if !piece_type.needs_formula() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Facing: {:?}", editor.orientation));
+                        if ui.small_button("⟲").on_hover_text("Shift+R").clicked() {
+                            editor.rotate_placement(false);
+                        }
+                        if ui.small_button("⟳").on_hover_text("R").clicked() {
+                            editor.rotate_placement(true);
+                        }
+                    });
+                }
             }
 
             ui.add_space(20.0);
@@ -161,20 +251,26 @@ pub fn editor_ui_system(
             ui.separator();
 
             ui.horizontal(|ui| {
-                ui.colored_label(egui::Color32::from_rgb(76, 204, 76), "■");
+                ui.colored_label(egui::Color32::from_rgb(76, 204, 76), "■ ▶");
                 ui.label("Assumption");
             });
             ui.horizontal(|ui| {
-                ui.colored_label(egui::Color32::from_rgb(204, 76, 76), "■");
+                ui.colored_label(egui::Color32::from_rgb(204, 76, 76), "■ ◎");
                 ui.label("Goal");
             });
             ui.horizontal(|ui| {
-                ui.colored_label(egui::Color32::from_rgb(128, 128, 204), "■");
+                ui.colored_label(egui::Color32::from_rgb(128, 128, 204), "■ ∧ ∨ →");
                 ui.label("Logic Gate");
             });
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::from_rgb(153, 153, 153), "■ ¬ ∀ ∃");
+                ui.label("Negation / Quantifier");
+            });
         });
 
-    // Right panel - level properties
+    // Right panel - level properties. Text fields edit the level in place;
+    // the changes are recorded for undo once the panel has been drawn.
+    let metadata_before = editor.metadata();
     egui::Panel::right("editor_properties")
         .min_size(250.0)
         .show(&mut viewport_ui, |ui| {
@@ -182,24 +278,15 @@ pub fn editor_ui_system(
             ui.separator();
 
             ui.label("Name:");
-            if ui.text_edit_singleline(&mut editor.level.name).changed() {
-                editor.dirty = true;
-            }
+            ui.text_edit_singleline(&mut editor.level.name);
 
             ui.add_space(5.0);
             ui.label("Description:");
-            if ui
-                .text_edit_multiline(&mut editor.level.description)
-                .changed()
-            {
-                editor.dirty = true;
-            }
+            ui.text_edit_multiline(&mut editor.level.description);
 
             ui.add_space(5.0);
             ui.label("Theorem (SMT-LIB2):");
-            if ui.text_edit_singleline(&mut editor.level.theorem).changed() {
-                editor.dirty = true;
-            }
+            ui.text_edit_singleline(&mut editor.level.theorem);
 
             ui.add_space(10.0);
             ui.separator();
@@ -242,7 +329,23 @@ pub fn editor_ui_system(
                 editor.level.goal_state = GoalCondition::ProveFormula {
                     formula: goal_formula,
                 };
-                editor.dirty = true;
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.heading("Player Inventory");
+            inventory_editor(ui, &mut editor.level.inventory);
+
+            if let [position] = editor.selection[..] {
+                ui.add_space(10.0);
+                ui.separator();
+                ui.heading("Selected Piece");
+                let mut flags = editor.level.initial_state.flags_at(position);
+                let movable = ui.checkbox(&mut flags.movable, "Player can move");
+                let deletable = ui.checkbox(&mut flags.deletable, "Player can delete");
+                if movable.changed() || deletable.changed() {
+                    editor.set_piece_flags(position, flags);
+                }
             }
 
             ui.add_space(10.0);
@@ -251,20 +354,20 @@ pub fn editor_ui_system(
 
             ui.label(format!(
                 "Total: {} pieces",
-                editor.level.initial_state.pieces.len()
+                editor.level.initial_state.pieces().len()
             ));
 
             let assumption_count = editor
                 .level
                 .initial_state
-                .pieces
+                .pieces()
                 .iter()
                 .filter(|p| matches!(p, LogicPiece::Assumption { .. }))
                 .count();
             let goal_count = editor
                 .level
                 .initial_state
-                .pieces
+                .pieces()
                 .iter()
                 .filter(|p| matches!(p, LogicPiece::Goal { .. }))
                 .count();
@@ -286,65 +389,287 @@ pub fn editor_ui_system(
                 }
             }
 
+            // Board diagnostics (also highlighted on the grid)
+            if let Some(diagnostics) = diagnostics.result() {
+                for error in &diagnostics.errors {
+                    ui.colored_label(egui::Color32::RED, format!("✗ {}", error));
+                }
+                for warning in &diagnostics.warnings {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning));
+                }
+            }
+
             // Pack selection
             if let Some(pack_manager) = &pack_manager {
                 ui.add_space(10.0);
                 ui.separator();
                 ui.heading("Save To Pack");
-
-                egui::ComboBox::from_label("Pack")
-                    .selected_text(
-                        editor
-                            .pack_id
-                            .as_ref()
-                            .map(|id| {
-                                pack_manager
-                                    .packs
-                                    .iter()
-                                    .find(|p| &p.id == id)
-                                    .map(|p| p.name.as_str())
-                                    .unwrap_or(id.as_str())
-                            })
-                            .unwrap_or("Select pack..."),
-                    )
-                    .show_ui(ui, |ui| {
-                        for pack in &pack_manager.packs {
-                            if ui
-                                .selectable_label(
-                                    editor.pack_id.as_ref() == Some(&pack.id),
-                                    &pack.name,
-                                )
-                                .clicked()
-                            {
-                                editor.pack_id = Some(pack.id.clone());
-                            }
-                        }
-                    });
+                pack_picker(ui, "editor_pack", &mut editor, pack_manager);
             }
         });
 
+    editor.commit_metadata(metadata_before);
+
     // Bottom panel - instructions
     egui::Panel::bottom("editor_help").show(&mut viewport_ui, |ui| {
         ui.horizontal_centered(|ui| {
             ui.label("Left-click: Place/Select");
             ui.separator();
+            ui.label("Drag: Box select / Move / Wire");
+            ui.separator();
             ui.label("Right-click: Delete");
             ui.separator();
-            ui.label("Middle-click: Pan");
+            ui.label("Ctrl+Z/Y: Undo/Redo");
             ui.separator();
-            ui.label("Scroll: Zoom");
+            ui.label("Ctrl+C/V: Copy/Paste");
+            ui.separator();
+            ui.label("Middle-drag: Pan");
+            ui.separator();
+            ui.label("Ctrl+Scroll: Zoom");
+            ui.separator();
+            ui.label("Home: Fit board");
+            ui.separator();
+            ui.label("R / Scroll: Rotate gate");
             ui.separator();
             ui.label("ESC: Exit");
         });
     });
 }
 
+/// Checkboxes and counts for the pieces a level gives the player. With
+/// nothing ticked, every kind is available without limit.
+fn inventory_editor(ui: &mut egui::Ui, inventory: &mut Inventory) {
+    if inventory.is_unrestricted() {
+        ui.label("All pieces, unlimited (tick a piece to restrict)");
+    }
+    for kind in PlaceablePiece::ALL {
+        let slot = inventory.slots.iter().find(|s| s.kind == kind).copied();
+        ui.horizontal(|ui| {
+            let mut offered = slot.is_some();
+            if ui.checkbox(&mut offered, kind.name()).changed() {
+                if offered {
+                    inventory.set_count(kind, Some(1));
+                } else {
+                    inventory.remove(kind);
+                }
+            }
+            if let Some(slot) = slot {
+                let mut unlimited = slot.count.is_none();
+                let mut count = slot.count.unwrap_or(1);
+                if ui.checkbox(&mut unlimited, "∞").changed() {
+                    inventory.set_count(kind, (!unlimited).then_some(count));
+                }
+                if !unlimited
+                    && ui
+                        .add(egui::DragValue::new(&mut count).range(0..=99))
+                        .changed()
+                {
+                    inventory.set_count(kind, Some(count));
+                }
+            }
+        });
+    }
+}
+
+/// Drop-down choosing the pack the level is saved to. `id_salt` keeps
+/// the panel's and the dialog's pickers apart.
+fn pack_picker(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    editor: &mut EditorState,
+    pack_manager: &LevelPackManager,
+) {
+    egui::ComboBox::new(id_salt, "Pack")
+        .selected_text(
+            editor
+                .pack_id
+                .as_ref()
+                .map(|id| {
+                    pack_manager
+                        .packs
+                        .iter()
+                        .find(|p| &p.id == id)
+                        .map(|p| p.name.as_str())
+                        .unwrap_or(id.as_str())
+                })
+                .unwrap_or("Select pack..."),
+        )
+        .show_ui(ui, |ui| {
+            // Built-in packs are read-only
+            let user_packs = pack_manager
+                .packs
+                .iter()
+                .filter(|pack| !pack_manager.is_builtin(&pack.id));
+            for pack in user_packs {
+                if ui
+                    .selectable_label(editor.pack_id.as_ref() == Some(&pack.id), &pack.name)
+                    .clicked()
+                {
+                    editor.pack_id = Some(pack.id.clone());
+                }
+            }
+            let new_pack = create_user_pack(NEW_PACK_NAME, "");
+            if !pack_manager.packs.iter().any(|p| p.id == new_pack.id)
+                && ui
+                    .selectable_label(
+                        editor.pack_id.as_ref() == Some(&new_pack.id),
+                        format!("{} (new)", NEW_PACK_NAME),
+                    )
+                    .clicked()
+            {
+                editor.pack_id = Some(new_pack.id);
+            }
+        });
+}
+
+/// Run `action` now if the level has no unsaved changes, otherwise open
+/// the save/discard dialog for it
+fn request_confirm(
+    editor: &mut EditorState,
+    action: EditorConfirm,
+    next_state: &mut NextState<GameState>,
+) {
+    if editor.dirty {
+        editor.pending_confirm = Some(action);
+    } else {
+        perform_confirmed(editor, action, next_state);
+    }
+}
+
+/// Carry out an exit or new-level action whose changes are saved or discarded
+fn perform_confirmed(
+    editor: &mut EditorState,
+    action: EditorConfirm,
+    next_state: &mut NextState<GameState>,
+) {
+    match action {
+        EditorConfirm::Exit => {
+            *editor = EditorState::default();
+            next_state.set(GameState::MainMenu);
+        }
+        EditorConfirm::New => *editor = EditorState::default(),
+    }
+}
+
+/// Modal asking whether to save, discard or keep editing unsaved changes
+fn show_confirm_dialog(
+    ctx: &egui::Context,
+    editor: &mut EditorState,
+    pack_manager: Option<&mut LevelPackManager>,
+    autosave: &mut EditorAutosave,
+    next_state: &mut NextState<GameState>,
+) {
+    let Some(action) = editor.pending_confirm else {
+        return;
+    };
+    let verb = match action {
+        EditorConfirm::Exit => "leaving the editor",
+        EditorConfirm::New => "starting a new level",
+    };
+
+    let mut choice = None;
+    egui::Modal::new(egui::Id::new("editor_unsaved_changes")).show(ctx, |ui| {
+        ui.heading("Unsaved changes");
+        ui.label(format!(
+            "\"{}\" has unsaved changes. Save them before {}?",
+            editor.level.name, verb
+        ));
+        if let Some(pack_manager) = pack_manager.as_deref() {
+            pack_picker(ui, "unsaved_changes_pack", editor, pack_manager);
+        }
+        ui.label(&editor.status_message);
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(editor.pack_id.is_some(), egui::Button::new("Save"))
+                .clicked()
+            {
+                choice = Some(true);
+            }
+            if ui.button("Discard").clicked() {
+                choice = Some(false);
+            }
+            if ui.button("Cancel").clicked() {
+                editor.pending_confirm = None;
+            }
+        });
+    });
+
+    match choice {
+        Some(true) => {
+            if let Err(errors) = editor.validate() {
+                editor.status_message = format!("Cannot save: {}", errors.join(", "));
+                return;
+            }
+            let pack_id = editor.pack_id.clone();
+            if save_to_pack(editor, pack_manager, pack_id.as_ref()) {
+                autosave.clear();
+                perform_confirmed(editor, action, next_state);
+            }
+        }
+        Some(false) => {
+            autosave.clear();
+            editor.dirty = false;
+            perform_confirmed(editor, action, next_state);
+        }
+        None => {}
+    }
+}
+
+/// Modal offering back a level autosaved by a previous session
+fn show_recovery_dialog(
+    ctx: &egui::Context,
+    editor: &mut EditorState,
+    autosave: &mut EditorAutosave,
+) {
+    let Some(recovery) = &editor.recovery else {
+        return;
+    };
+    let name = recovery.level.name.clone();
+
+    let mut restore = None;
+    egui::Modal::new(egui::Id::new("editor_recovery")).show(ctx, |ui| {
+        ui.heading("Recover unsaved level?");
+        ui.label(format!(
+            "The editor closed with unsaved changes to \"{}\".",
+            name
+        ));
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.button("Restore").clicked() {
+                restore = Some(true);
+            }
+            if ui.button("Discard").clicked() {
+                restore = Some(false);
+            }
+        });
+    });
+
+    match restore {
+        Some(true) => {
+            if let Some(recovery) = editor.recovery.take() {
+                *editor = recovery.restore();
+            }
+        }
+        Some(false) => {
+            editor.recovery = None;
+            autosave.clear();
+        }
+        None => {}
+    }
+}
+
 /// Handle editor input (piece placement, selection, deletion)
+#[allow(clippy::too_many_arguments)]
 pub fn editor_input_system(
     mut editor: ResMut<EditorState>,
     mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
+    egui_input: Option<Res<EguiWantsInput>>,
+    scroll: Res<AccumulatedMouseScroll>,
 ) {
     let Ok(window) = windows.single() else {
         return;
@@ -353,99 +678,215 @@ pub fn editor_input_system(
         return;
     };
 
-    // Get mouse position in world space
-    if let Some(screen_pos) = window.cursor_position() {
-        if let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, screen_pos) {
-            // Convert to grid coordinates
-            let grid_x = ((world_pos.x / 80.0).round() as i32 + (editor.grid_width as i32 / 2))
-                .clamp(0, editor.grid_width as i32 - 1) as u32;
-            let grid_y = ((world_pos.y / 80.0).round() as i32 + (editor.grid_height as i32 / 2))
-                .clamp(0, editor.grid_height as i32 - 1) as u32;
-
-            // Left click - place or select
-            if mouse.just_pressed(MouseButton::Left) {
-                match editor.tool {
-                    EditorTool::Place => {
-                        if let Some(piece_type) = editor.selected_piece {
-                            // Validate formula input for pieces that need it
-                            if piece_type.needs_formula() && editor.formula_input.trim().is_empty()
-                            {
-                                editor.status_message =
-                                    "Enter a formula before placing".to_string();
-                            } else {
-                                let piece = piece_type.to_logic_piece(
-                                    (grid_x, grid_y),
-                                    &editor.formula_input,
-                                    &editor.variable_input,
-                                );
-                                editor.add_piece(piece);
-                            }
-                        } else {
-                            editor.status_message = "Select a piece type first".to_string();
-                        }
-                    }
-                    EditorTool::Delete => {
-                        editor.remove_piece_at((grid_x, grid_y));
-                    }
-                    EditorTool::Select => {
-                        if let Some(piece) = editor.get_piece_at((grid_x, grid_y)) {
-                            editor.status_message = format!("Selected: {}", piece.label());
-                        }
-                    }
-                    _ => {}
+    // Dialogs are modal
+    if editor.pending_confirm.is_some() || editor.recovery.is_some() {
+        return;
+    }
+
+    let egui_wants_pointer = egui_input
+        .as_ref()
+        .is_some_and(|input| input.wants_any_pointer_input());
+    let egui_wants_keyboard = egui_input
+        .as_ref()
+        .is_some_and(|input| input.wants_any_keyboard_input());
+
+    // Get mouse position in world space, as the nearest grid cell
+    let hovered = window
+        .cursor_position()
+        .and_then(|screen_pos| {
+            camera
+                .viewport_to_world_2d(camera_transform, screen_pos)
+                .ok()
+        })
+        .map(|world_pos| editor.board_grid().clamp_to_cell(world_pos));
+
+    if !egui_wants_keyboard {
+        handle_editor_shortcuts(&mut editor, &keyboard, &actions, hovered);
+    }
+
+    // R or the mouse wheel turns the gate under the cursor, or else the
+    // way new gates will face
+    let meant_for_egui = (egui_wants_keyboard
+        && (actions.just_pressed(Action::TurnClockwise) || actions.just_pressed(Action::TurnBack)))
+        || (egui_wants_pointer && scroll.delta.y != 0.0);
+    let turn = rotation_input(&actions, &keyboard, &scroll).filter(|_| !meant_for_egui);
+    if let Some(clockwise) = turn {
+        let gate = hovered.filter(|&(x, y)| {
+            editor
+                .get_piece_at((x, y))
+                .is_some_and(|p| p.orientation().is_some())
+        });
+        match gate {
+            Some(cell) => {
+                let anchor = editor.get_piece_at(cell).map_or(cell, |p| p.position());
+                editor.rotate_piece_at(anchor, clockwise);
+            }
+            None if editor.tool == EditorTool::Place => editor.rotate_placement(clockwise),
+            None => {}
+        }
+    }
+
+    let Some((grid_x, grid_y)) = hovered else {
+        return;
+    };
+
+    // Track drags for box selection and group moves
+    if editor.drag_start.is_some() && editor.drag_current != Some((grid_x, grid_y)) {
+        editor.drag_current = Some((grid_x, grid_y));
+    }
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(start) = editor.drag_start.take() {
+            editor.drag_current = None;
+            let extend = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+            match editor.tool {
+                EditorTool::Select => editor.select_rect(start, (grid_x, grid_y), extend),
+                EditorTool::Move => {
+                    let delta = (
+                        grid_x as i32 - start.0 as i32,
+                        grid_y as i32 - start.1 as i32,
+                    );
+                    editor.move_selection(delta);
                 }
+                EditorTool::Wire if start != (grid_x, grid_y) => {
+                    editor.draw_wire(start, (grid_x, grid_y));
+                }
+                _ => {}
             }
+        }
+    }
+
+    if egui_wants_pointer {
+        return;
+    }
 
-            // Right click - delete
-            if mouse.just_pressed(MouseButton::Right) {
+    // Left click - place or select
+    if mouse.just_pressed(MouseButton::Left) {
+        match editor.tool {
+            EditorTool::Place => {
+                if let Some(piece_type) = editor.selected_piece {
+                    // Validate formula input for pieces that need it
+                    if piece_type.needs_formula() && editor.formula_input.trim().is_empty() {
+                        editor.status_message = "Enter a formula before placing".to_string();
+                    } else {
+                        let mut piece = piece_type.to_logic_piece(
+                            (grid_x, grid_y),
+                            &editor.formula_input,
+                            &editor.variable_input,
+                        );
+                        piece.set_orientation(editor.orientation);
+                        editor.add_piece(piece);
+                    }
+                } else {
+                    editor.status_message = "Select a piece type first".to_string();
+                }
+            }
+            EditorTool::Delete => {
                 editor.remove_piece_at((grid_x, grid_y));
             }
+            EditorTool::Obstacle => {
+                editor.toggle_obstacle((grid_x, grid_y));
+            }
+            EditorTool::Select => {
+                if let Some(piece) = editor.get_piece_at((grid_x, grid_y)) {
+                    editor.status_message = format!("Selected: {}", piece.label());
+                }
+                editor.drag_start = Some((grid_x, grid_y));
+                editor.drag_current = Some((grid_x, grid_y));
+            }
+            EditorTool::Wire => {
+                editor.drag_start = Some((grid_x, grid_y));
+                editor.drag_current = Some((grid_x, grid_y));
+            }
+            EditorTool::Move => {
+                // Grab the piece under the cursor along with the selection
+                if editor.is_position_occupied((grid_x, grid_y)) {
+                    if !editor.selection.contains(&(grid_x, grid_y)) {
+                        editor.selection = vec![(grid_x, grid_y)];
+                    }
+                    editor.drag_start = Some((grid_x, grid_y));
+                    editor.drag_current = Some((grid_x, grid_y));
+                }
+            }
         }
     }
+
+    // Right click - delete
+    if mouse.just_pressed(MouseButton::Right) {
+        editor.remove_piece_at((grid_x, grid_y));
+    }
 }
 
-/// Spawn editor grid visualization
-pub fn spawn_editor_grid(mut commands: Commands, editor: Res<EditorState>) {
-    let half_width = editor.grid_width as f32 / 2.0;
-    let half_height = editor.grid_height as f32 / 2.0;
+/// Keyboard shortcuts: undo/redo, clipboard, select all, delete or nudge
+/// the selection
+fn handle_editor_shortcuts(
+    editor: &mut EditorState,
+    keyboard: &ButtonInput<KeyCode>,
+    actions: &ActionState,
+    hovered: Option<(u32, u32)>,
+) {
+    if actions.just_pressed(Action::Delete) {
+        editor.delete_selection();
+    }
+    let step = actions.cursor_step();
+    if step != IVec2::ZERO {
+        editor.move_selection((step.x, step.y));
+    }
+    if actions.just_pressed(Action::Undo) {
+        editor.undo();
+    }
+    if actions.just_pressed(Action::Redo) {
+        editor.redo();
+    }
 
-    // Spawn grid cells
-    for x in 0..editor.grid_width {
-        for y in 0..editor.grid_height {
-            commands.spawn((
-                Sprite {
-                    color: if (x + y) % 2 == 0 {
-                        Color::srgba(0.15, 0.15, 0.2, 1.0)
-                    } else {
-                        Color::srgba(0.12, 0.12, 0.17, 1.0)
-                    },
-                    custom_size: Some(Vec2::new(78.0, 78.0)),
-                    ..default()
-                },
-                Transform::from_xyz(
-                    (x as f32 - half_width + 0.5) * 80.0,
-                    (y as f32 - half_height + 0.5) * 80.0,
-                    -1.0,
-                ),
-                EditorEntity,
-            ));
+    if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if keyboard.just_pressed(KeyCode::KeyZ) {
+        if shift {
+            editor.redo();
+        } else {
+            editor.undo();
+        }
+    }
+    if keyboard.just_pressed(KeyCode::KeyY) {
+        editor.redo();
+    }
+    if keyboard.just_pressed(KeyCode::KeyA) {
+        editor.select_all();
+    }
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        editor.copy_selection();
+    }
+    if keyboard.just_pressed(KeyCode::KeyX) {
+        editor.copy_selection();
+        editor.delete_selection();
+    }
+    if keyboard.just_pressed(KeyCode::KeyV) {
+        if let Some(cell) = hovered {
+            editor.paste_at(cell);
         }
     }
+}
+
+/// Spawn editor grid visualization
+pub fn spawn_editor_grid(mut commands: Commands, editor: Res<EditorState>) {
+    let grid = editor.board_grid();
+    commands.insert_resource(grid);
+    spawn_grid_cells(&mut commands, &grid);
 
     // Spawn existing pieces
-    for piece in &editor.level.initial_state.pieces {
-        let (x, y) = piece.position();
+    let to_world = |cell| grid.cell_to_world(cell);
+    for piece in editor.level.initial_state.pieces() {
+        let (size, transform) = piece.tile(to_world);
         commands.spawn((
             Sprite {
                 color: piece.color(),
-                custom_size: Some(Vec2::new(64.0, 64.0)),
+                custom_size: Some(size),
                 ..default()
             },
-            Transform::from_xyz(
-                (x as f32 - half_width + 0.5) * 80.0,
-                (y as f32 - half_height + 0.5) * 80.0,
-                0.0,
-            ),
+            transform,
             piece.clone(),
             EditorEntity,
         ));
@@ -456,44 +897,49 @@ pub fn spawn_editor_grid(mut commands: Commands, editor: Res<EditorState>) {
 pub fn update_editor_pieces(
     mut commands: Commands,
     editor: Res<EditorState>,
+    mut board_grid: ResMut<BoardGrid>,
     existing_pieces: Query<(Entity, &LogicPiece), With<EditorEntity>>,
+    grid_cells: Query<Entity, With<EditorGridCell>>,
 ) {
     if !editor.is_changed() {
         return;
     }
 
-    let half_width = editor.grid_width as f32 / 2.0;
-    let half_height = editor.grid_height as f32 / 2.0;
+    // A resized grid moves every cell, so redraw the whole board
+    let grid = editor.board_grid();
+    let resized = board_grid.set_if_neq(grid);
+    if resized {
+        for entity in grid_cells.iter() {
+            commands.entity(entity).despawn();
+        }
+        spawn_grid_cells(&mut commands, &grid);
+    }
 
-    // Find pieces to remove (in ECS but not in editor state)
+    // Find pieces to remove (in ECS but not in editor state). Pieces are
+    // compared whole, so a moved group never reuses another piece's sprite.
     for (entity, piece) in existing_pieces.iter() {
-        let pos = piece.position();
-        if !editor
-            .level
-            .initial_state
-            .pieces
-            .iter()
-            .any(|p| p.position() == pos)
-        {
+        if resized || !editor.level.initial_state.pieces().contains(piece) {
             commands.entity(entity).despawn();
         }
     }
 
     // Find pieces to add (in editor state but not in ECS)
-    for piece in &editor.level.initial_state.pieces {
-        let pos = piece.position();
-        if !existing_pieces.iter().any(|(_, p)| p.position() == pos) {
+    let to_world = |cell| grid.cell_to_world(cell);
+    for piece in editor.level.initial_state.pieces() {
+        if resized || !existing_pieces.iter().any(|(_, p)| p == piece) {
+            let (size, transform) = match piece {
+                LogicPiece::Wire { from, to } => {
+                    wiring::segment(to_world(*from), to_world(*to), 0.1)
+                }
+                _ => piece.tile(to_world),
+            };
             commands.spawn((
                 Sprite {
                     color: piece.color(),
-                    custom_size: Some(Vec2::new(64.0, 64.0)),
+                    custom_size: Some(size),
                     ..default()
                 },
-                Transform::from_xyz(
-                    (pos.0 as f32 - half_width + 0.5) * 80.0,
-                    (pos.1 as f32 - half_height + 0.5) * 80.0,
-                    0.0,
-                ),
+                transform,
                 piece.clone(),
                 EditorEntity,
             ));
@@ -501,6 +947,194 @@ pub fn update_editor_pieces(
     }
 }
 
+/// Draw blocked cells, and mark pre-placed pieces the player may move or
+/// delete
+pub fn update_editor_obstacles(
+    mut commands: Commands,
+    editor: Res<EditorState>,
+    markers: Query<Entity, With<EditorObstacle>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    for entity in markers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let grid = editor.board_grid();
+    let to_world = |cell, z| grid.cell_to_world(cell).extend(z);
+
+    let board = &editor.level.initial_state;
+    for &cell in &board.obstacles {
+        if !grid.contains(cell) {
+            continue;
+        }
+        commands.spawn((
+            Sprite {
+                color: Color::srgb(0.08, 0.08, 0.1),
+                custom_size: Some(Vec2::new(76.0, 76.0)),
+                ..default()
+            },
+            Transform::from_translation(to_world(cell, -0.05)),
+            EditorObstacle,
+            EditorEntity,
+        ));
+    }
+    for flagged in &board.flags {
+        if board
+            .piece_at(flagged.position.0, flagged.position.1)
+            .is_none()
+        {
+            continue;
+        }
+        commands.spawn((
+            Sprite {
+                color: Color::srgba(1.0, 1.0, 1.0, 0.8),
+                custom_size: Some(Vec2::new(12.0, 12.0)),
+                ..default()
+            },
+            Transform::from_translation(
+                to_world(flagged.position, 0.4) + Vec3::new(24.0, 24.0, 0.0),
+            ),
+            EditorObstacle,
+            EditorEntity,
+        ));
+    }
+}
+
+/// Highlight selected pieces and the box being dragged out
+pub fn update_editor_selection(
+    mut commands: Commands,
+    editor: Res<EditorState>,
+    markers: Query<Entity, With<EditorSelectionMarker>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    for entity in markers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let grid = editor.board_grid();
+    let to_world = |cell| grid.cell_to_world(cell);
+
+    for &cell in &editor.selection {
+        commands.spawn((
+            Sprite {
+                color: Color::srgba(0.3, 0.6, 1.0, 0.4),
+                custom_size: Some(Vec2::new(72.0, 72.0)),
+                ..default()
+            },
+            Transform::from_translation(to_world(cell).extend(0.6)),
+            EditorSelectionMarker,
+            EditorEntity,
+        ));
+    }
+
+    // Wire preview, from the drag start to the cell under the cursor
+    if editor.tool == EditorTool::Wire {
+        if let (Some(start), Some(current)) = (editor.drag_start, editor.drag_current) {
+            if start != current {
+                let (size, transform) = wiring::segment(to_world(start), to_world(current), 0.7);
+                commands.spawn((
+                    Sprite {
+                        color: Color::srgba(0.9, 0.9, 0.9, 0.5),
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    transform,
+                    EditorSelectionMarker,
+                    EditorEntity,
+                ));
+            }
+        }
+    }
+
+    // Box-select preview
+    if editor.tool == EditorTool::Select {
+        if let (Some(start), Some(current)) = (editor.drag_start, editor.drag_current) {
+            let min = to_world((start.0.min(current.0), start.1.min(current.1)));
+            let max = to_world((start.0.max(current.0), start.1.max(current.1)));
+            commands.spawn((
+                Sprite {
+                    color: Color::srgba(0.3, 0.6, 1.0, 0.15),
+                    custom_size: Some(max - min + Vec2::splat(CELL_SIZE)),
+                    ..default()
+                },
+                Transform::from_translation(((min + max) / 2.0).extend(0.7)),
+                EditorSelectionMarker,
+                EditorEntity,
+            ));
+        }
+    }
+}
+
+/// Revalidate the level being edited when it has changed
+pub fn refresh_editor_diagnostics(
+    editor: Res<EditorState>,
+    mut diagnostics: ResMut<EditorDiagnostics>,
+) {
+    // Only take the result mutably when it is stale, so the overlay is
+    // redrawn just when the diagnostics change
+    if editor.is_changed() && !diagnostics.is_current(&editor.level) {
+        diagnostics.refresh(&editor.level);
+    }
+}
+
+/// Highlight cells that have validation errors (red) or warnings (yellow)
+pub fn update_editor_diagnostics(
+    mut commands: Commands,
+    editor: Res<EditorState>,
+    diagnostics: Res<EditorDiagnostics>,
+    markers: Query<Entity, With<EditorDiagnostic>>,
+) {
+    if !diagnostics.is_changed() {
+        return;
+    }
+
+    for entity in markers.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let grid = editor.board_grid();
+    let Some(diagnostics) = diagnostics.result() else {
+        return;
+    };
+
+    let error_cells = diagnostics.errors.iter().filter_map(|e| e.position());
+    let warning_cells = diagnostics.warnings.iter().filter_map(|w| w.position());
+    let cells = error_cells
+        .map(|pos| (pos, Color::srgba(1.0, 0.2, 0.2, 0.35)))
+        .chain(warning_cells.map(|pos| (pos, Color::srgba(1.0, 0.85, 0.2, 0.3))));
+
+    for (cell, color) in cells {
+        if !grid.contains(cell) {
+            continue;
+        }
+        commands.spawn((
+            Sprite {
+                color,
+                custom_size: Some(grid.tile_size()),
+                ..default()
+            },
+            Transform::from_translation(grid.cell_to_world(cell).extend(0.5)),
+            EditorDiagnostic,
+            EditorEntity,
+        ));
+    }
+}
+
+/// Spawn the background cells of the editor grid.
+fn spawn_grid_cells(commands: &mut Commands, grid: &BoardGrid) {
+    for x in 0..grid.width {
+        for y in 0..grid.height {
+            commands.spawn((grid.cell_tile((x, y)), EditorGridCell, EditorEntity));
+        }
+    }
+}
+
 /// Cleanup editor entities
 pub fn cleanup_editor(mut commands: Commands, entities: Query<Entity, With<EditorEntity>>) {
     for entity in entities.iter() {
@@ -511,69 +1145,152 @@ pub fn cleanup_editor(mut commands: Commands, entities: Query<Entity, With<Edito
 /// Handle test level event
 pub fn handle_test_level(
     mut events: MessageReader<TestLevelEvent>,
-    editor: Res<EditorState>,
+    mut editor: ResMut<EditorState>,
+    mut test_play: ResMut<TestPlaySession>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     for _ in events.read() {
         match editor.validate() {
             Ok(_) => {
-                // TODO: Store the level for testing
+                // `load_level` picks the level up from the editor state
                 info!("Testing level: {}", editor.level.name);
+                *test_play = TestPlaySession {
+                    active: true,
+                    solution: None,
+                };
                 next_state.set(GameState::Playing);
             }
             Err(errors) => {
                 warn!("Cannot test level: {:?}", errors);
+                editor.status_message = format!("Cannot test: {}", errors.join(", "));
             }
         }
     }
 }
 
+/// End any test play when returning to the editor
+pub fn end_test_play(mut test_play: ResMut<TestPlaySession>) {
+    test_play.active = false;
+}
+
 /// Handle save level event
 pub fn handle_save_level(
     mut events: MessageReader<SaveLevelEvent>,
     mut editor: ResMut<EditorState>,
     mut pack_manager: Option<ResMut<LevelPackManager>>,
+    mut autosave: ResMut<EditorAutosave>,
 ) {
     for event in events.read() {
-        let level = editor.build_level();
-
-        if let Some(pack_manager) = &mut pack_manager {
-            if let Some(pack_id) = &event.to_pack_id {
-                // Find the pack index first
-                let pack_idx = pack_manager.packs.iter().position(|p| &p.id == pack_id);
-
-                if let Some(idx) = pack_idx {
-                    // Update existing or add new level
-                    let pack = &mut pack_manager.packs[idx];
-                    if let Some(existing) = pack.levels.iter_mut().find(|l| l.id == level.id) {
-                        *existing = level.clone();
-                        editor.status_message = format!("Updated level in {}", pack.name);
-                    } else {
-                        pack.levels.push(level.clone());
-                        editor.status_message = format!("Added level to {}", pack.name);
-                    }
+        if save_to_pack(
+            &mut editor,
+            pack_manager.as_deref_mut(),
+            event.to_pack_id.as_ref(),
+        ) {
+            autosave.clear();
+        }
+    }
+}
 
-                    // Clone pack for saving
-                    let pack_to_save = pack_manager.packs[idx].clone();
+/// Write the edited level into a pack and save the pack to disk.
+/// Returns true if the level was saved.
+fn save_to_pack(
+    editor: &mut EditorState,
+    pack_manager: Option<&mut LevelPackManager>,
+    pack_id: Option<&String>,
+) -> bool {
+    let level = editor.build_level();
 
-                    // Save pack to disk
-                    match pack_manager.save_pack(&pack_to_save) {
-                        Ok(path) => {
-                            info!("Saved pack to {:?}", path);
-                            editor.dirty = false;
-                        }
-                        Err(e) => {
-                            editor.status_message = format!("Save failed: {}", e);
-                        }
-                    }
-                } else {
-                    editor.status_message = format!("Pack '{}' not found", pack_id);
-                }
-            } else {
-                editor.status_message = "Select a pack to save to".to_string();
-            }
-        } else {
-            editor.status_message = "Level pack manager not available".to_string();
+    let Some(pack_manager) = pack_manager else {
+        editor.status_message = "Level pack manager not available".to_string();
+        return false;
+    };
+    let Some(pack_id) = pack_id else {
+        editor.status_message = "Select a pack to save to".to_string();
+        return false;
+    };
+
+    if pack_manager.is_builtin(pack_id) {
+        editor.status_message = format!("'{}' is a built-in pack; pick one of yours", pack_id);
+        return false;
+    }
+
+    // Find the pack index first, creating the player's first pack if that
+    // is the one picked
+    let new_pack = create_user_pack(NEW_PACK_NAME, "");
+    let idx = match pack_manager.packs.iter().position(|p| &p.id == pack_id) {
+        Some(idx) => idx,
+        None if *pack_id == new_pack.id => {
+            pack_manager.packs.push(new_pack);
+            pack_manager.packs.len() - 1
+        }
+        None => {
+            editor.status_message = format!("Pack '{}' not found", pack_id);
+            return false;
+        }
+    };
+
+    // Update existing or add new level
+    let pack = &mut pack_manager.packs[idx];
+    if let Some(existing) = pack.levels.iter_mut().find(|l| l.id == level.id) {
+        *existing = level;
+        editor.status_message = format!("Updated level in {}", pack.name);
+    } else {
+        pack.levels.push(level);
+        editor.status_message = format!("Added level to {}", pack.name);
+    }
+
+    // Clone pack for saving
+    let pack_to_save = pack_manager.packs[idx].clone();
+
+    // Save pack to disk
+    match pack_manager.save_pack(&pack_to_save) {
+        Ok(path) => {
+            info!("Saved pack to {:?}", path);
+            editor.dirty = false;
+            true
+        }
+        Err(e) => {
+            editor.status_message = format!("Save failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Periodically autosave the edited level to the recovery file
+pub fn autosave_editor(
+    time: Res<Time>,
+    editor: Res<EditorState>,
+    mut autosave: ResMut<EditorAutosave>,
+) {
+    if !autosave.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if let Err(e) = autosave.write(&editor) {
+        warn!("Editor autosave failed: {}", e);
+    }
+}
+
+/// Offer back a level autosaved by a previous session, the first time the
+/// editor opens
+pub fn offer_editor_recovery(
+    mut editor: ResMut<EditorState>,
+    mut autosave: ResMut<EditorAutosave>,
+) {
+    if let Some(recovery) = autosave.take_recovery() {
+        editor.recovery = Some(recovery);
+    }
+}
+
+/// Autosave immediately when leaving for a test play; the level is still
+/// being worked on
+pub fn autosave_on_exit(
+    editor: Res<EditorState>,
+    test_play: Res<TestPlaySession>,
+    mut autosave: ResMut<EditorAutosave>,
+) {
+    if test_play.active {
+        if let Err(e) = autosave.write(&editor) {
+            warn!("Editor autosave failed: {}", e);
         }
     }
 }
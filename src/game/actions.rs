@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Input actions, so play mode and the editor read "place a piece" rather
+//! than "Enter was pressed".
+//!
+//! [`InputMap`] binds keys and gamepad buttons to [`Action`]s and can be
+//! rebound at runtime. Every frame [`update_action_state`] reads the bound
+//! inputs into [`ActionState`], which the game systems query. Chords such
+//! as Ctrl+Z stay with the systems that use them.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Something the player can ask for, whatever input it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    /// Place the chosen piece, or pick up or put down the piece under the
+    /// cursor
+    Place,
+    /// Remove the selected piece
+    Delete,
+    /// Choose the palette slot with this index
+    PickPiece(u8),
+    NextPiece,
+    PreviousPiece,
+    TurnClockwise,
+    TurnBack,
+    Undo,
+    Redo,
+    /// Check the solution
+    Verify,
+    /// Leave the current screen
+    Back,
+}
+
+impl Action {
+    /// Every action, in the order the settings screen lists them.
+    pub fn all() -> impl Iterator<Item = Action> {
+        use Action::*;
+        [
+            CursorUp,
+            CursorDown,
+            CursorLeft,
+            CursorRight,
+            Place,
+            Delete,
+            NextPiece,
+            PreviousPiece,
+            TurnClockwise,
+            TurnBack,
+            Undo,
+            Redo,
+            Verify,
+            Back,
+        ]
+        .into_iter()
+        .chain((0..9).map(PickPiece))
+    }
+
+    /// Name shown when rebinding.
+    pub fn label(&self) -> String {
+        match self {
+            Action::CursorUp => "Cursor up".to_string(),
+            Action::CursorDown => "Cursor down".to_string(),
+            Action::CursorLeft => "Cursor left".to_string(),
+            Action::CursorRight => "Cursor right".to_string(),
+            Action::Place => "Place / pick up".to_string(),
+            Action::Delete => "Delete".to_string(),
+            Action::PickPiece(slot) => format!("Piece {}", slot + 1),
+            Action::NextPiece => "Next piece".to_string(),
+            Action::PreviousPiece => "Previous piece".to_string(),
+            Action::TurnClockwise => "Turn clockwise".to_string(),
+            Action::TurnBack => "Turn back".to_string(),
+            Action::Undo => "Undo".to_string(),
+            Action::Redo => "Redo".to_string(),
+            Action::Verify => "Verify".to_string(),
+            Action::Back => "Back".to_string(),
+        }
+    }
+}
+
+/// A single input that can trigger an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    /// Name shown when rebinding.
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+/// Which inputs trigger which actions. An input may be bound to several
+/// actions and an action to several inputs.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    pub bindings: Vec<(Binding, Action)>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Action::*;
+        use Binding::{Gamepad as Pad, Key};
+
+        let mut bindings = vec![
+            (Key(KeyCode::ArrowUp), CursorUp),
+            (Key(KeyCode::ArrowDown), CursorDown),
+            (Key(KeyCode::ArrowLeft), CursorLeft),
+            (Key(KeyCode::ArrowRight), CursorRight),
+            (Key(KeyCode::Enter), Place),
+            (Key(KeyCode::NumpadEnter), Place),
+            (Key(KeyCode::Delete), Delete),
+            (Key(KeyCode::Backspace), Delete),
+            (Key(KeyCode::BracketRight), NextPiece),
+            (Key(KeyCode::BracketLeft), PreviousPiece),
+            (Key(KeyCode::KeyR), TurnClockwise),
+            (Key(KeyCode::Space), Verify),
+            (Key(KeyCode::Escape), Back),
+            (Pad(GamepadButton::DPadUp), CursorUp),
+            (Pad(GamepadButton::DPadDown), CursorDown),
+            (Pad(GamepadButton::DPadLeft), CursorLeft),
+            (Pad(GamepadButton::DPadRight), CursorRight),
+            (Pad(GamepadButton::South), Place),
+            (Pad(GamepadButton::North), Delete),
+            (Pad(GamepadButton::West), NextPiece),
+            (Pad(GamepadButton::RightTrigger), TurnClockwise),
+            (Pad(GamepadButton::LeftTrigger), TurnBack),
+            (Pad(GamepadButton::LeftTrigger2), Undo),
+            (Pad(GamepadButton::RightTrigger2), Redo),
+            (Pad(GamepadButton::Start), Verify),
+            (Pad(GamepadButton::Select), Back),
+        ];
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        bindings.extend(
+            (0..)
+                .zip(digits)
+                .map(|(slot, key)| (Key(key), PickPiece(slot))),
+        );
+        Self { bindings }
+    }
+}
+
+impl InputMap {
+    /// Inputs bound to `action`.
+    pub fn bindings_for(&self, action: Action) -> impl Iterator<Item = Binding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, a)| *a == action)
+            .map(|(binding, _)| *binding)
+    }
+
+    /// Bind `binding` to `action`, replacing whatever else it triggered.
+    pub fn bind(&mut self, binding: Binding, action: Action) {
+        self.bindings.retain(|(b, _)| *b != binding);
+        self.bindings.push((binding, action));
+    }
+
+    /// Remove every binding of `action`.
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.retain(|(_, a)| *a != action);
+    }
+}
+
+/// The actions being held, and those started, this frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    /// Whether any input bound to `action` is held.
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether an input bound to `action` was pressed this frame.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Cells to move the cursor this frame, y up.
+    pub fn cursor_step(&self) -> IVec2 {
+        [
+            (Action::CursorUp, IVec2::Y),
+            (Action::CursorDown, IVec2::NEG_Y),
+            (Action::CursorLeft, IVec2::NEG_X),
+            (Action::CursorRight, IVec2::X),
+        ]
+        .into_iter()
+        .filter(|(action, _)| self.just_pressed(*action))
+        .map(|(_, step)| step)
+        .sum()
+    }
+
+    /// Read the actions from `map`, given the `(pressed, just_pressed)`
+    /// state of each binding.
+    pub fn update(&mut self, map: &InputMap, read: impl Fn(Binding) -> (bool, bool)) {
+        self.pressed.clear();
+        self.just_pressed.clear();
+        for &(binding, action) in &map.bindings {
+            let (pressed, just_pressed) = read(binding);
+            if pressed {
+                self.pressed.insert(action);
+            }
+            if just_pressed {
+                self.just_pressed.insert(action);
+            }
+        }
+    }
+}
+
+/// Read this frame's keyboard and gamepad input into the action state.
+pub fn update_action_state(
+    map: Res<InputMap>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+) {
+    state.update(&map, |binding| match binding {
+        Binding::Key(key) => (keyboard.pressed(key), keyboard.just_pressed(key)),
+        Binding::Gamepad(button) => gamepads.iter().fold((false, false), |(held, new), pad| {
+            (held || pad.pressed(button), new || pad.just_pressed(button))
+        }),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_keys(keys: &ButtonInput<KeyCode>) -> impl Fn(Binding) -> (bool, bool) + '_ {
+        |binding| match binding {
+            Binding::Key(key) => (keys.pressed(key), keys.just_pressed(key)),
+            Binding::Gamepad(_) => (false, false),
+        }
+    }
+
+    #[test]
+    fn test_keys_trigger_bound_actions() {
+        let map = InputMap::default();
+        let mut keys = ButtonInput::default();
+        keys.press(KeyCode::Digit3);
+        keys.press(KeyCode::ArrowUp);
+        keys.press(KeyCode::ArrowLeft);
+
+        let mut state = ActionState::default();
+        state.update(&map, read_keys(&keys));
+        assert!(state.just_pressed(Action::PickPiece(2)));
+        assert_eq!(state.cursor_step(), IVec2::new(-1, 1));
+
+        // Held keys stay pressed but don't fire again
+        keys.clear();
+        state.update(&map, read_keys(&keys));
+        assert!(state.pressed(Action::CursorUp));
+        assert!(!state.just_pressed(Action::CursorUp));
+        assert_eq!(state.cursor_step(), IVec2::ZERO);
+    }
+
+    #[test]
+    fn test_rebinding() {
+        let mut map = InputMap::default();
+        map.bind(Binding::Key(KeyCode::Enter), Action::Verify);
+        assert!(map
+            .bindings_for(Action::Verify)
+            .any(|b| b == Binding::Key(KeyCode::Enter)));
+        assert!(!map
+            .bindings_for(Action::Place)
+            .any(|b| b == Binding::Key(KeyCode::Enter)));
+        assert!(map
+            .bindings_for(Action::Place)
+            .any(|b| b == Binding::Gamepad(GamepadButton::South)));
+
+        map.clear(Action::Back);
+        assert_eq!(map.bindings_for(Action::Back).count(), 0);
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::from_str::<InputMap>(&json).unwrap(), map);
+    }
+}
@@ -2,9 +2,12 @@
 //! Board management for the logic puzzle game.
 //!
 //! Provides operations for creating and manipulating the puzzle board,
-//! including piece placement, removal, and spatial queries.
+//! including piece placement, removal, and spatial queries. Queries go
+//! through the board's [`SpatialIndex`], which the mutating methods here
+//! keep in step with the piece list.
 
-use super::{BoardState, LogicPiece};
+use super::spatial::SpatialIndex;
+use super::{BoardState, Direction, FlaggedPiece, LogicPiece, PieceFlags};
 
 impl BoardState {
     /// Create a new empty board with the specified dimensions.
@@ -13,6 +16,9 @@ pub fn new(width: u32, height: u32) -> Self {
             width,
             height,
             pieces: Vec::new(),
+            obstacles: Vec::new(),
+            flags: Vec::new(),
+            index: Default::default(),
         }
     }
 
@@ -22,6 +28,9 @@ pub fn with_pieces(width: u32, height: u32, pieces: Vec<LogicPiece>) -> Self {
             width,
             height,
             pieces,
+            obstacles: Vec::new(),
+            flags: Vec::new(),
+            index: Default::default(),
         }
     }
 
@@ -30,19 +39,101 @@ pub fn in_bounds(&self, x: u32, y: u32) -> bool {
         x < self.width && y < self.height
     }
 
-    /// Check if a position is occupied by any piece.
+    /// The cell index over this board's pieces.
+    pub fn spatial(&self) -> &SpatialIndex {
+        self.index.get(&self.pieces)
+    }
+
+    /// The pieces on the board.
+    pub fn pieces(&self) -> &[LogicPiece] {
+        &self.pieces
+    }
+
+    /// Mutable access to the piece list. The spatial index is dropped and
+    /// rebuilt on the next query, so prefer the placement methods for
+    /// single-piece changes.
+    pub fn pieces_mut(&mut self) -> &mut Vec<LogicPiece> {
+        self.index.invalidate();
+        &mut self.pieces
+    }
+
+    /// Check if a position is covered by any piece's footprint.
     pub fn is_occupied(&self, x: u32, y: u32) -> bool {
-        self.pieces.iter().any(|p| p.position() == (x, y))
+        !self.spatial().at((x, y)).is_empty()
+    }
+
+    /// Check if a cell is blocked terrain.
+    pub fn is_obstacle(&self, x: u32, y: u32) -> bool {
+        self.obstacles.contains(&(x, y))
+    }
+
+    /// Check if a cell can't take a piece: an obstacle or already occupied.
+    pub fn is_blocked(&self, x: u32, y: u32) -> bool {
+        self.is_obstacle(x, y) || self.is_occupied(x, y)
+    }
+
+    /// Mark or clear a cell as blocked terrain.
+    pub fn set_obstacle(&mut self, cell: (u32, u32), blocked: bool) {
+        self.obstacles.retain(|&c| c != cell);
+        if blocked {
+            self.obstacles.push(cell);
+        }
+    }
+
+    /// Flags of the pre-placed piece at a position (locked unless listed).
+    pub fn flags_at(&self, position: (u32, u32)) -> PieceFlags {
+        self.flags
+            .iter()
+            .find(|f| f.position == position)
+            .map(|f| f.flags)
+            .unwrap_or(PieceFlags::LOCKED)
+    }
+
+    /// Set the flags of the pre-placed piece at a position.
+    pub fn set_flags(&mut self, position: (u32, u32), flags: PieceFlags) {
+        self.flags.retain(|f| f.position != position);
+        if !flags.is_locked() {
+            self.flags.push(FlaggedPiece { position, flags });
+        }
+    }
+
+    /// Index of the piece anchored at `cell`, or failing that the first
+    /// piece covering it.
+    fn index_at(&self, cell: (u32, u32)) -> Option<usize> {
+        let covering = self.spatial().at(cell);
+        covering
+            .iter()
+            .find(|&&i| self.pieces[i].position() == cell)
+            .or_else(|| covering.first())
+            .copied()
     }
 
     /// Get the piece at a specific position, if any.
     pub fn piece_at(&self, x: u32, y: u32) -> Option<&LogicPiece> {
-        self.pieces.iter().find(|p| p.position() == (x, y))
+        Some(&self.pieces[self.index_at((x, y))?])
     }
 
-    /// Get a mutable reference to the piece at a specific position.
+    /// Get a mutable reference to the piece at a specific position. Use
+    /// `move_piece` to change its position, so the index follows it.
     pub fn piece_at_mut(&mut self, x: u32, y: u32) -> Option<&mut LogicPiece> {
-        self.pieces.iter_mut().find(|p| p.position() == (x, y))
+        let index = self.index_at((x, y))?;
+        Some(&mut self.pieces[index])
+    }
+
+    /// Whether every cell of `piece`'s footprint is in bounds, clear of
+    /// obstacles, and free of other pieces. Pieces anchored at a position
+    /// in `ignore` don't count, so a piece can be checked against the
+    /// cells it is moving out of.
+    pub fn fits(&self, piece: &LogicPiece, ignore: &[(u32, u32)]) -> bool {
+        piece.footprint().into_iter().all(|(x, y)| {
+            self.in_bounds(x, y)
+                && !self.is_obstacle(x, y)
+                && self
+                    .spatial()
+                    .at((x, y))
+                    .iter()
+                    .all(|&i| ignore.contains(&self.pieces[i].position()))
+        })
     }
 
     /// Add a piece to the board if the position is valid and unoccupied.
@@ -52,60 +143,134 @@ pub fn piece_at_mut(&mut self, x: u32, y: u32) -> Option<&mut LogicPiece> {
     // placement returning `true` preserves "all pieces in bounds AND no
     // two pieces share a primary position". Machine-checked in Idris2 as
     // `placePreservesWF` (no postulate, no `believe_me`); the Rust guard
-    // here (`in_bounds && !is_occupied`) is exactly the precondition the
-    // theorem inverts.
+    // here (`in_bounds && !is_occupied`, now checked for every footprint
+    // cell, the anchor included) is exactly the precondition the theorem
+    // inverts.
     // See: src/abi/ProofOfWork/ABI/Invariants.idr I3 (placePreservesWF)
     pub fn place_piece(&mut self, piece: LogicPiece) -> bool {
-        let (x, y) = piece.position();
-
-        if !self.in_bounds(x, y) {
+        if !self.fits(&piece, &[]) {
             return false;
         }
 
-        if self.is_occupied(x, y) {
-            return false;
+        let next = self.pieces.len();
+        if let Some(index) = self.index.get_mut() {
+            index.insert(next, &piece);
         }
-
         self.pieces.push(piece);
         true
     }
 
-    /// Remove a piece at the specified position.
+    /// Remove a piece at the specified position, along with its flags.
     /// Returns the removed piece if found.
     pub fn remove_piece(&mut self, x: u32, y: u32) -> Option<LogicPiece> {
-        let index = self.pieces.iter().position(|p| p.position() == (x, y))?;
-        Some(self.pieces.remove(index))
+        let index = self.index_at((x, y))?;
+        let removed = self.pieces.remove(index);
+        if let Some(spatial) = self.index.get_mut() {
+            spatial.remove(index, &removed.footprint());
+        }
+        self.set_flags(removed.position(), PieceFlags::LOCKED);
+        Some(removed)
     }
 
-    /// Move a piece from one position to another.
+    /// Move a piece from one position to another, taking its flags along.
     /// Returns true if the move was successful.
     pub fn move_piece(&mut self, from: (u32, u32), to: (u32, u32)) -> bool {
-        if !self.in_bounds(to.0, to.1) {
+        let Some(index) = self
+            .index_at(from)
+            .filter(|&i| self.pieces[i].position() == from)
+        else {
             return false;
+        };
+        let mut moved = self.pieces[index].clone();
+        moved.set_position(to);
+        if !self.fits(&moved, &[from]) {
+            return false;
+        }
+
+        let before = self.pieces[index].footprint();
+        let after = moved.footprint();
+        self.pieces[index] = moved;
+        if let Some(spatial) = self.index.get_mut() {
+            spatial.relocate(index, &before, &after);
         }
+        let flags = self.flags_at(from);
+        self.set_flags(from, PieceFlags::LOCKED);
+        self.set_flags(to, flags);
+        true
+    }
 
-        if self.is_occupied(to.0, to.1) {
+    /// Turn the gate anchored at `at` to face `facing`, if its turned
+    /// footprint still fits. Returns true if the gate was turned.
+    pub fn orient_piece(&mut self, at: (u32, u32), facing: Direction) -> bool {
+        let Some(index) = self
+            .index_at(at)
+            .filter(|&i| self.pieces[i].position() == at)
+        else {
             return false;
+        };
+        let mut turned = self.pieces[index].clone();
+        if !turned.set_orientation(facing) || !self.fits(&turned, &[at]) {
+            return false;
+        }
+
+        let before = self.pieces[index].footprint();
+        let after = turned.footprint();
+        self.pieces[index] = turned;
+        if let Some(spatial) = self.index.get_mut() {
+            spatial.relocate(index, &before, &after);
         }
+        true
+    }
 
-        if let Some(piece) = self.piece_at_mut(from.0, from.1) {
-            piece.set_position(to);
-            true
-        } else {
-            false
+    /// Make the gates of a board saved before pieces had footprints fit.
+    /// Those gates covered one cell, but AND, OR and IMPLIES now cover two,
+    /// so one on the top row or just below another piece is moved down a
+    /// row, or failing that turned to lie along its row. Pieces that fit
+    /// stay put, and a gate with nowhere to go is left for validation to
+    /// report.
+    pub fn fit_pre_ports_gates(&mut self) {
+        for i in 0..self.pieces.len() {
+            let piece = &self.pieces[i];
+            let at = piece.position();
+            if self.fits(piece, &[at]) {
+                continue;
+            }
+            let (x, y) = at;
+            let lowered = y.checked_sub(1).map(|below| {
+                let mut lowered = piece.clone();
+                lowered.set_position((x, below));
+                lowered
+            });
+            let turned = [Some(x), x.checked_sub(1)]
+                .into_iter()
+                .flatten()
+                .map(|left| {
+                    let mut turned = piece.clone();
+                    turned.set_position((left, y));
+                    turned.set_orientation(Direction::South);
+                    turned
+                });
+            let Some(fitted) = lowered
+                .into_iter()
+                .chain(turned)
+                .find(|candidate| self.fits(candidate, &[at]))
+            else {
+                continue;
+            };
+
+            let flags = self.flags_at(at);
+            self.set_flags(at, PieceFlags::LOCKED);
+            self.set_flags(fitted.position(), flags);
+            self.pieces_mut()[i] = fitted;
         }
     }
 
     /// Get all pieces within a given radius of a position.
     pub fn pieces_near(&self, x: u32, y: u32, radius: u32) -> Vec<&LogicPiece> {
-        self.pieces
-            .iter()
-            .filter(|p| {
-                let (px, py) = p.position();
-                let dx = (px as i32 - x as i32).unsigned_abs();
-                let dy = (py as i32 - y as i32).unsigned_abs();
-                dx <= radius && dy <= radius
-            })
+        self.spatial()
+            .within(x, y, radius)
+            .into_iter()
+            .map(|i| &self.pieces[i])
             .collect()
     }
 
@@ -151,7 +316,8 @@ pub fn wires(&self) -> Vec<&LogicPiece> {
 
     /// Clear all pieces from the board.
     pub fn clear(&mut self) {
-        self.pieces.clear();
+        self.pieces_mut().clear();
+        self.flags.clear();
     }
 
     /// Get the total number of pieces on the board.
@@ -184,21 +350,30 @@ fn test_bounds_check() {
     #[test]
     fn test_place_piece() {
         let mut board = BoardState::new(10, 10);
-        let piece = LogicPiece::AndIntro { position: (5, 5) };
+        let piece = LogicPiece::AndIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        };
 
         assert!(board.place_piece(piece.clone()));
         assert!(board.is_occupied(5, 5));
         assert!(!board.is_occupied(6, 6));
 
         // Can't place another piece at the same position
-        let piece2 = LogicPiece::OrIntro { position: (5, 5) };
+        let piece2 = LogicPiece::OrIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        };
         assert!(!board.place_piece(piece2));
     }
 
     #[test]
     fn test_remove_piece() {
         let mut board = BoardState::new(10, 10);
-        let piece = LogicPiece::AndIntro { position: (5, 5) };
+        let piece = LogicPiece::AndIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        };
         board.place_piece(piece);
 
         assert!(board.is_occupied(5, 5));
@@ -210,7 +385,10 @@ fn test_remove_piece() {
     #[test]
     fn test_move_piece() {
         let mut board = BoardState::new(10, 10);
-        let piece = LogicPiece::AndIntro { position: (5, 5) };
+        let piece = LogicPiece::AndIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        };
         board.place_piece(piece);
 
         assert!(board.move_piece((5, 5), (7, 7)));
@@ -221,11 +399,162 @@ fn test_move_piece() {
     #[test]
     fn test_pieces_near() {
         let mut board = BoardState::new(10, 10);
-        board.place_piece(LogicPiece::AndIntro { position: (5, 5) });
-        board.place_piece(LogicPiece::OrIntro { position: (6, 5) });
-        board.place_piece(LogicPiece::NotIntro { position: (9, 9) });
+        board.place_piece(LogicPiece::AndIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        });
+        board.place_piece(LogicPiece::OrIntro {
+            position: (6, 5),
+            orientation: Direction::East,
+        });
+        board.place_piece(LogicPiece::NotIntro {
+            position: (9, 9),
+            orientation: Direction::East,
+        });
 
         let near = board.pieces_near(5, 5, 2);
         assert_eq!(near.len(), 2);
     }
+
+    #[test]
+    fn test_obstacles_block_placement_and_moves() {
+        let mut board = BoardState::new(10, 10);
+        board.set_obstacle((4, 4), true);
+
+        assert!(!board.place_piece(LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East
+        }));
+        assert!(board.place_piece(LogicPiece::AndIntro {
+            position: (3, 4),
+            orientation: Direction::East
+        }));
+        assert!(!board.move_piece((3, 4), (4, 4)));
+
+        board.set_obstacle((4, 4), false);
+        assert!(board.move_piece((3, 4), (4, 4)));
+    }
+
+    #[test]
+    fn test_flags_follow_moved_pieces() {
+        let mut board = BoardState::new(10, 10);
+        board.place_piece(LogicPiece::OrIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        });
+        let movable = PieceFlags {
+            movable: true,
+            deletable: false,
+        };
+        board.set_flags((1, 1), movable);
+
+        assert!(board.move_piece((1, 1), (2, 2)));
+        assert_eq!(board.flags_at((2, 2)), movable);
+        assert!(board.flags_at((1, 1)).is_locked());
+
+        // Removing the piece takes its flags too
+        assert!(board.remove_piece(2, 2).is_some());
+        assert!(board.flags.is_empty());
+    }
+
+    #[test]
+    fn test_index_follows_board_changes() {
+        // Each AND spans two rows, so the last one sits one row below the top
+        let mut board = BoardState::new(40, 40);
+        for i in 0..39 {
+            assert!(board.place_piece(LogicPiece::AndIntro {
+                position: (i, i),
+                orientation: Direction::East
+            }));
+        }
+        assert!(board.move_piece((3, 3), (3, 4)));
+        assert!(board.remove_piece(0, 0).is_some());
+        // Removing a piece shifts the index rather than dropping it
+        assert!(board.index.get_mut().is_some());
+        assert_eq!(board.spatial().at((4, 4)), &[3]);
+        board.pieces_mut().push(LogicPiece::NotIntro {
+            position: (0, 1),
+            orientation: Direction::East,
+        });
+
+        assert!(!board.is_occupied(3, 3));
+        assert!(board.is_occupied(3, 4));
+        assert!(!board.is_occupied(0, 0));
+        assert!(matches!(
+            board.piece_at(0, 1),
+            Some(LogicPiece::NotIntro { .. })
+        ));
+        assert_eq!(board.pieces_near(2, 2, 1).len(), 2);
+
+        // The index isn't part of the saved board
+        let json = serde_json::to_string(&board).unwrap();
+        assert!(!json.contains("index"));
+        let loaded: BoardState = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, board);
+        assert!(loaded.is_occupied(3, 4));
+    }
+
+    #[test]
+    fn test_multi_cell_pieces_block_their_footprint() {
+        let mut board = BoardState::new(10, 10);
+        assert!(board.place_piece(LogicPiece::AndIntro {
+            position: (2, 2),
+            orientation: Direction::East
+        }));
+        assert!(board.is_occupied(2, 3));
+        assert!(!board.place_piece(LogicPiece::NotIntro {
+            position: (2, 3),
+            orientation: Direction::East
+        }));
+        assert!(!board.place_piece(LogicPiece::OrIntro {
+            position: (2, 1),
+            orientation: Direction::East
+        }));
+        // The top row has no room for a two-cell gate
+        assert!(!board.place_piece(LogicPiece::OrIntro {
+            position: (5, 9),
+            orientation: Direction::East
+        }));
+
+        // A gate can move onto a cell it already covers
+        assert!(board.move_piece((2, 2), (2, 3)));
+        assert_eq!(board.piece_at(2, 4), board.piece_at(2, 3));
+        assert!(!board.is_occupied(2, 2));
+    }
+
+    #[test]
+    fn test_turning_a_gate_turns_its_footprint() {
+        let mut board = BoardState::new(10, 10);
+        assert!(board.place_piece(LogicPiece::AndIntro {
+            position: (2, 2),
+            orientation: Direction::East,
+        }));
+
+        assert!(board.orient_piece((2, 2), Direction::South));
+        assert!(board.is_occupied(3, 2));
+        assert!(!board.is_occupied(2, 3));
+        assert_eq!(
+            board.piece_at(3, 2).and_then(LogicPiece::orientation),
+            Some(Direction::South)
+        );
+
+        // Turning back is blocked once the freed cell is taken
+        assert!(board.place_piece(LogicPiece::NotIntro {
+            position: (2, 3),
+            orientation: Direction::East,
+        }));
+        assert!(!board.orient_piece((2, 2), Direction::East));
+
+        // Only gates turn, and never off the board
+        board.place_piece(LogicPiece::Assumption {
+            formula: "P".to_string(),
+            position: (0, 0),
+        });
+        assert!(!board.orient_piece((0, 0), Direction::North));
+        board.place_piece(LogicPiece::OrIntro {
+            position: (9, 0),
+            orientation: Direction::East,
+        });
+        assert!(!board.orient_piece((9, 0), Direction::South));
+    }
 }
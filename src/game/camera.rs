@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Board camera controls, shared by play mode and the editor.
+//!
+//! Ctrl+wheel zooms about the mouse (the wheel alone turns gates),
+//! middle-dragging pans, and Home frames the whole board. A board is
+//! framed automatically whenever a board of different size is shown.
+
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use super::BoardGrid;
+
+/// Closest and farthest zoom, as orthographic projection scales
+const MIN_SCALE: f32 = 0.2;
+const MAX_SCALE: f32 = 10.0;
+/// Zoom factor per wheel notch
+const ZOOM_STEP: f32 = 1.15;
+/// Space kept around the board when framing it, in pixels
+const FIT_MARGIN: f32 = 40.0;
+
+/// Whether the wheel should zoom rather than turn gates this frame.
+pub fn zoom_held(keyboard: &ButtonInput<KeyCode>) -> bool {
+    keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// Camera position that keeps the world point `offset` pixels from the
+/// viewport centre (y up) in place when the scale changes from `scale` to
+/// `new_scale`.
+pub fn zoom_about(camera: Vec2, offset: Vec2, scale: f32, new_scale: f32) -> Vec2 {
+    camera + offset * (scale - new_scale)
+}
+
+/// Zoom, pan and frame the board camera.
+#[allow(clippy::too_many_arguments)]
+pub fn camera_controls(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    scroll: Res<AccumulatedMouseScroll>,
+    motion: Res<AccumulatedMouseMotion>,
+    grid: Res<BoardGrid>,
+    windows: Query<&Window>,
+    egui_input: Option<Res<EguiWantsInput>>,
+    mut camera_query: Query<(&Camera, &mut Transform, &mut Projection), With<Camera2d>>,
+) {
+    let Ok((camera, mut transform, mut projection)) = camera_query.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(ortho) = projection.as_mut() else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    let egui_wants_pointer = egui_input
+        .as_ref()
+        .is_some_and(|input| input.wants_any_pointer_input());
+    let egui_wants_keyboard = egui_input
+        .as_ref()
+        .is_some_and(|input| input.wants_any_keyboard_input());
+
+    if grid.is_changed() || (keyboard.just_pressed(KeyCode::Home) && !egui_wants_keyboard) {
+        ortho.scale = grid
+            .fit_scale(viewport, FIT_MARGIN)
+            .clamp(MIN_SCALE, MAX_SCALE);
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        return;
+    }
+    if egui_wants_pointer {
+        return;
+    }
+
+    if zoom_held(&keyboard) && scroll.delta.y != 0.0 {
+        let notches = match scroll.unit {
+            MouseScrollUnit::Line => scroll.delta.y,
+            MouseScrollUnit::Pixel => scroll.delta.y / 100.0,
+        };
+        let new_scale = (ortho.scale * ZOOM_STEP.powf(-notches)).clamp(MIN_SCALE, MAX_SCALE);
+        let offset = windows
+            .single()
+            .ok()
+            .and_then(Window::cursor_position)
+            .map_or(Vec2::ZERO, |pos| {
+                (pos - viewport / 2.0) * Vec2::new(1.0, -1.0)
+            });
+        let centre = zoom_about(
+            transform.translation.truncate(),
+            offset,
+            ortho.scale,
+            new_scale,
+        );
+        transform.translation.x = centre.x;
+        transform.translation.y = centre.y;
+        ortho.scale = new_scale;
+    }
+
+    if mouse.pressed(MouseButton::Middle) {
+        transform.translation.x -= motion.delta.x * ortho.scale;
+        transform.translation.y += motion.delta.y * ortho.scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zoom_keeps_point_under_mouse() {
+        let camera = Vec2::new(100.0, -50.0);
+        let offset = Vec2::new(200.0, 120.0);
+        let under_mouse = camera + offset * 2.0;
+        let moved = zoom_about(camera, offset, 2.0, 0.5);
+        assert_eq!(moved + offset * 0.5, under_mouse);
+    }
+}
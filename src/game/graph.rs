@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Connection graph for the logic puzzle board.
+//!
+//! Signal flows between typed ports (see [`super::ports`]): from an output
+//! port into an input port that faces it across a cell edge, or along a
+//! wire whose ends sit on the ports' attach cells. Assumptions only have
+//! outputs and goals only have inputs, so signal flows out of assumptions,
+//! through gates, into goals.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::ports::{Port, PortKind};
+use super::spatial::SpatialIndex;
+use super::{BoardState, LogicPiece};
+
+/// A connection from an output port of one node to an input of another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Link {
+    /// Node the signal leaves
+    pub from: usize,
+    /// Output port it leaves through
+    pub output: &'static str,
+    /// Node the signal enters
+    pub to: usize,
+    /// Input port it enters through
+    pub input: &'static str,
+}
+
+/// Directed connection graph over the non-wire pieces of a board.
+#[derive(Debug, Clone)]
+pub struct ConnectionGraph<'a> {
+    /// Nodes, in board order (wires are edges, not nodes).
+    pub nodes: Vec<&'a LogicPiece>,
+    /// Port-to-port connections, without duplicates.
+    pub links: Vec<Link>,
+    /// Nodes each node feeds, indexed like `nodes`.
+    downstream: Vec<Vec<usize>>,
+    /// Nodes feeding each node, indexed like `nodes`.
+    upstream: Vec<Vec<usize>>,
+}
+
+impl<'a> ConnectionGraph<'a> {
+    /// Build the graph for a board.
+    pub fn from_board(board: &'a BoardState) -> Self {
+        Self::from_pieces(board.pieces())
+    }
+
+    /// Build the graph for an arbitrary piece list.
+    pub fn from_pieces(pieces: &'a [LogicPiece]) -> Self {
+        // Node number of each piece, for looking nodes up by cell
+        let mut node_of = vec![None; pieces.len()];
+        let mut nodes: Vec<&LogicPiece> = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            if !matches!(piece, LogicPiece::Wire { .. }) {
+                node_of[i] = Some(nodes.len());
+                nodes.push(piece);
+            }
+        }
+        let ports: Vec<Vec<Port>> = nodes.iter().map(|n| n.ports()).collect();
+        let index = SpatialIndex::build(pieces);
+        let mut links = Vec::new();
+
+        // Ports facing each other across a cell edge
+        for (i, node_ports) in ports.iter().enumerate() {
+            for out in node_ports.iter().filter(|p| p.kind == PortKind::Output) {
+                let Some(cell) = out.attach() else {
+                    continue;
+                };
+                for j in index.at(cell).iter().filter_map(|&k| node_of[k]) {
+                    for input in ports[j].iter().filter(|q| out.faces(q)) {
+                        links.push(Link {
+                            from: i,
+                            output: out.name,
+                            to: j,
+                            input: input.name,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Wires between attach cells, joining outputs to inputs
+        let mut attached: HashMap<(u32, u32), Vec<(usize, &Port)>> = HashMap::new();
+        for (i, node_ports) in ports.iter().enumerate() {
+            for port in node_ports {
+                if let Some(cell) = port.attach() {
+                    attached.entry(cell).or_default().push((i, port));
+                }
+            }
+        }
+        for piece in pieces {
+            if let LogicPiece::Wire { from, to } = piece {
+                let ends = |cell| attached.get(cell).map(Vec::as_slice).unwrap_or(&[]);
+                for &(i, a) in ends(from) {
+                    for &(j, b) in ends(to) {
+                        let (out, input) = match (a.kind, b.kind) {
+                            (PortKind::Output, PortKind::Input) => ((i, a), (j, b)),
+                            (PortKind::Input, PortKind::Output) => ((j, b), (i, a)),
+                            _ => continue,
+                        };
+                        if out.0 != input.0 {
+                            links.push(Link {
+                                from: out.0,
+                                output: out.1.name,
+                                to: input.0,
+                                input: input.1.name,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let unique: Vec<Link> = links.into_iter().filter(|l| seen.insert(*l)).collect();
+        let mut downstream = vec![Vec::new(); nodes.len()];
+        let mut upstream = vec![Vec::new(); nodes.len()];
+        for link in &unique {
+            if !downstream[link.from].contains(&link.to) {
+                downstream[link.from].push(link.to);
+                upstream[link.to].push(link.from);
+            }
+        }
+
+        Self {
+            nodes,
+            links: unique,
+            downstream,
+            upstream,
+        }
+    }
+
+    /// The links feeding input port `input` of node `index`.
+    pub fn sources<'s>(&'s self, index: usize, input: &'s str) -> impl Iterator<Item = &'s Link> {
+        self.links
+            .iter()
+            .filter(move |l| l.to == index && l.input == input)
+    }
+
+    /// Indices of the nodes `index` feeds, without duplicates.
+    pub fn downstream(&self, index: usize) -> &[usize] {
+        &self.downstream[index]
+    }
+
+    /// Indices of the nodes connected to `index` in either direction.
+    pub fn neighbours(&self, index: usize) -> Vec<usize> {
+        let mut all: Vec<usize> = self.downstream[index]
+            .iter()
+            .chain(&self.upstream[index])
+            .copied()
+            .collect();
+        all.sort_unstable();
+        all.dedup();
+        all
+    }
+
+    /// For each node, whether signal from the assumptions reaches it. A
+    /// gate is only reached once every input port is fed by a reached
+    /// node, except OR, which needs one side.
+    pub fn reachable_from_assumptions(&self) -> Vec<bool> {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if matches!(node, LogicPiece::Assumption { .. }) {
+                seen[i] = true;
+                queue.push_back(i);
+            }
+        }
+
+        while let Some(i) = queue.pop_front() {
+            for &j in &self.downstream[i] {
+                if !seen[j] && self.inputs_fed(j, &seen) {
+                    seen[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+
+        seen
+    }
+
+    /// Whether the input ports of node `index` are fed by nodes in `fed`:
+    /// one side for an OR gate, every port otherwise.
+    fn inputs_fed(&self, index: usize, fed: &[bool]) -> bool {
+        let mut inputs = self.nodes[index]
+            .ports()
+            .into_iter()
+            .filter(|p| p.kind == PortKind::Input);
+        let port_fed = |port: Port| self.sources(index, port.name).any(|l| fed[l.from]);
+        match self.nodes[index] {
+            LogicPiece::OrIntro { .. } => inputs.any(port_fed),
+            _ => inputs.all(port_fed),
+        }
+    }
+
+    /// For each node, whether it has a path into some goal.
+    pub fn reaches_goal(&self) -> Vec<bool> {
+        self.flood(|_, p| matches!(p, LogicPiece::Goal { .. }), &self.upstream)
+    }
+
+    /// For each node, whether it has a path into node `index`. The node
+    /// itself counts.
+    pub fn feeding(&self, index: usize) -> Vec<bool> {
+        self.flood(|i, _| i == index, &self.upstream)
+    }
+
+    /// Breadth-first search from every node matching `is_start` along
+    /// `edges`.
+    fn flood(
+        &self,
+        is_start: impl Fn(usize, &LogicPiece) -> bool,
+        edges: &[Vec<usize>],
+    ) -> Vec<bool> {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if is_start(i, node) {
+                seen[i] = true;
+                queue.push_back(i);
+            }
+        }
+
+        while let Some(i) = queue.pop_front() {
+            for &j in &edges[i] {
+                if !seen[j] {
+                    seen[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+
+        seen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+
+    fn assumption(formula: &str, position: (u32, u32)) -> LogicPiece {
+        LogicPiece::Assumption {
+            formula: formula.to_string(),
+            position,
+        }
+    }
+
+    fn goal(formula: &str, position: (u32, u32)) -> LogicPiece {
+        LogicPiece::Goal {
+            formula: formula.to_string(),
+            position,
+        }
+    }
+
+    #[test]
+    fn test_facing_ports_link() {
+        let pieces = vec![
+            assumption("P", (3, 5)),
+            LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            },
+            goal("R", (5, 4)),
+            assumption("Q", (3, 4)),
+        ];
+        let graph = ConnectionGraph::from_pieces(&pieces);
+        assert_eq!(graph.neighbours(1), vec![0, 2, 3]);
+        assert_eq!(
+            graph.sources(1, "right").collect::<Vec<_>>(),
+            vec![&Link {
+                from: 3,
+                output: "out",
+                to: 1,
+                input: "right",
+            }]
+        );
+        assert_eq!(graph.reaches_goal(), vec![true, true, true, true]);
+    }
+
+    #[test]
+    fn test_wire_connects_distant_pieces() {
+        let pieces = vec![
+            assumption("P", (0, 0)),
+            LogicPiece::Wire {
+                from: (1, 0),
+                to: (7, 0),
+            },
+            goal("P", (8, 0)),
+        ];
+        let graph = ConnectionGraph::from_pieces(&pieces);
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.reachable_from_assumptions(), vec![true, true]);
+    }
+
+    #[test]
+    fn test_signal_only_flows_from_outputs_to_inputs() {
+        // The gate's input faces the goal, which has no output to feed it;
+        // the wire joins two outputs and carries nothing.
+        let pieces = vec![
+            assumption("P", (0, 0)),
+            goal("R", (1, 0)),
+            LogicPiece::NotIntro {
+                position: (2, 0),
+                orientation: Direction::East,
+            },
+            assumption("Q", (0, 5)),
+            LogicPiece::Wire {
+                from: (1, 5),
+                to: (3, 0),
+            },
+        ];
+        let graph = ConnectionGraph::from_pieces(&pieces);
+        assert_eq!(
+            graph.reachable_from_assumptions(),
+            vec![true, true, false, true]
+        );
+        assert_eq!(graph.reaches_goal(), vec![true, true, false, false]);
+        assert_eq!(graph.links.len(), 1);
+    }
+
+    #[test]
+    fn test_turned_gates_link_through_their_new_sides() {
+        // Two stacked NOT gates chain along the stack, in the direction
+        // they face, but not when both face sideways
+        let links = |orientation| {
+            let pieces = vec![
+                LogicPiece::NotIntro {
+                    position: (2, 2),
+                    orientation,
+                },
+                LogicPiece::NotIntro {
+                    position: (2, 3),
+                    orientation,
+                },
+            ];
+            ConnectionGraph::from_pieces(&pieces).links
+        };
+        assert!(links(Direction::East).is_empty());
+        assert_eq!(
+            links(Direction::South),
+            vec![Link {
+                from: 1,
+                output: "out",
+                to: 0,
+                input: "in",
+            }]
+        );
+        assert_eq!(
+            links(Direction::North),
+            vec![Link {
+                from: 0,
+                output: "out",
+                to: 1,
+                input: "in",
+            }]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Mapping between board cells and world space.
+//!
+//! A board is drawn centred on the world origin, one `CELL_SIZE` square
+//! per cell, whatever its dimensions. Play mode and the editor both go
+//! through [`BoardGrid`], so the cell under the mouse is the cell a piece
+//! placed there is drawn in.
+
+use bevy::prelude::*;
+
+use super::BoardState;
+
+/// Side of a board cell in world units.
+pub const CELL_SIZE: f32 = 80.0;
+
+/// Dimensions of the board on screen, for converting between cells and
+/// world positions.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardGrid {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for BoardGrid {
+    fn default() -> Self {
+        Self::new(10, 10)
+    }
+}
+
+impl BoardGrid {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width.max(1),
+            height: height.max(1),
+        }
+    }
+
+    /// The grid for a board's dimensions.
+    pub fn for_board(board: &BoardState) -> Self {
+        Self::new(board.width, board.height)
+    }
+
+    /// World position of the centre of `cell`.
+    pub fn cell_to_world(&self, (x, y): (u32, u32)) -> Vec2 {
+        Vec2::new(
+            (x as f32 - (self.width as f32 - 1.0) / 2.0) * CELL_SIZE,
+            (y as f32 - (self.height as f32 - 1.0) / 2.0) * CELL_SIZE,
+        )
+    }
+
+    /// The cell containing world position `pos`, if it is on the board.
+    pub fn world_to_cell(&self, pos: Vec2) -> Option<(u32, u32)> {
+        let (x, y) = self.cell_coords(pos);
+        let on_board = (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y);
+        on_board.then_some((x as u32, y as u32))
+    }
+
+    /// The board cell nearest world position `pos`.
+    pub fn clamp_to_cell(&self, pos: Vec2) -> (u32, u32) {
+        let (x, y) = self.cell_coords(pos);
+        (
+            x.clamp(0, self.width as i64 - 1) as u32,
+            y.clamp(0, self.height as i64 - 1) as u32,
+        )
+    }
+
+    /// Whether `cell` is on the board.
+    pub fn contains(&self, (x, y): (u32, u32)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Size of the whole board in world units.
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * CELL_SIZE
+    }
+
+    /// Camera scale that shows the whole board in a viewport of
+    /// `viewport` logical pixels, with a margin of `margin` pixels a side.
+    pub fn fit_scale(&self, viewport: Vec2, margin: f32) -> f32 {
+        let room = (viewport - Vec2::splat(2.0 * margin)).max(Vec2::ONE);
+        (self.size() / room).max_element()
+    }
+
+    /// Size of a sprite filling one cell, leaving a gap to its neighbours.
+    pub fn tile_size(&self) -> Vec2 {
+        Vec2::splat(CELL_SIZE - 2.0)
+    }
+
+    /// Checkerboard background tile for `cell`.
+    pub fn cell_tile(&self, (x, y): (u32, u32)) -> (Sprite, Transform) {
+        let color = if (x + y) % 2 == 0 {
+            Color::srgba(0.15, 0.15, 0.2, 1.0)
+        } else {
+            Color::srgba(0.12, 0.12, 0.17, 1.0)
+        };
+        (
+            Sprite {
+                color,
+                custom_size: Some(self.tile_size()),
+                ..default()
+            },
+            Transform::from_translation(self.cell_to_world((x, y)).extend(-1.0)),
+        )
+    }
+
+    /// Unbounded cell coordinates of world position `pos`.
+    fn cell_coords(&self, pos: Vec2) -> (i64, i64) {
+        let origin = self.cell_to_world((0, 0)) - Vec2::splat(CELL_SIZE / 2.0);
+        let cell = ((pos - origin) / CELL_SIZE).floor();
+        (cell.x as i64, cell.y as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells_round_trip() {
+        for grid in [BoardGrid::new(10, 10), BoardGrid::new(7, 24)] {
+            for cell in [(0, 0), (3, 5), (grid.width - 1, grid.height - 1)] {
+                let centre = grid.cell_to_world(cell);
+                assert_eq!(grid.world_to_cell(centre), Some(cell));
+                let corner = centre + Vec2::splat(CELL_SIZE / 2.0 - 1.0);
+                assert_eq!(grid.world_to_cell(corner), Some(cell));
+            }
+        }
+
+        // The 10x10 board keeps its old placement
+        let grid = BoardGrid::default();
+        assert_eq!(grid.cell_to_world((0, 0)), Vec2::new(-360.0, -360.0));
+        assert_eq!(grid.world_to_cell(Vec2::new(401.0, 0.0)), None);
+        assert_eq!(grid.clamp_to_cell(Vec2::new(401.0, -9000.0)), (9, 0));
+    }
+
+    #[test]
+    fn test_fit_scale() {
+        let grid = BoardGrid::new(40, 10);
+        // 3200 x 800 world units into 1600 x 1600 pixels, less margins
+        assert_eq!(grid.fit_scale(Vec2::new(1640.0, 1640.0), 20.0), 2.0);
+        assert!(BoardGrid::new(5, 5).fit_scale(Vec2::new(1280.0, 720.0), 0.0) < 1.0);
+    }
+}
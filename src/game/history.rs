@@ -0,0 +1,418 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Undo/redo history for play mode.
+//!
+//! Every edit the player makes to the board is recorded as a reversible
+//! [`PlayAction`]. The history is bounded (oldest actions are dropped first)
+//! and serializable, so it can be saved alongside a partly solved level.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::{BoardState, Direction, LogicPiece, PieceFlags};
+
+/// Default number of actions kept for undo.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 256;
+
+/// A single reversible edit made by the player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlayAction {
+    /// A piece was placed on the board. `level_flags` are the flags of a
+    /// level's own piece coming back, `None` for the player's pieces.
+    Place {
+        piece: LogicPiece,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level_flags: Option<PieceFlags>,
+    },
+    /// `piece`, as it stood, was moved to `to`.
+    Move { piece: LogicPiece, to: (u32, u32) },
+    /// A piece was removed from the board, with its flags if it was one of
+    /// the level's own.
+    Delete {
+        piece: LogicPiece,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level_flags: Option<PieceFlags>,
+    },
+    /// A wire was rerouted, replacing `old` with `new`.
+    Reroute { old: LogicPiece, new: LogicPiece },
+    /// The gate at `position` was turned from facing `from` to `to`.
+    Rotate {
+        position: (u32, u32),
+        from: Direction,
+        to: Direction,
+    },
+}
+
+impl PlayAction {
+    /// The action that reverts this one.
+    pub fn inverse(&self) -> PlayAction {
+        match self {
+            Self::Place { piece, level_flags } => Self::Delete {
+                piece: piece.clone(),
+                level_flags: *level_flags,
+            },
+            Self::Move { piece, to } => {
+                let mut moved = piece.clone();
+                moved.set_position(*to);
+                Self::Move {
+                    piece: moved,
+                    to: piece.position(),
+                }
+            }
+            Self::Delete { piece, level_flags } => Self::Place {
+                piece: piece.clone(),
+                level_flags: *level_flags,
+            },
+            Self::Reroute { old, new } => Self::Reroute {
+                old: new.clone(),
+                new: old.clone(),
+            },
+            Self::Rotate { position, from, to } => Self::Rotate {
+                position: *position,
+                from: *to,
+                to: *from,
+            },
+        }
+    }
+
+    /// Apply this action to a board. Returns true if the board changed.
+    pub fn apply(&self, board: &mut BoardState) -> bool {
+        match self {
+            Self::Place { piece, .. } => board.place_piece(piece.clone()),
+            Self::Move { piece, to } => board.move_piece(piece.position(), *to),
+            Self::Delete { piece, .. } => {
+                let (x, y) = piece.position();
+                board.remove_piece(x, y).is_some()
+            }
+            Self::Reroute { old, new } => {
+                let Some(index) = board.pieces().iter().position(|p| p == old) else {
+                    return false;
+                };
+                let removed = board.pieces_mut().remove(index);
+                if board.place_piece(new.clone()) {
+                    true
+                } else {
+                    board.pieces_mut().insert(index, removed);
+                    false
+                }
+            }
+            Self::Rotate { position, to, .. } => board.orient_piece(*position, *to),
+        }
+    }
+}
+
+/// Bounded undo/redo stacks of player actions.
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+pub struct ActionHistory {
+    undo_stack: VecDeque<PlayAction>,
+    redo_stack: Vec<PlayAction>,
+    capacity: usize,
+}
+
+impl Default for ActionHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl ActionHistory {
+    /// Create an empty history that keeps at most `capacity` undo steps.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an action the player just performed. Clears the redo stack.
+    pub fn record(&mut self, action: PlayAction) {
+        self.redo_stack.clear();
+        self.undo_stack.push_back(action);
+        while self.undo_stack.len() > self.capacity {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Pop the most recent action and return the action that reverts it.
+    pub fn undo(&mut self) -> Option<PlayAction> {
+        let action = self.undo_stack.pop_back()?;
+        let inverse = action.inverse();
+        self.redo_stack.push(action);
+        Some(inverse)
+    }
+
+    /// Pop the most recently undone action and return it for re-applying.
+    pub fn redo(&mut self) -> Option<PlayAction> {
+        let action = self.redo_stack.pop()?;
+        self.undo_stack.push_back(action.clone());
+        Some(action)
+    }
+
+    /// Forget the action the last `undo` returned the inverse of, when it
+    /// could not be applied.
+    pub fn discard_undone(&mut self) {
+        self.redo_stack.pop();
+    }
+
+    /// Forget the action the last `redo` returned, when it could not be
+    /// applied.
+    pub fn discard_redone(&mut self) {
+        self.undo_stack.pop_back();
+    }
+
+    /// Whether there is anything to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Whether there is anything to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Number of actions that can be undone.
+    pub fn len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Whether the history is empty (nothing to undo or redo).
+    pub fn is_empty(&self) -> bool {
+        self.undo_stack.is_empty() && self.redo_stack.is_empty()
+    }
+
+    /// Forget all recorded actions.
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_place() {
+        let mut board = BoardState::new(10, 10);
+        let mut history = ActionHistory::default();
+
+        let place = PlayAction::Place {
+            piece: LogicPiece::AndIntro {
+                position: (3, 3),
+                orientation: Direction::East,
+            },
+            level_flags: None,
+        };
+        assert!(place.apply(&mut board));
+        history.record(place);
+
+        let undo = history.undo().expect("one action to undo");
+        assert!(undo.apply(&mut board));
+        assert!(!board.is_occupied(3, 3));
+
+        let redo = history.redo().expect("one action to redo");
+        assert!(redo.apply(&mut board));
+        assert!(board.is_occupied(3, 3));
+    }
+
+    #[test]
+    fn test_undo_move_and_delete() {
+        let mut board = BoardState::new(10, 10);
+        let mut history = ActionHistory::default();
+        let piece = LogicPiece::OrIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        };
+
+        for action in [
+            PlayAction::Place {
+                piece: piece.clone(),
+                level_flags: None,
+            },
+            PlayAction::Move {
+                piece: piece.clone(),
+                to: (4, 2),
+            },
+            PlayAction::Delete {
+                piece: LogicPiece::OrIntro {
+                    position: (4, 2),
+                    orientation: Direction::East,
+                },
+                level_flags: None,
+            },
+        ] {
+            assert!(action.apply(&mut board));
+            history.record(action);
+        }
+        assert_eq!(board.piece_count(), 0);
+
+        history.undo().unwrap().apply(&mut board);
+        assert!(board.is_occupied(4, 2));
+        history.undo().unwrap().apply(&mut board);
+        assert!(board.is_occupied(1, 1));
+        assert!(!board.is_occupied(4, 2));
+    }
+
+    #[test]
+    fn test_undo_keeps_what_it_needs_to_restore_a_moved_level_piece() {
+        let gate = LogicPiece::AndIntro {
+            position: (2, 2),
+            orientation: Direction::East,
+        };
+        let moved = PlayAction::Move {
+            piece: gate.clone(),
+            to: (5, 5),
+        };
+        let mut back = gate.clone();
+        back.set_position((5, 5));
+        assert_eq!(
+            moved.inverse(),
+            PlayAction::Move {
+                piece: back.clone(),
+                to: (2, 2),
+            }
+        );
+
+        let flags = PieceFlags {
+            movable: true,
+            deletable: true,
+        };
+        let deleted = PlayAction::Delete {
+            piece: back.clone(),
+            level_flags: Some(flags),
+        };
+        assert_eq!(
+            deleted.inverse(),
+            PlayAction::Place {
+                piece: back,
+                level_flags: Some(flags),
+            }
+        );
+    }
+
+    #[test]
+    fn test_undo_reroute() {
+        let mut board = BoardState::new(10, 10);
+        let old = LogicPiece::Wire {
+            from: (1, 1),
+            to: (5, 1),
+        };
+        let new = LogicPiece::Wire {
+            from: (1, 1),
+            to: (5, 4),
+        };
+        board.place_piece(old.clone());
+
+        let reroute = PlayAction::Reroute {
+            old: old.clone(),
+            new: new.clone(),
+        };
+        assert!(reroute.apply(&mut board));
+        assert_eq!(board.pieces(), vec![new]);
+        assert!(reroute.inverse().apply(&mut board));
+        assert_eq!(board.pieces(), vec![old]);
+    }
+
+    #[test]
+    fn test_undo_rotate() {
+        let mut board = BoardState::new(10, 10);
+        board.place_piece(LogicPiece::ImpliesIntro {
+            position: (2, 2),
+            orientation: Direction::East,
+        });
+
+        let rotate = PlayAction::Rotate {
+            position: (2, 2),
+            from: Direction::East,
+            to: Direction::West,
+        };
+        assert!(rotate.apply(&mut board));
+        assert_eq!(board.pieces()[0].orientation(), Some(Direction::West));
+        assert!(rotate.inverse().apply(&mut board));
+        assert_eq!(board.pieces()[0].orientation(), Some(Direction::East));
+    }
+
+    #[test]
+    fn test_record_clears_redo() {
+        let mut history = ActionHistory::default();
+        history.record(PlayAction::Place {
+            piece: LogicPiece::AndIntro {
+                position: (0, 0),
+                orientation: Direction::East,
+            },
+            level_flags: None,
+        });
+        history.undo();
+        assert!(history.can_redo());
+
+        history.record(PlayAction::Place {
+            piece: LogicPiece::AndIntro {
+                position: (1, 0),
+                orientation: Direction::East,
+            },
+            level_flags: None,
+        });
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_capacity_drops_oldest() {
+        let mut history = ActionHistory::new(2);
+        for x in 0..5 {
+            history.record(PlayAction::Place {
+                piece: LogicPiece::AndIntro {
+                    position: (x, 0),
+                    orientation: Direction::East,
+                },
+                level_flags: None,
+            });
+        }
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history.undo(),
+            Some(PlayAction::Delete {
+                piece: LogicPiece::AndIntro {
+                    position: (4, 0),
+                    orientation: Direction::East
+                },
+                level_flags: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_history_round_trips_through_json() {
+        let mut history = ActionHistory::new(8);
+        history.record(PlayAction::Move {
+            piece: LogicPiece::NotIntro {
+                position: (1, 2),
+                orientation: Direction::East,
+            },
+            to: (3, 4),
+        });
+        history.record(PlayAction::Place {
+            piece: LogicPiece::AndIntro {
+                position: (0, 0),
+                orientation: Direction::East,
+            },
+            level_flags: None,
+        });
+        history.undo();
+
+        let json = serde_json::to_string(&history).unwrap();
+        let mut restored: ActionHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert!(restored.can_redo());
+        assert_eq!(
+            restored.redo(),
+            Some(PlayAction::Place {
+                piece: LogicPiece::AndIntro {
+                    position: (0, 0),
+                    orientation: Direction::East
+                },
+                level_flags: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Per-level piece inventories.
+//!
+//! A level lists which kinds of piece the player may place and how many of
+//! each. Remaining counts are derived from the pieces the player has on the
+//! board, so undo/redo and deletion give pieces back automatically.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Direction, LogicPiece, PlaceablePiece};
+
+/// Variable bound by quantifier pieces placed from the palette.
+pub const DEFAULT_QUANTIFIER_VARIABLE: &str = "x";
+
+impl PlaceablePiece {
+    /// Every kind, in palette order.
+    pub const ALL: [PlaceablePiece; 7] = [
+        PlaceablePiece::AndGate,
+        PlaceablePiece::OrGate,
+        PlaceablePiece::ImpliesGate,
+        PlaceablePiece::NotGate,
+        PlaceablePiece::Forall,
+        PlaceablePiece::Exists,
+        PlaceablePiece::Wire,
+    ];
+
+    /// Display name for the palette.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AndGate => "AND Gate",
+            Self::OrGate => "OR Gate",
+            Self::ImpliesGate => "Implies",
+            Self::NotGate => "NOT",
+            Self::Forall => "For All",
+            Self::Exists => "Exists",
+            Self::Wire => "Wire",
+        }
+    }
+
+    /// The kind of a piece on the board, if players can place it.
+    pub fn of(piece: &LogicPiece) -> Option<Self> {
+        match piece {
+            LogicPiece::AndIntro { .. } => Some(Self::AndGate),
+            LogicPiece::OrIntro { .. } => Some(Self::OrGate),
+            LogicPiece::ImpliesIntro { .. } => Some(Self::ImpliesGate),
+            LogicPiece::NotIntro { .. } => Some(Self::NotGate),
+            LogicPiece::ForallIntro { .. } => Some(Self::Forall),
+            LogicPiece::ExistsIntro { .. } => Some(Self::Exists),
+            LogicPiece::Wire { .. } => Some(Self::Wire),
+            LogicPiece::Assumption { .. } | LogicPiece::Goal { .. } => None,
+        }
+    }
+
+    /// Build a piece of this kind at a grid cell. Wires are drawn between
+    /// two cells instead, so they return `None`.
+    pub fn to_logic_piece(self, position: (u32, u32)) -> Option<LogicPiece> {
+        let variable = DEFAULT_QUANTIFIER_VARIABLE.to_string();
+        Some(match self {
+            Self::AndGate => LogicPiece::AndIntro {
+                position,
+                orientation: Direction::East,
+            },
+            Self::OrGate => LogicPiece::OrIntro {
+                position,
+                orientation: Direction::East,
+            },
+            Self::ImpliesGate => LogicPiece::ImpliesIntro {
+                position,
+                orientation: Direction::East,
+            },
+            Self::NotGate => LogicPiece::NotIntro {
+                position,
+                orientation: Direction::East,
+            },
+            Self::Forall => LogicPiece::ForallIntro {
+                position,
+                variable,
+                orientation: Direction::East,
+            },
+            Self::Exists => LogicPiece::ExistsIntro {
+                position,
+                variable,
+                orientation: Direction::East,
+            },
+            Self::Wire => return None,
+        })
+    }
+}
+
+/// How many of one kind of piece a level provides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventorySlot {
+    pub kind: PlaceablePiece,
+    /// Number available; `None` means unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+/// The pieces a level makes available to the player.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Inventory {
+    /// Slots in palette order. An empty list means every kind is available
+    /// without limit.
+    pub slots: Vec<InventorySlot>,
+}
+
+impl Inventory {
+    /// Whether the level places no restrictions on the palette.
+    pub fn is_unrestricted(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Slots to show in the palette, expanding an unrestricted inventory
+    /// to every kind.
+    pub fn palette(&self) -> Vec<InventorySlot> {
+        if self.is_unrestricted() {
+            PlaceablePiece::ALL
+                .iter()
+                .map(|&kind| InventorySlot { kind, count: None })
+                .collect()
+        } else {
+            self.slots.clone()
+        }
+    }
+
+    /// The slot for a kind, if the level offers it.
+    pub fn slot(&self, kind: PlaceablePiece) -> Option<InventorySlot> {
+        self.palette().into_iter().find(|s| s.kind == kind)
+    }
+
+    /// Offer `count` pieces of a kind (`None` for unlimited), adding a slot
+    /// if needed.
+    pub fn set_count(&mut self, kind: PlaceablePiece, count: Option<u32>) {
+        match self.slots.iter_mut().find(|s| s.kind == kind) {
+            Some(slot) => slot.count = count,
+            None => self.slots.push(InventorySlot { kind, count }),
+        }
+    }
+
+    /// Stop offering a kind.
+    pub fn remove(&mut self, kind: PlaceablePiece) {
+        self.slots.retain(|s| s.kind != kind);
+    }
+
+    /// How many more pieces of `kind` can be placed, given the pieces the
+    /// player has already placed. `None` means unlimited; `Some(0)` means
+    /// the kind is exhausted or not offered.
+    pub fn remaining<'a>(
+        &self,
+        kind: PlaceablePiece,
+        placed: impl IntoIterator<Item = &'a LogicPiece>,
+    ) -> Option<u32> {
+        let Some(slot) = self.slot(kind) else {
+            return Some(0);
+        };
+        let limit = slot.count?;
+        let used = placed
+            .into_iter()
+            .filter(|p| PlaceablePiece::of(p) == Some(kind))
+            .count() as u32;
+        Some(limit.saturating_sub(used))
+    }
+
+    /// Whether one more piece of `kind` can be placed.
+    pub fn can_place<'a>(
+        &self,
+        kind: PlaceablePiece,
+        placed: impl IntoIterator<Item = &'a LogicPiece>,
+    ) -> bool {
+        self.remaining(kind, placed) != Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrestricted_inventory_offers_everything() {
+        let inventory = Inventory::default();
+        assert_eq!(inventory.palette().len(), PlaceablePiece::ALL.len());
+        assert!(inventory.can_place(PlaceablePiece::Exists, &[]));
+        assert_eq!(inventory.remaining(PlaceablePiece::NotGate, &[]), None);
+    }
+
+    #[test]
+    fn test_counts_are_used_up_by_placed_pieces() {
+        let mut inventory = Inventory::default();
+        inventory.set_count(PlaceablePiece::AndGate, Some(1));
+        inventory.set_count(PlaceablePiece::Wire, None);
+
+        let placed = vec![LogicPiece::AndIntro {
+            position: (1, 1),
+            orientation: Direction::East,
+        }];
+        assert_eq!(
+            inventory.remaining(PlaceablePiece::AndGate, &placed),
+            Some(0)
+        );
+        assert!(!inventory.can_place(PlaceablePiece::AndGate, &placed));
+        assert!(inventory.can_place(PlaceablePiece::Wire, &placed));
+        // Kinds missing from a restricted inventory can't be placed
+        assert!(!inventory.can_place(PlaceablePiece::OrGate, &placed));
+    }
+
+    #[test]
+    fn test_inventory_round_trips_through_json() {
+        let mut inventory = Inventory::default();
+        inventory.set_count(PlaceablePiece::ImpliesGate, Some(2));
+        inventory.set_count(PlaceablePiece::Forall, None);
+
+        let json = serde_json::to_string(&inventory).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"kind":"ImpliesGate","count":2},{"kind":"Forall"}]"#
+        );
+        let restored: Inventory = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, inventory);
+    }
+}
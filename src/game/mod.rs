@@ -1,19 +1,46 @@
 // SPDX-License-Identifier: MPL-2.0
 
+#[allow(dead_code)]
+pub mod actions;
 #[allow(dead_code)]
 pub mod board;
+pub mod camera;
+#[allow(dead_code)]
+pub mod graph;
+pub mod grid;
+#[allow(dead_code)]
+pub mod history;
+#[allow(dead_code)]
+pub mod inventory;
 pub mod pieces;
 #[allow(dead_code)]
+pub mod ports;
+#[allow(dead_code)]
+pub mod signal;
+#[allow(dead_code)]
+pub mod spatial;
+#[allow(dead_code)]
+pub mod stats;
+#[allow(dead_code)]
 pub mod validation;
+#[allow(dead_code)]
+pub mod wiring;
 
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Instant;
 
+pub use actions::{Action, ActionState, Binding, InputMap};
+pub use grid::{BoardGrid, CELL_SIZE};
+pub use history::{ActionHistory, PlayAction};
+pub use inventory::{Inventory, InventorySlot};
 pub use pieces::*;
+pub use ports::Direction;
+pub use stats::LevelHistory;
 
 // Level definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Level {
     pub id: u32,
     pub name: String,
@@ -21,16 +48,33 @@ pub struct Level {
     pub theorem: String,
     pub initial_state: BoardState,
     pub goal_state: GoalCondition,
+    /// A full board the verifier accepted, kept as evidence that the level
+    /// is solvable (see I4 in src/abi/ProofOfWork/ABI/Invariants.idr)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<Vec<LogicPiece>>,
+    /// Pieces the player may place; empty means every kind, unlimited
+    #[serde(default, skip_serializing_if = "Inventory::is_unrestricted")]
+    pub inventory: Inventory,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BoardState {
     pub width: u32,
     pub height: u32,
-    pub pieces: Vec<LogicPiece>,
+    /// Private so every change goes through the board, keeping `index` valid
+    pieces: Vec<LogicPiece>,
+    /// Blocked terrain cells that no piece may occupy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<(u32, u32)>,
+    /// Flags for pre-placed pieces, by position; unlisted pieces are locked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<FlaggedPiece>,
+    /// Cell lookup for `pieces`, kept in sync by the board's methods
+    #[serde(skip)]
+    index: spatial::SpatialCache,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GoalCondition {
     ConnectNodes { start: (u32, u32), end: (u32, u32) },
     ProveFormula { formula: String },
@@ -41,34 +85,124 @@ pub enum GoalCondition {
 #[derive(Component)]
 pub struct CurrentLevel(pub Level);
 
+/// Pack id and level id of a level played from a pack, under which its
+/// unfinished solution is saved
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct LevelKey {
+    pub pack_id: String,
+    pub level_id: u32,
+}
+
 #[derive(Component)]
 pub struct PlayerCursor {
     pub position: Vec2,
     pub selected_piece: Option<Entity>,
+    /// Grid cell the selected piece was picked up from (for undoing moves)
+    pub selected_from: Option<(u32, u32)>,
+    /// Grid cell where the wire being drawn was started
+    pub wire_start: Option<(u32, u32)>,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PlayerStats {
     pub proofs_completed: u32,
     pub levels_completed: u32,
     pub total_playtime_secs: u64,
     pub last_level_time_secs: u64,
+    #[serde(skip)]
     pub level_start_time: Option<Instant>,
+    /// Time spent on the current level in earlier sessions
+    #[serde(skip)]
+    pub resumed_secs: u64,
+    /// Per-level history (by pack id, then level id)
+    pub levels: HashMap<String, HashMap<u32, LevelHistory>>,
+    /// Consecutive days, up to `last_solve_day`, with at least one solve
+    pub streak_days: u32,
+    /// Longest run of consecutive days with a solve
+    pub best_streak_days: u32,
+    /// Day (counted from the Unix epoch, UTC) of the most recent solve
+    pub last_solve_day: Option<u64>,
 }
 
 impl PlayerStats {
     pub fn start_level(&mut self) {
+        self.resume_level(0);
+    }
+
+    /// Start timing a level that already took `elapsed_secs` before
+    pub fn resume_level(&mut self, elapsed_secs: u64) {
         self.level_start_time = Some(Instant::now());
+        self.resumed_secs = elapsed_secs;
+    }
+
+    /// Whether a level is being timed, i.e. started and not yet solved
+    pub fn is_level_running(&self) -> bool {
+        self.level_start_time.is_some()
+    }
+
+    /// Total seconds spent on the current level, across sessions
+    pub fn elapsed_secs(&self) -> u64 {
+        self.resumed_secs
+            + self
+                .level_start_time
+                .map(|start| start.elapsed().as_secs())
+                .unwrap_or(0)
+    }
+
+    /// Stop timing an unfinished level, adding this session to the total
+    /// play time. Returns the time spent on the level so far.
+    pub fn pause_level(&mut self) -> u64 {
+        let elapsed = self.elapsed_secs();
+        self.total_playtime_secs += elapsed - self.resumed_secs;
+        self.level_start_time = None;
+        self.resumed_secs = elapsed;
+        elapsed
     }
 
     pub fn complete_level(&mut self) {
-        if let Some(start_time) = self.level_start_time {
-            self.last_level_time_secs = start_time.elapsed().as_secs();
+        if self.level_start_time.is_some() {
+            self.last_level_time_secs = self.elapsed_secs();
             self.level_start_time = None;
         }
     }
 }
 
+/// The level the player just solved, kept for the completion screen and
+/// stats once the board has been cleaned up
+#[derive(Resource, Debug, Clone)]
+pub struct SolvedLevel {
+    pub level: Level,
+    pub key: Option<LevelKey>,
+    /// Every piece on the verified board
+    pub pieces: Vec<LogicPiece>,
+    /// How many of those the player placed
+    pub pieces_used: u32,
+}
+
+/// What the pieces on the board carry right now, kept up to date as the
+/// player edits so the board can show signal before verification
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LiveCircuit {
+    /// Piece entities, in the order they were evaluated
+    pub entities: Vec<Entity>,
+    /// Evaluation of those entities' pieces, indexed like `entities`
+    pub state: signal::CircuitState,
+}
+
+impl LiveCircuit {
+    /// The formula piece `entity` carries, if any.
+    #[allow(dead_code)]
+    pub fn signal(&self, entity: Entity) -> Option<&str> {
+        let index = self.entities.iter().position(|&e| e == entity)?;
+        self.state.signals.get(index)?.as_deref()
+    }
+}
+
+/// Request to put the current level back to its starting layout
+#[derive(Message, Clone)]
+pub struct ResetLevelEvent;
+
 // Marker component for cleanup
 #[derive(Component)]
 pub struct GameEntity;
@@ -79,10 +213,14 @@ pub fn complete_level(&mut self) {
 
 /// Placeable piece types for the UI
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PlaceablePiece {
     AndGate,
     OrGate,
+    ImpliesGate,
+    NotGate,
+    Forall,
+    Exists,
     Wire,
 }
 
@@ -1,9 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use bevy::input::mouse::AccumulatedMouseScroll;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+use super::camera::zoom_held;
+use super::{signal, Action, ActionState, Direction, CELL_SIZE};
+
+#[derive(Debug, Clone, PartialEq, Eq, Component, Serialize, Deserialize)]
 pub enum LogicPiece {
     // Basic building blocks
     Assumption {
@@ -15,28 +19,41 @@ pub enum LogicPiece {
         position: (u32, u32),
     },
 
-    // Logical operators (movable pieces)
+    // Logical operators (movable pieces). Gates face the side their
+    // output is on; levels saved before gates could turn face east.
     AndIntro {
         position: (u32, u32),
+        #[serde(default)]
+        orientation: Direction,
     },
     OrIntro {
         position: (u32, u32),
+        #[serde(default)]
+        orientation: Direction,
     },
     ImpliesIntro {
         position: (u32, u32),
+        #[serde(default)]
+        orientation: Direction,
     },
     NotIntro {
         position: (u32, u32),
+        #[serde(default)]
+        orientation: Direction,
     },
 
     // Quantifiers
     ForallIntro {
         position: (u32, u32),
         variable: String,
+        #[serde(default)]
+        orientation: Direction,
     },
     ExistsIntro {
         position: (u32, u32),
         variable: String,
+        #[serde(default)]
+        orientation: Direction,
     },
 
     // Connectors
@@ -51,10 +68,10 @@ pub fn position(&self) -> (u32, u32) {
         match self {
             Self::Assumption { position, .. } => *position,
             Self::Goal { position, .. } => *position,
-            Self::AndIntro { position } => *position,
-            Self::OrIntro { position } => *position,
-            Self::ImpliesIntro { position } => *position,
-            Self::NotIntro { position } => *position,
+            Self::AndIntro { position, .. } => *position,
+            Self::OrIntro { position, .. } => *position,
+            Self::ImpliesIntro { position, .. } => *position,
+            Self::NotIntro { position, .. } => *position,
             Self::ForallIntro { position, .. } => *position,
             Self::ExistsIntro { position, .. } => *position,
             Self::Wire { from, .. } => *from,
@@ -65,16 +82,63 @@ pub fn set_position(&mut self, new_pos: (u32, u32)) {
         match self {
             Self::Assumption { position, .. } => *position = new_pos,
             Self::Goal { position, .. } => *position = new_pos,
-            Self::AndIntro { position } => *position = new_pos,
-            Self::OrIntro { position } => *position = new_pos,
-            Self::ImpliesIntro { position } => *position = new_pos,
-            Self::NotIntro { position } => *position = new_pos,
+            Self::AndIntro { position, .. } => *position = new_pos,
+            Self::OrIntro { position, .. } => *position = new_pos,
+            Self::ImpliesIntro { position, .. } => *position = new_pos,
+            Self::NotIntro { position, .. } => *position = new_pos,
             Self::ForallIntro { position, .. } => *position = new_pos,
             Self::ExistsIntro { position, .. } => *position = new_pos,
             Self::Wire { from, .. } => *from = new_pos,
         }
     }
 
+    /// The way a gate faces: the side its output is on. Assumptions,
+    /// goals and wires have no orientation.
+    pub fn orientation(&self) -> Option<Direction> {
+        match self {
+            Self::AndIntro { orientation, .. }
+            | Self::OrIntro { orientation, .. }
+            | Self::ImpliesIntro { orientation, .. }
+            | Self::NotIntro { orientation, .. }
+            | Self::ForallIntro { orientation, .. }
+            | Self::ExistsIntro { orientation, .. } => Some(*orientation),
+            _ => None,
+        }
+    }
+
+    /// Turn a gate to face `facing`. Returns false for pieces that can't
+    /// be turned.
+    pub fn set_orientation(&mut self, facing: Direction) -> bool {
+        match self {
+            Self::AndIntro { orientation, .. }
+            | Self::OrIntro { orientation, .. }
+            | Self::ImpliesIntro { orientation, .. }
+            | Self::NotIntro { orientation, .. }
+            | Self::ForallIntro { orientation, .. }
+            | Self::ExistsIntro { orientation, .. } => {
+                *orientation = facing;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Copy of this piece shifted by `(dx, dy)`; wires move both endpoints.
+    /// Returns `None` if any coordinate would become negative.
+    pub fn translated(&self, dx: i32, dy: i32) -> Option<Self> {
+        let shift = |(x, y): (u32, u32)| -> Option<(u32, u32)> {
+            Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?))
+        };
+        let mut piece = self.clone();
+        if let Self::Wire { from, to } = &mut piece {
+            *from = shift(*from)?;
+            *to = shift(*to)?;
+        } else {
+            piece.set_position(shift(self.position())?);
+        }
+        Some(piece)
+    }
+
     #[allow(dead_code)]
     pub fn to_smt(&self) -> String {
         match self {
@@ -104,6 +168,60 @@ pub fn label(&self) -> String {
         }
     }
 
+    /// Symbol marking the piece's kind on the board, so kinds can be told
+    /// apart without relying on colour.
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            Self::Assumption { .. } => "▶",
+            Self::Goal { .. } => "◎",
+            Self::AndIntro { .. } => "∧",
+            Self::OrIntro { .. } => "∨",
+            Self::ImpliesIntro { .. } => "→",
+            Self::NotIntro { .. } => "¬",
+            Self::ForallIntro { .. } => "∀",
+            Self::ExistsIntro { .. } => "∃",
+            Self::Wire { .. } => "─",
+        }
+    }
+
+    /// Text drawn on the piece's tile: its glyph, followed by the formula
+    /// of an assumption or goal or the variable a quantifier binds.
+    pub fn board_label(&self) -> String {
+        match self {
+            Self::Assumption { formula, .. } | Self::Goal { formula, .. } => {
+                format!("{} {}", self.glyph(), signal::pretty(formula))
+            }
+            Self::ForallIntro { variable, .. } | Self::ExistsIntro { variable, .. } => {
+                format!("{}{}", self.glyph(), variable)
+            }
+            _ => self.glyph().to_string(),
+        }
+    }
+
+    /// Sprite size and transform of a tile covering the piece's footprint,
+    /// given the world position of a cell's centre. The sprite is sized
+    /// for the east-facing layout and turned to the piece's orientation.
+    pub fn tile(&self, to_world: impl Fn((u32, u32)) -> Vec2) -> (Vec2, Transform) {
+        let cells: Vec<Vec2> = self.footprint().into_iter().map(&to_world).collect();
+        let min = cells.iter().copied().fold(Vec2::MAX, Vec2::min);
+        let max = cells.iter().copied().fold(Vec2::MIN, Vec2::max);
+        let (cols, rows) = self
+            .layout()
+            .0
+            .iter()
+            .fold((1, 1), |(w, h), &(dx, dy)| (w.max(dx + 1), h.max(dy + 1)));
+        let size = Vec2::new(
+            cols as f32 * CELL_SIZE - 16.0,
+            rows as f32 * CELL_SIZE - 16.0,
+        );
+        let turns = self.orientation().unwrap_or_default().quarter_turns();
+
+        let transform = Transform::from_translation(((min + max) / 2.0).extend(0.0)).with_rotation(
+            Quat::from_rotation_z(-(turns as f32) * std::f32::consts::FRAC_PI_2),
+        );
+        (size, transform)
+    }
+
     pub fn color(&self) -> Color {
         match self {
             LogicPiece::Assumption { .. } => Color::srgb(0.3, 0.8, 0.3), // Green
@@ -116,6 +234,42 @@ pub fn color(&self) -> Color {
     }
 }
 
+/// What the player may do with a pre-placed piece. Pieces the player
+/// places themselves are always movable and deletable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component, Serialize, Deserialize)]
+pub struct PieceFlags {
+    #[serde(default)]
+    pub movable: bool,
+    #[serde(default)]
+    pub deletable: bool,
+}
+
+impl PieceFlags {
+    /// Fixed in place; the default for level pieces.
+    pub const LOCKED: Self = Self {
+        movable: false,
+        deletable: false,
+    };
+    /// Free to move and delete, like a player-placed piece.
+    pub const FREE: Self = Self {
+        movable: true,
+        deletable: true,
+    };
+
+    /// Whether the player can neither move nor delete the piece.
+    pub fn is_locked(&self) -> bool {
+        !self.movable && !self.deletable
+    }
+}
+
+/// Flags for the pre-placed piece at a board position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlaggedPiece {
+    pub position: (u32, u32),
+    #[serde(flatten)]
+    pub flags: PieceFlags,
+}
+
 /// Visual representation bundle for pieces
 #[derive(Bundle)]
 pub struct PieceBundle {
@@ -125,18 +279,73 @@ pub struct PieceBundle {
 }
 
 impl PieceBundle {
-    pub fn new(piece: LogicPiece, _asset_server: &AssetServer) -> Self {
-        let (x, y) = piece.position();
+    pub fn new(piece: LogicPiece) -> Self {
         let color = piece.color();
+        let (size, transform) = piece.tile(|(x, y)| Vec2::new(x as f32, y as f32) * CELL_SIZE);
 
         Self {
             piece,
             sprite: Sprite {
                 color,
-                custom_size: Some(Vec2::new(64.0, 64.0)),
+                custom_size: Some(size),
                 ..default()
             },
-            transform: Transform::from_xyz(x as f32 * 80.0, y as f32 * 80.0, 0.0),
+            transform,
         }
     }
 }
+
+/// Shorten `label` to at most `max_chars` characters, ending it with an
+/// ellipsis if anything was cut. Returns the text and whether it was cut.
+pub fn fit_label(label: &str, max_chars: usize) -> (String, bool) {
+    if label.chars().count() <= max_chars {
+        return (label.to_string(), false);
+    }
+    let kept: String = label.chars().take(max_chars.saturating_sub(1)).collect();
+    let text = if max_chars == 0 { kept } else { kept + "…" };
+    (text, true)
+}
+
+/// Rotation asked for this frame: `Some(true)` to turn a gate clockwise
+/// ([`Action::TurnClockwise`], or scrolling down), `Some(false)` to turn it
+/// back ([`Action::TurnBack`], Shift with the clockwise binding, or
+/// scrolling up). Scrolling with Ctrl held zooms instead.
+pub fn rotation_input(
+    actions: &ActionState,
+    keyboard: &ButtonInput<KeyCode>,
+    scroll: &AccumulatedMouseScroll,
+) -> Option<bool> {
+    if actions.just_pressed(Action::TurnClockwise) {
+        Some(!keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]))
+    } else if actions.just_pressed(Action::TurnBack) {
+        Some(false)
+    } else if scroll.delta.y != 0.0 && !zoom_held(keyboard) {
+        Some(scroll.delta.y < 0.0)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_board_labels() {
+        let goal = LogicPiece::Goal {
+            formula: "(and P (not Q))".to_string(),
+            position: (0, 0),
+        };
+        assert_eq!(goal.board_label(), "◎ P ∧ ¬Q");
+        let forall = LogicPiece::ForallIntro {
+            position: (0, 0),
+            variable: "x".to_string(),
+            orientation: Direction::East,
+        };
+        assert_eq!(forall.board_label(), "∀x");
+
+        assert_eq!(fit_label("∀x", 5), ("∀x".to_string(), false));
+        assert_eq!(fit_label("◎ P ∧ ¬Q", 5), ("◎ P …".to_string(), true));
+        assert_eq!(fit_label("P", 0), (String::new(), true));
+    }
+}
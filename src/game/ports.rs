@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Piece footprints and typed ports.
+//!
+//! Every piece covers a footprint of cells anchored at its `position`, and
+//! exposes named ports on the sides of those cells. Signal leaves a piece
+//! through its output ports and enters through its inputs. Two pieces are
+//! connected when an output and an input port face each other across a
+//! cell edge, or when a wire runs between the cells just outside them
+//! (the port's "attach" cell).
+//!
+//! Layouts are fixed per piece kind and drawn facing east, with inputs on
+//! the west side and the output on the east. A gate's orientation turns
+//! its layout in quarter turns about the anchor, which stays the
+//! bottom-left cell of the footprint. Gates saved before gates could
+//! turn face east. Packs saved before footprints existed had one-cell
+//! gates; AND, OR and IMPLIES now cover two cells, so loading such a pack
+//! moves any that no longer fit (see [`crate::levels::LevelPack::upgrade`]).
+
+use serde::{Deserialize, Serialize};
+
+use super::LogicPiece;
+
+/// A side of a cell. Y grows upward, so north is `+y`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    North,
+    #[default]
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Quarter turns clockwise from east.
+    pub fn quarter_turns(self) -> u8 {
+        match self {
+            Self::East => 0,
+            Self::South => 1,
+            Self::West => 2,
+            Self::North => 3,
+        }
+    }
+
+    /// The side a quarter turn clockwise from this one.
+    pub fn clockwise(self) -> Self {
+        match self {
+            Self::North => Self::East,
+            Self::East => Self::South,
+            Self::South => Self::West,
+            Self::West => Self::North,
+        }
+    }
+
+    /// The side a quarter turn counter-clockwise from this one.
+    pub fn counter_clockwise(self) -> Self {
+        self.clockwise().opposite()
+    }
+
+    /// The side facing the other way.
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
+
+    /// The neighbouring cell on this side, if it has non-negative
+    /// coordinates.
+    pub fn step(self, (x, y): (u32, u32)) -> Option<(u32, u32)> {
+        match self {
+            Self::North => Some((x, y.checked_add(1)?)),
+            Self::East => Some((x.checked_add(1)?, y)),
+            Self::South => Some((x, y.checked_sub(1)?)),
+            Self::West => Some((x.checked_sub(1)?, y)),
+        }
+    }
+}
+
+/// Whether signal enters or leaves a piece through a port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PortKind {
+    Input,
+    Output,
+}
+
+/// A port of a piece kind, relative to the piece's anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortSpec {
+    pub name: &'static str,
+    pub kind: PortKind,
+    /// Footprint cell the port sits on, as an offset from the anchor
+    pub offset: (u32, u32),
+    /// Side of that cell the port faces
+    pub side: Direction,
+}
+
+/// A port of a placed piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Port {
+    pub name: &'static str,
+    pub kind: PortKind,
+    /// Board cell the port sits on
+    pub cell: (u32, u32),
+    /// Side of that cell the port faces
+    pub side: Direction,
+}
+
+impl Port {
+    /// The cell just outside the port, where a wire end attaches.
+    pub fn attach(&self) -> Option<(u32, u32)> {
+        self.side.step(self.cell)
+    }
+
+    /// Whether this output feeds `input` directly, the two facing each
+    /// other across a shared cell edge.
+    pub fn faces(&self, input: &Port) -> bool {
+        self.kind == PortKind::Output
+            && input.kind == PortKind::Input
+            && self.attach() == Some(input.cell)
+            && input.attach() == Some(self.cell)
+    }
+}
+
+const fn input(name: &'static str, offset: (u32, u32), side: Direction) -> PortSpec {
+    PortSpec {
+        name,
+        kind: PortKind::Input,
+        offset,
+        side,
+    }
+}
+
+const fn output(name: &'static str, offset: (u32, u32), side: Direction) -> PortSpec {
+    PortSpec {
+        name,
+        kind: PortKind::Output,
+        offset,
+        side,
+    }
+}
+
+const SINGLE: &[(u32, u32)] = &[(0, 0)];
+/// Two cells stacked vertically, for gates with two inputs
+const TALL: &[(u32, u32)] = &[(0, 0), (0, 1)];
+
+const SOURCE_PORTS: &[PortSpec] = &[output("out", (0, 0), Direction::East)];
+const SINK_PORTS: &[PortSpec] = &[input("in", (0, 0), Direction::West)];
+const BINARY_PORTS: &[PortSpec] = &[
+    input("left", (0, 1), Direction::West),
+    input("right", (0, 0), Direction::West),
+    output("out", (0, 0), Direction::East),
+];
+const IMPLIES_PORTS: &[PortSpec] = &[
+    input("antecedent", (0, 1), Direction::West),
+    input("consequent", (0, 0), Direction::West),
+    output("out", (0, 0), Direction::East),
+];
+const UNARY_PORTS: &[PortSpec] = &[
+    input("in", (0, 0), Direction::West),
+    output("out", (0, 0), Direction::East),
+];
+const QUANTIFIER_PORTS: &[PortSpec] = &[
+    input("body", (0, 0), Direction::West),
+    output("out", (0, 0), Direction::East),
+];
+
+impl LogicPiece {
+    /// Footprint offsets and port layout of this kind of piece, facing
+    /// east. Wires have no ports: they join the ports of other pieces.
+    pub fn layout(&self) -> (&'static [(u32, u32)], &'static [PortSpec]) {
+        match self {
+            Self::Assumption { .. } => (SINGLE, SOURCE_PORTS),
+            Self::Goal { .. } => (SINGLE, SINK_PORTS),
+            Self::AndIntro { .. } | Self::OrIntro { .. } => (TALL, BINARY_PORTS),
+            Self::ImpliesIntro { .. } => (TALL, IMPLIES_PORTS),
+            Self::NotIntro { .. } => (SINGLE, UNARY_PORTS),
+            Self::ForallIntro { .. } | Self::ExistsIntro { .. } => (SINGLE, QUANTIFIER_PORTS),
+            Self::Wire { .. } => (SINGLE, &[]),
+        }
+    }
+
+    /// Footprint offsets and port layout turned to the piece's
+    /// orientation, shifted so the offsets stay non-negative.
+    pub fn oriented_layout(&self) -> (Vec<(u32, u32)>, Vec<PortSpec>) {
+        let (cells, specs) = self.layout();
+        let turns = self.orientation().unwrap_or_default().quarter_turns();
+        let turn =
+            |(dx, dy): (u32, u32)| (0..turns).fold((dx as i64, dy as i64), |(x, y), _| (y, -x));
+        let turned: Vec<(i64, i64)> = cells.iter().map(|&c| turn(c)).collect();
+        let min_x = turned.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = turned.iter().map(|c| c.1).min().unwrap_or(0);
+        let shift = |(x, y): (i64, i64)| ((x - min_x) as u32, (y - min_y) as u32);
+
+        let footprint = turned.into_iter().map(shift).collect();
+        let ports = specs
+            .iter()
+            .map(|spec| PortSpec {
+                offset: shift(turn(spec.offset)),
+                side: (0..turns).fold(spec.side, |side, _| side.clockwise()),
+                ..*spec
+            })
+            .collect();
+        (footprint, ports)
+    }
+
+    /// Board cells covered by the piece. A wire covers its `from` cell.
+    pub fn footprint(&self) -> Vec<(u32, u32)> {
+        let (x, y) = self.position();
+        self.oriented_layout()
+            .0
+            .into_iter()
+            .map(|(dx, dy)| (x.saturating_add(dx), y.saturating_add(dy)))
+            .collect()
+    }
+
+    /// Whether the piece covers `cell`.
+    pub fn occupies(&self, cell: (u32, u32)) -> bool {
+        self.footprint().contains(&cell)
+    }
+
+    /// The piece's ports, placed on the board.
+    pub fn ports(&self) -> Vec<Port> {
+        let (x, y) = self.position();
+        self.oriented_layout()
+            .1
+            .iter()
+            .map(|spec| Port {
+                name: spec.name,
+                kind: spec.kind,
+                cell: (
+                    x.saturating_add(spec.offset.0),
+                    y.saturating_add(spec.offset.1),
+                ),
+                side: spec.side,
+            })
+            .collect()
+    }
+
+    /// The port called `name`, if the piece has one.
+    pub fn port(&self, name: &str) -> Option<Port> {
+        self.ports().into_iter().find(|p| p.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate_ports() {
+        let and = LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        assert_eq!(and.footprint(), vec![(4, 4), (4, 5)]);
+        let inputs: Vec<_> = and
+            .ports()
+            .into_iter()
+            .filter(|p| p.kind == PortKind::Input)
+            .map(|p| (p.name, p.attach()))
+            .collect();
+        assert_eq!(
+            inputs,
+            vec![("left", Some((3, 5))), ("right", Some((3, 4)))]
+        );
+        assert_eq!(and.port("out").unwrap().attach(), Some((5, 4)));
+
+        let implies = LogicPiece::ImpliesIntro {
+            position: (0, 0),
+            orientation: Direction::East,
+        };
+        assert!(implies.port("antecedent").is_some());
+        assert_eq!(implies.port("consequent").unwrap().attach(), None);
+
+        let not = LogicPiece::NotIntro {
+            position: (2, 2),
+            orientation: Direction::East,
+        };
+        assert_eq!(not.footprint(), vec![(2, 2)]);
+        assert_eq!(not.ports().len(), 2);
+    }
+
+    #[test]
+    fn test_facing_ports_connect() {
+        let p = LogicPiece::Assumption {
+            formula: "P".to_string(),
+            position: (3, 5),
+        };
+        let and = LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        let out = p.port("out").unwrap();
+        assert!(out.faces(&and.port("left").unwrap()));
+        assert!(!out.faces(&and.port("right").unwrap()));
+        assert!(!and.port("left").unwrap().faces(&out));
+    }
+
+    #[test]
+    fn test_orientation_turns_layout() {
+        let north = LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::North,
+        };
+        assert_eq!(north.footprint(), vec![(5, 4), (4, 4)]);
+        assert_eq!(north.port("out").unwrap().attach(), Some((5, 5)));
+        assert_eq!(north.port("left").unwrap().attach(), Some((4, 3)));
+        assert_eq!(north.port("right").unwrap().attach(), Some((5, 3)));
+
+        let west = LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::West,
+        };
+        assert_eq!(west.footprint(), vec![(4, 5), (4, 4)]);
+        assert_eq!(west.port("out").unwrap().attach(), Some((3, 5)));
+        assert_eq!(west.port("left").unwrap().attach(), Some((5, 4)));
+
+        // Gates saved before orientation existed face east
+        let old: LogicPiece = serde_json::from_str(r#"{"NotIntro":{"position":[1,2]}}"#).unwrap();
+        assert_eq!(old.orientation(), Some(Direction::East));
+        assert_eq!(Direction::West.clockwise(), Direction::North);
+        assert_eq!(Direction::West.counter_clockwise(), Direction::South);
+    }
+}
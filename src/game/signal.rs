@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Live signal evaluation.
+//!
+//! Works out what each piece of a circuit carries, using the port
+//! semantics the verifier relies on (see [`crate::verification`]) but no
+//! solver. Gates are evaluated in dependency order: a gate is read once,
+//! after everything feeding it has been, so the work grows with the number
+//! of connections rather than the number of paths through them. Pieces on
+//! or past a loop are never ready and carry nothing, since a circular
+//! argument proves nothing. Formulas are capped at [`MAX_FORMULA_LEN`]:
+//! a gate that reads the same formula on both inputs doubles it, so a
+//! chain of them would otherwise grow the text exponentially.
+
+use std::collections::{HashMap, VecDeque};
+use std::iter::Peekable;
+
+use super::graph::ConnectionGraph;
+use super::ports::{Port, PortKind};
+use super::LogicPiece;
+
+/// Longest formula a piece can carry, in bytes. Anything longer carries
+/// nothing; no level needs formulas anywhere near this size.
+pub const MAX_FORMULA_LEN: usize = 4096;
+
+/// The formula leaving each node of `graph`, indexed like its nodes.
+/// Goals have no output and always derive `None`, as does a node whose
+/// formula would be longer than [`MAX_FORMULA_LEN`].
+pub fn derive(graph: &ConnectionGraph) -> Vec<Option<String>> {
+    let count = graph.nodes.len();
+    let mut waiting = vec![0usize; count];
+    for i in 0..count {
+        for &j in graph.downstream(i) {
+            waiting[j] += 1;
+        }
+    }
+
+    let mut derived = vec![None; count];
+    let mut ready: VecDeque<usize> = (0..count).filter(|&i| waiting[i] == 0).collect();
+    while let Some(i) = ready.pop_front() {
+        derived[i] = output(graph, i, &derived).filter(|f| f.len() <= MAX_FORMULA_LEN);
+        for &j in graph.downstream(i) {
+            waiting[j] -= 1;
+            if waiting[j] == 0 {
+                ready.push_back(j);
+            }
+        }
+    }
+    derived
+}
+
+/// The formula arriving at input port `input` of node `index`, given what
+/// every node derives.
+pub fn arriving(
+    graph: &ConnectionGraph,
+    derived: &[Option<String>],
+    index: usize,
+    input: &str,
+) -> Option<String> {
+    graph
+        .sources(index, input)
+        .find_map(|link| derived[link.from].clone())
+}
+
+/// The formula leaving node `index`, from what its sources derive.
+fn output(graph: &ConnectionGraph, index: usize, derived: &[Option<String>]) -> Option<String> {
+    let input = |name| arriving(graph, derived, index, name);
+    match graph.nodes[index] {
+        LogicPiece::Assumption { formula, .. } => Some(formula.clone()),
+        LogicPiece::AndIntro { .. } => {
+            Some(format!("(and {} {})", input("left")?, input("right")?))
+        }
+        LogicPiece::OrIntro { .. } => match (input("left"), input("right")) {
+            (Some(left), Some(right)) => Some(format!("(or {} {})", left, right)),
+            // Either side alone proves the disjunction
+            (Some(side), None) | (None, Some(side)) => Some(side),
+            (None, None) => None,
+        },
+        LogicPiece::ImpliesIntro { .. } => Some(format!(
+            "(=> {} {})",
+            input("antecedent")?,
+            input("consequent")?
+        )),
+        LogicPiece::NotIntro { .. } => Some(format!("(not {})", input("in")?)),
+        LogicPiece::ForallIntro { .. } | LogicPiece::ExistsIntro { .. } => input("body"),
+        LogicPiece::Goal { .. } | LogicPiece::Wire { .. } => None,
+    }
+}
+
+/// What every piece of a board carries right now.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CircuitState {
+    /// Indexed like the piece list: the formula leaving an assumption or
+    /// gate, arriving at a goal, or running along a wire. `None` where
+    /// nothing is carried.
+    pub signals: Vec<Option<String>>,
+    /// Number of goals on the board
+    pub goals: usize,
+    /// Number of goals with a formula arriving
+    pub goals_fed: usize,
+}
+
+impl CircuitState {
+    /// Evaluate a piece list.
+    pub fn evaluate(pieces: &[LogicPiece]) -> Self {
+        let graph = ConnectionGraph::from_pieces(pieces);
+        let derived = derive(&graph);
+        let mut state = Self {
+            signals: vec![None; pieces.len()],
+            ..Self::default()
+        };
+
+        let nodes = pieces
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !matches!(p, LogicPiece::Wire { .. }))
+            .map(|(i, _)| i);
+        for (node, i) in nodes.enumerate() {
+            state.signals[i] = match pieces[i] {
+                LogicPiece::Goal { .. } => {
+                    state.goals += 1;
+                    let fed = arriving(&graph, &derived, node, "in");
+                    state.goals_fed += usize::from(fed.is_some());
+                    fed
+                }
+                _ => derived[node].clone(),
+            };
+        }
+
+        // A wire carries what leaves the output at one end, as long as an
+        // input of another piece sits at the other end
+        let mut attached: HashMap<(u32, u32), Vec<(usize, Port)>> = HashMap::new();
+        for (node, piece) in graph.nodes.iter().enumerate() {
+            for port in piece.ports() {
+                if let Some(cell) = port.attach() {
+                    attached.entry(cell).or_default().push((node, port));
+                }
+            }
+        }
+        let ends = |cell| attached.get(cell).map(Vec::as_slice).unwrap_or(&[]);
+        for (i, piece) in pieces.iter().enumerate() {
+            let LogicPiece::Wire { from, to } = piece else {
+                continue;
+            };
+            state.signals[i] = [(from, to), (to, from)].iter().find_map(|&(out, input)| {
+                ends(out)
+                    .iter()
+                    .filter(|(_, p)| p.kind == PortKind::Output)
+                    .filter(|(node, _)| {
+                        ends(input)
+                            .iter()
+                            .any(|(other, p)| p.kind == PortKind::Input && other != node)
+                    })
+                    .find_map(|(node, _)| derived[*node].clone())
+            });
+        }
+        state
+    }
+
+    /// Whether piece `index` carries a formula.
+    pub fn carries(&self, index: usize) -> bool {
+        self.signals.get(index).is_some_and(Option::is_some)
+    }
+
+    /// Whether every goal has a formula arriving. The verifier still has
+    /// to check that the formula proves the goal.
+    pub fn goal_reached(&self) -> bool {
+        self.goals > 0 && self.goals_fed == self.goals
+    }
+}
+
+/// Render an SMT-LIB2 formula with logic symbols, e.g. `(and P (not Q))`
+/// as `P ∧ ¬Q`, for labels. Text that doesn't parse is returned as is.
+pub fn pretty(formula: &str) -> String {
+    let spaced = formula.replace('(', " ( ").replace(')', " ) ");
+    let mut tokens = spaced.split_whitespace().peekable();
+    match pretty_term(&mut tokens, false) {
+        Some(text) if tokens.next().is_none() => text,
+        _ => formula.to_string(),
+    }
+}
+
+/// One term from `tokens`, parenthesised if it is compound and `nested`.
+fn pretty_term<'a, I>(tokens: &mut Peekable<I>, nested: bool) -> Option<String>
+where
+    I: Iterator<Item = &'a str>,
+{
+    match tokens.next()? {
+        "(" => {}
+        ")" => return None,
+        atom => return Some(atom.to_string()),
+    }
+    let op = tokens.next()?;
+    let mut args = Vec::new();
+    while *tokens.peek()? != ")" {
+        args.push(pretty_term(tokens, true)?);
+    }
+    tokens.next();
+    let text = match (op, args.as_slice()) {
+        ("not", [arg]) => return Some(format!("¬{}", arg)),
+        ("and", [_, _, ..]) => args.join(" ∧ "),
+        ("or", [_, _, ..]) => args.join(" ∨ "),
+        ("=>", [a, c]) => format!("{} → {}", a, c),
+        _ => format!("{} {}", op, args.join(" ")),
+    };
+    Some(if nested { format!("({})", text) } else { text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+
+    fn assumption(formula: &str, position: (u32, u32)) -> LogicPiece {
+        LogicPiece::Assumption {
+            formula: formula.to_string(),
+            position,
+        }
+    }
+
+    fn goal(formula: &str, position: (u32, u32)) -> LogicPiece {
+        LogicPiece::Goal {
+            formula: formula.to_string(),
+            position,
+        }
+    }
+
+    #[test]
+    fn test_signal_follows_the_circuit() {
+        let mut pieces = vec![
+            assumption("P", (3, 5)),
+            LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            },
+            LogicPiece::Wire {
+                from: (5, 4),
+                to: (8, 4),
+            },
+            goal("(and P Q)", (9, 4)),
+        ];
+        let state = CircuitState::evaluate(&pieces);
+        assert_eq!(state.signals[0].as_deref(), Some("P"));
+        assert!(!state.carries(1) && !state.carries(2));
+        assert_eq!((state.goals, state.goals_fed), (1, 0));
+
+        pieces.push(assumption("Q", (3, 4)));
+        let state = CircuitState::evaluate(&pieces);
+        assert_eq!(state.signals[1].as_deref(), Some("(and P Q)"));
+        assert_eq!(state.signals[2].as_deref(), Some("(and P Q)"));
+        assert_eq!(state.signals[3].as_deref(), Some("(and P Q)"));
+        assert!(state.goal_reached());
+    }
+
+    #[test]
+    fn test_loops_carry_nothing() {
+        // Two NOTs feeding each other through wires
+        let pieces = vec![
+            LogicPiece::NotIntro {
+                position: (2, 2),
+                orientation: Direction::East,
+            },
+            LogicPiece::NotIntro {
+                position: (6, 2),
+                orientation: Direction::West,
+            },
+            LogicPiece::Wire {
+                from: (3, 2),
+                to: (7, 2),
+            },
+            LogicPiece::Wire {
+                from: (5, 2),
+                to: (1, 2),
+            },
+        ];
+        let state = CircuitState::evaluate(&pieces);
+        assert!(state.signals.iter().all(Option::is_none));
+        assert!(!state.goal_reached());
+    }
+
+    #[test]
+    fn test_loop_of_or_gates_is_quick() {
+        // Each OR takes both inputs from the one before it, and the last
+        // feeds the first, so every path doubles at every gate
+        let count = 30;
+        let out = |i: u32| (3 * i + 2, 0);
+        let mut pieces = vec![];
+        for i in 0..count {
+            pieces.push(LogicPiece::OrIntro {
+                position: (3 * i + 1, 0),
+                orientation: Direction::East,
+            });
+            let (x, y) = out((i + count - 1) % count);
+            let input = 3 * i;
+            pieces.push(LogicPiece::Wire {
+                from: (x, y),
+                to: (input, 0),
+            });
+            pieces.push(LogicPiece::Wire {
+                from: (input, 1),
+                to: (x, y),
+            });
+        }
+
+        let graph = ConnectionGraph::from_pieces(&pieces);
+        assert!((0..count as usize).all(|i| graph.downstream(i) == [(i + 1) % count as usize]));
+
+        let start = std::time::Instant::now();
+        let state = CircuitState::evaluate(&pieces);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert!(state.signals.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_chain_of_doubling_gates_is_capped() {
+        // Each AND takes both inputs from the one before it, the first
+        // from P, so its formula doubles at every gate
+        let count = 40;
+        let out = |i: u32| (3 * i + 2, 0);
+        let mut pieces = vec![assumption("P", (0, 5))];
+        for i in 0..count {
+            pieces.push(LogicPiece::AndIntro {
+                position: (3 * i + 1, 0),
+                orientation: Direction::East,
+            });
+            let source = if i == 0 { (1, 5) } else { out(i - 1) };
+            let input = 3 * i;
+            pieces.push(LogicPiece::Wire {
+                from: source,
+                to: (input, 0),
+            });
+            pieces.push(LogicPiece::Wire {
+                from: (input, 1),
+                to: source,
+            });
+        }
+        pieces.push(goal("P", (3 * count + 3, 0)));
+        pieces.push(LogicPiece::Wire {
+            from: out(count - 1),
+            to: (3 * count + 2, 0),
+        });
+
+        let start = std::time::Instant::now();
+        let state = CircuitState::evaluate(&pieces);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(state.signals[1].as_deref(), Some("(and P P)"));
+        assert!(!state.carries(1 + 3 * (count as usize - 1)));
+        assert_eq!((state.goals, state.goals_fed), (1, 0));
+    }
+
+    #[test]
+    fn test_pretty_formulas() {
+        assert_eq!(pretty("P"), "P");
+        assert_eq!(pretty("(and P (not Q))"), "P ∧ ¬Q");
+        assert_eq!(pretty("(=> (or P Q) R)"), "(P ∨ Q) → R");
+        assert_eq!(pretty("(and P"), "(and P");
+    }
+}
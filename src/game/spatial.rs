@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Spatial index over a board's pieces.
+//!
+//! Maps each cell to the pieces whose footprint covers it, so point
+//! and area queries don't have to scan the whole piece list. The index is
+//! derived data: it is never serialized, is ignored when boards are
+//! compared, and is built the first time a board is queried.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::LogicPiece;
+
+/// Piece indices bucketed by the cells they cover.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    cells: HashMap<(u32, u32), Vec<usize>>,
+    len: usize,
+}
+
+impl SpatialIndex {
+    /// Index a piece list.
+    pub fn build(pieces: &[LogicPiece]) -> Self {
+        let mut index = Self::default();
+        for (i, piece) in pieces.iter().enumerate() {
+            index.insert(i, piece);
+        }
+        index
+    }
+
+    /// Number of pieces indexed.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no pieces are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Indices of the pieces covering `cell`, in board order.
+    pub fn at(&self, cell: (u32, u32)) -> &[usize] {
+        self.cells.get(&cell).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Record `piece` as piece number `index`. Indices must be added in
+    /// increasing order, as pushing onto the piece list does.
+    pub fn insert(&mut self, index: usize, piece: &LogicPiece) {
+        for cell in piece.footprint() {
+            let bucket = self.cells.entry(cell).or_default();
+            if bucket.last() != Some(&index) {
+                bucket.push(index);
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Forget piece `index`, which covered `cells`, and shift the pieces
+    /// after it down one, as removing from the piece list does.
+    pub fn remove(&mut self, index: usize, cells: &[(u32, u32)]) {
+        for cell in cells {
+            if let Some(bucket) = self.cells.get_mut(cell) {
+                bucket.retain(|&i| i != index);
+                if bucket.is_empty() {
+                    self.cells.remove(cell);
+                }
+            }
+        }
+        for bucket in self.cells.values_mut() {
+            for i in bucket.iter_mut().filter(|i| **i > index) {
+                *i -= 1;
+            }
+        }
+        self.len -= 1;
+    }
+
+    /// Record that piece `index` moved from covering `from` to `to`.
+    pub fn relocate(&mut self, index: usize, from: &[(u32, u32)], to: &[(u32, u32)]) {
+        for cell in from {
+            if let Some(bucket) = self.cells.get_mut(cell) {
+                bucket.retain(|&i| i != index);
+                if bucket.is_empty() {
+                    self.cells.remove(cell);
+                }
+            }
+        }
+        for &cell in to {
+            let bucket = self.cells.entry(cell).or_default();
+            if let Err(at) = bucket.binary_search(&index) {
+                bucket.insert(at, index);
+            }
+        }
+    }
+
+    /// Indices of the pieces within Chebyshev distance `radius` of
+    /// `(x, y)`, in board order. Large windows fall back to walking every
+    /// bucket, so the cost is bounded by the piece count either way.
+    pub fn within(&self, x: u32, y: u32, radius: u32) -> Vec<usize> {
+        let side = 2 * radius as u64 + 1;
+        let mut found: Vec<usize> = if side * side >= self.cells.len() as u64 {
+            self.cells
+                .iter()
+                .filter(|(&(px, py), _)| px.abs_diff(x) <= radius && py.abs_diff(y) <= radius)
+                .flat_map(|(_, bucket)| bucket.iter().copied())
+                .collect()
+        } else {
+            let mut found = Vec::new();
+            for py in y.saturating_sub(radius)..=y.saturating_add(radius) {
+                for px in x.saturating_sub(radius)..=x.saturating_add(radius) {
+                    found.extend_from_slice(self.at((px, py)));
+                }
+            }
+            found
+        };
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+/// Lazily built [`SpatialIndex`] stored on a board. Boards compare equal
+/// regardless of whether their index has been built yet.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialCache(OnceLock<SpatialIndex>);
+
+impl PartialEq for SpatialCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl SpatialCache {
+    /// The index for `pieces`, building it on first use.
+    pub fn get(&self, pieces: &[LogicPiece]) -> &SpatialIndex {
+        let index = self.0.get_or_init(|| SpatialIndex::build(pieces));
+        debug_assert_eq!(
+            index.len(),
+            pieces.len(),
+            "board pieces were changed without updating the spatial index"
+        );
+        index
+    }
+
+    /// The index, if it has been built, for updating in place.
+    pub(crate) fn get_mut(&mut self) -> Option<&mut SpatialIndex> {
+        self.0.get_mut()
+    }
+
+    /// Drop the index so the next query rebuilds it.
+    pub(crate) fn invalidate(&mut self) {
+        self.0.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+
+    #[test]
+    fn test_index_buckets_and_windows() {
+        let pieces = vec![
+            LogicPiece::AndIntro {
+                position: (5, 5),
+                orientation: Direction::East,
+            },
+            LogicPiece::NotIntro {
+                position: (9, 9),
+                orientation: Direction::East,
+            },
+            LogicPiece::NotIntro {
+                position: (5, 6),
+                orientation: Direction::East,
+            },
+            LogicPiece::Wire {
+                from: (6, 4),
+                to: (9, 9),
+            },
+        ];
+        let mut index = SpatialIndex::build(&pieces);
+        assert_eq!(index.at((5, 6)), &[0, 2]);
+        assert!(index.at((0, 0)).is_empty());
+        assert_eq!(index.within(5, 4, 1), vec![0, 3]);
+        assert_eq!(index.within(0, 0, 100), vec![0, 1, 2, 3]);
+
+        index.relocate(2, &[(5, 6)], &[(9, 9)]);
+        assert_eq!(index.at((5, 6)), &[0]);
+        assert_eq!(index.at((9, 9)), &[1, 2]);
+
+        index.remove(1, &[(9, 9)]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.at((9, 9)), &[1]);
+        assert_eq!(index.at((6, 4)), &[2]);
+    }
+}
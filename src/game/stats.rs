@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Per-level play history and daily streaks, recorded into `PlayerStats`
+//! and saved with the player's profile.
+
+use serde::{Deserialize, Serialize};
+
+use super::{LevelKey, PlayerStats};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Today's date as whole days since the Unix epoch (UTC)
+pub fn current_day() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECS_PER_DAY)
+        .unwrap_or(0)
+}
+
+/// What happened on one level across all of a player's attempts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelHistory {
+    /// Fresh starts, including resets
+    pub attempts: u32,
+    /// Times the verifier rejected a submitted board
+    pub verification_failures: u32,
+    /// Time taken by each solve, in seconds
+    pub solve_times_secs: Vec<u64>,
+    /// Pieces the player placed in each solve
+    pub pieces_used: Vec<u32>,
+}
+
+impl LevelHistory {
+    /// Number of times the level was solved
+    pub fn solves(&self) -> usize {
+        self.solve_times_secs.len()
+    }
+
+    /// Fastest solve
+    pub fn best_time(&self) -> Option<u64> {
+        self.solve_times_secs.iter().copied().min()
+    }
+
+    /// Slowest solve
+    pub fn worst_time(&self) -> Option<u64> {
+        self.solve_times_secs.iter().copied().max()
+    }
+
+    /// Median solve time (the lower middle for an even count)
+    pub fn median_time(&self) -> Option<u64> {
+        let mut times = self.solve_times_secs.clone();
+        times.sort_unstable();
+        times.get(times.len().checked_sub(1)? / 2).copied()
+    }
+
+    /// Fewest pieces placed in a solve
+    pub fn fewest_pieces(&self) -> Option<u32> {
+        self.pieces_used.iter().copied().min()
+    }
+}
+
+impl PlayerStats {
+    /// History for a level, if it has ever been played
+    pub fn level_history(&self, pack_id: &str, level_id: u32) -> Option<&LevelHistory> {
+        self.levels.get(pack_id)?.get(&level_id)
+    }
+
+    fn history_mut(&mut self, key: &LevelKey) -> &mut LevelHistory {
+        self.levels
+            .entry(key.pack_id.clone())
+            .or_default()
+            .entry(key.level_id)
+            .or_default()
+    }
+
+    /// Count a fresh start of a level
+    pub fn record_attempt(&mut self, key: &LevelKey) {
+        self.history_mut(key).attempts += 1;
+    }
+
+    /// Count a board the verifier rejected
+    pub fn record_failure(&mut self, key: &LevelKey) {
+        self.history_mut(key).verification_failures += 1;
+    }
+
+    /// Record a solve timed by `complete_level`: totals, the level's
+    /// history (for pack levels) and the daily streak.
+    pub fn record_solve(&mut self, key: Option<&LevelKey>, pieces_used: u32, day: u64) {
+        self.proofs_completed += 1;
+        self.levels_completed += 1;
+        // Earlier sessions on this level were counted when they were paused
+        self.total_playtime_secs += self.last_level_time_secs.saturating_sub(self.resumed_secs);
+
+        if let Some(key) = key {
+            let time = self.last_level_time_secs;
+            let history = self.history_mut(key);
+            history.solve_times_secs.push(time);
+            history.pieces_used.push(pieces_used);
+        }
+
+        match self.last_solve_day {
+            Some(last) if last == day => {}
+            Some(last) if last + 1 == day => self.streak_days += 1,
+            _ => self.streak_days = 1,
+        }
+        self.last_solve_day = Some(day);
+        self.best_streak_days = self.best_streak_days.max(self.streak_days);
+    }
+
+    /// The streak as of `day`: broken if a whole day passed without a solve
+    pub fn current_streak(&self, day: u64) -> u32 {
+        match self.last_solve_day {
+            Some(last) if last + 1 >= day => self.streak_days,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(level_id: u32) -> LevelKey {
+        LevelKey {
+            pack_id: "tutorial".to_string(),
+            level_id,
+        }
+    }
+
+    #[test]
+    fn test_level_history_summarises_solves() {
+        let mut stats = PlayerStats::default();
+        stats.record_attempt(&key(1));
+        stats.record_failure(&key(1));
+        for (time, pieces) in [(40, 3), (20, 2), (90, 5)] {
+            stats.last_level_time_secs = time;
+            stats.record_solve(Some(&key(1)), pieces, 100);
+        }
+
+        let history = stats.level_history("tutorial", 1).unwrap();
+        assert_eq!(history.attempts, 1);
+        assert_eq!(history.verification_failures, 1);
+        assert_eq!(history.solves(), 3);
+        assert_eq!(history.best_time(), Some(20));
+        assert_eq!(history.median_time(), Some(40));
+        assert_eq!(history.worst_time(), Some(90));
+        assert_eq!(history.fewest_pieces(), Some(2));
+        assert_eq!(stats.proofs_completed, 3);
+        assert_eq!(stats.total_playtime_secs, 150);
+    }
+
+    #[test]
+    fn test_resumed_time_is_not_counted_twice() {
+        let mut stats = PlayerStats::default();
+        stats.resume_level(30);
+        stats.total_playtime_secs = 30;
+        stats.complete_level();
+        stats.last_level_time_secs = 45;
+        stats.record_solve(None, 1, 0);
+        assert_eq!(stats.total_playtime_secs, 45);
+    }
+
+    #[test]
+    fn test_daily_streaks() {
+        let mut stats = PlayerStats::default();
+        for day in [10, 10, 11, 12] {
+            stats.record_solve(None, 1, day);
+        }
+        assert_eq!(stats.streak_days, 3);
+        assert_eq!(stats.current_streak(13), 3);
+        assert_eq!(stats.current_streak(14), 0);
+
+        stats.record_solve(None, 1, 20);
+        assert_eq!(stats.streak_days, 1);
+        assert_eq!(stats.best_streak_days, 3);
+    }
+}
@@ -4,8 +4,27 @@
 //! Provides rules for validating piece placement, wire connections,
 //! and overall board state correctness before proof verification.
 
+use super::graph::ConnectionGraph;
 use super::{BoardState, GoalCondition, Level, LogicPiece};
 
+/// Which side of a gate is missing a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateSide {
+    /// No path from any assumption feeds the gate.
+    Input,
+    /// The gate feeds no path into any goal.
+    Output,
+}
+
+impl std::fmt::Display for GateSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Input => write!(f, "input"),
+            Self::Output => write!(f, "output"),
+        }
+    }
+}
+
 /// Validation error types for piece placement and board state.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValidationError {
@@ -18,6 +37,10 @@ pub enum ValidationError {
     },
     /// Two pieces occupy the same position.
     OverlappingPieces { position: (u32, u32) },
+    /// A piece sits on a blocked terrain cell.
+    OnObstacle { position: (u32, u32) },
+    /// A pre-placed piece the player may not touch was moved or removed.
+    LockedPieceChanged { position: (u32, u32) },
     /// Wire endpoints are invalid.
     InvalidWire {
         from: (u32, u32),
@@ -28,12 +51,152 @@ pub enum ValidationError {
     NoGoals,
     /// No assumptions defined on the board.
     NoAssumptions,
-    /// Gate has no inputs connected.
-    DisconnectedGate { position: (u32, u32) },
+    /// Gate has no inputs or no outputs connected.
+    DisconnectedGate {
+        position: (u32, u32),
+        side: GateSide,
+    },
     /// Goal has no path from assumptions.
-    UnreachableGoal { formula: String },
-    /// Formula syntax is invalid.
-    InvalidFormula { formula: String, reason: String },
+    UnreachableGoal {
+        formula: String,
+        position: (u32, u32),
+    },
+    /// Formula syntax is invalid. `position` is `None` for formulas that
+    /// are not attached to a piece (e.g. the level's goal condition).
+    InvalidFormula {
+        formula: String,
+        reason: String,
+        position: Option<(u32, u32)>,
+    },
+}
+
+impl ValidationError {
+    /// Board cell this error refers to, if it has one.
+    pub fn position(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::OutOfBounds { x, y, .. } => Some((*x, *y)),
+            Self::OverlappingPieces { position } => Some(*position),
+            Self::OnObstacle { position } => Some(*position),
+            Self::LockedPieceChanged { position } => Some(*position),
+            Self::InvalidWire { from, .. } => Some(*from),
+            Self::NoGoals | Self::NoAssumptions => None,
+            Self::DisconnectedGate { position, .. } => Some(*position),
+            Self::UnreachableGoal { position, .. } => Some(*position),
+            Self::InvalidFormula { position, .. } => *position,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds { x, y, max_x, max_y } => write!(
+                f,
+                "Piece at ({}, {}) is outside the board (max ({}, {}))",
+                x, y, max_x, max_y
+            ),
+            Self::OverlappingPieces { position } => {
+                write!(f, "Multiple pieces at ({}, {})", position.0, position.1)
+            }
+            Self::OnObstacle { position } => {
+                write!(
+                    f,
+                    "Piece at ({}, {}) is on an obstacle",
+                    position.0, position.1
+                )
+            }
+            Self::LockedPieceChanged { position } => write!(
+                f,
+                "Locked piece at ({}, {}) was moved or removed",
+                position.0, position.1
+            ),
+            Self::InvalidWire { from, to, reason } => write!(
+                f,
+                "Invalid wire ({}, {}) -> ({}, {}): {}",
+                from.0, from.1, to.0, to.1, reason
+            ),
+            Self::NoGoals => write!(f, "Board has no goals"),
+            Self::NoAssumptions => write!(f, "Board has no assumptions"),
+            Self::DisconnectedGate { position, side } => write!(
+                f,
+                "Gate at ({}, {}) has no connected {}",
+                position.0, position.1, side
+            ),
+            Self::UnreachableGoal { formula, position } => write!(
+                f,
+                "Goal '{}' at ({}, {}) cannot be reached from any assumption",
+                formula, position.0, position.1
+            ),
+            Self::InvalidFormula {
+                formula, reason, ..
+            } => write!(f, "Invalid formula '{}': {}", formula, reason),
+        }
+    }
+}
+
+/// Validation warning types: problems that don't block play but are
+/// probably mistakes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    /// Pre-placed gate that is not yet connected on one side.
+    DisconnectedGate {
+        position: (u32, u32),
+        side: GateSide,
+    },
+    /// A `ConnectNodes` goal endpoint lies outside the board.
+    GoalOutOfBounds {
+        position: (u32, u32),
+        max_x: u32,
+        max_y: u32,
+    },
+    /// `BuildProofTree` goal whose depth is trivially satisfied.
+    TrivialProofTree { depth: u32 },
+    /// Assumption whose formula does not appear in the level's theorem.
+    UnusedAssumption {
+        formula: String,
+        position: (u32, u32),
+    },
+}
+
+impl ValidationWarning {
+    /// Board cell this warning refers to, if it has one.
+    pub fn position(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::DisconnectedGate { position, .. } => Some(*position),
+            Self::GoalOutOfBounds { position, .. } => Some(*position),
+            Self::TrivialProofTree { .. } => None,
+            Self::UnusedAssumption { position, .. } => Some(*position),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DisconnectedGate { position, side } => write!(
+                f,
+                "Gate at ({}, {}) has no connected {} yet",
+                position.0, position.1, side
+            ),
+            Self::GoalOutOfBounds {
+                position,
+                max_x,
+                max_y,
+            } => write!(
+                f,
+                "Goal node ({}, {}) is outside board bounds (max ({}, {}))",
+                position.0, position.1, max_x, max_y
+            ),
+            Self::TrivialProofTree { depth } => {
+                write!(f, "Proof tree depth of {} is trivially satisfied", depth)
+            }
+            Self::UnusedAssumption { formula, position } => write!(
+                f,
+                "Assumption '{}' at ({}, {}) is not used by the theorem",
+                formula, position.0, position.1
+            ),
+        }
+    }
 }
 
 /// Result of board validation.
@@ -41,7 +204,7 @@ pub enum ValidationError {
 pub struct ValidationResult {
     pub is_valid: bool,
     pub errors: Vec<ValidationError>,
-    pub warnings: Vec<String>,
+    pub warnings: Vec<ValidationWarning>,
 }
 
 impl ValidationResult {
@@ -63,11 +226,41 @@ pub fn invalid(errors: Vec<ValidationError>) -> Self {
         }
     }
 
+    /// Create a result from collected diagnostics; valid iff `errors` is empty.
+    pub fn from_diagnostics(
+        errors: Vec<ValidationError>,
+        warnings: Vec<ValidationWarning>,
+    ) -> Self {
+        Self {
+            is_valid: errors.is_empty(),
+            errors,
+            warnings,
+        }
+    }
+
     /// Add a warning to the result.
-    pub fn with_warning(mut self, warning: String) -> Self {
+    pub fn with_warning(mut self, warning: ValidationWarning) -> Self {
         self.warnings.push(warning);
         self
     }
+
+    /// Errors and warnings that refer to the given board cell.
+    pub fn diagnostics_at(
+        &self,
+        position: (u32, u32),
+    ) -> (Vec<&ValidationError>, Vec<&ValidationWarning>) {
+        let errors = self
+            .errors
+            .iter()
+            .filter(|e| e.position() == Some(position))
+            .collect();
+        let warnings = self
+            .warnings
+            .iter()
+            .filter(|w| w.position() == Some(position))
+            .collect();
+        (errors, warnings)
+    }
 }
 
 /// Validate a piece placement on the board.
@@ -77,19 +270,22 @@ pub fn validate_piece_placement(
 ) -> Result<(), ValidationError> {
     let (x, y) = piece.position();
 
-    // Check bounds
-    if x >= board.width || y >= board.height {
-        return Err(ValidationError::OutOfBounds {
-            x,
-            y,
-            max_x: board.width - 1,
-            max_y: board.height - 1,
-        });
-    }
-
-    // Check for overlap
-    if board.is_occupied(x, y) {
-        return Err(ValidationError::OverlappingPieces { position: (x, y) });
+    // Every cell of the piece's footprint must be free and on the board
+    for (cx, cy) in piece.footprint() {
+        if !board.in_bounds(cx, cy) {
+            return Err(ValidationError::OutOfBounds {
+                x: cx,
+                y: cy,
+                max_x: board.width - 1,
+                max_y: board.height - 1,
+            });
+        }
+        if board.is_occupied(cx, cy) {
+            return Err(ValidationError::OverlappingPieces { position: (cx, cy) });
+        }
+        if board.is_obstacle(cx, cy) {
+            return Err(ValidationError::OnObstacle { position: (cx, cy) });
+        }
     }
 
     // Validate wire-specific rules
@@ -127,17 +323,19 @@ pub fn validate_piece_placement(
                 return Err(ValidationError::InvalidFormula {
                     formula: formula.clone(),
                     reason: "Formula cannot be empty".to_string(),
+                    position: Some((x, y)),
                 });
             }
             // Basic formula validation: must start with alphanumeric or parenthesis
             if !formula
                 .chars()
                 .next()
-                .map_or(false, |c| c.is_alphanumeric() || c == '(')
+                .is_some_and(|c| c.is_alphanumeric() || c == '(')
             {
                 return Err(ValidationError::InvalidFormula {
                     formula: formula.clone(),
                     reason: "Formula must start with identifier or parenthesis".to_string(),
+                    position: Some((x, y)),
                 });
             }
         }
@@ -147,15 +345,24 @@ pub fn validate_piece_placement(
     Ok(())
 }
 
-/// Validate the entire board state.
+/// Validate the entire board state, including connectivity.
 pub fn validate_board(board: &BoardState) -> ValidationResult {
+    let mut errors = check_structure(board);
+    errors.extend(check_connectivity(board));
+    ValidationResult::from_diagnostics(errors, Vec::new())
+}
+
+/// Structural checks: bounds, overlaps, and presence of assumptions/goals.
+fn check_structure(board: &BoardState) -> Vec<ValidationError> {
     let mut errors = Vec::new();
-    let mut warnings = Vec::new();
 
     // Check each piece for basic validity
-    for piece in &board.pieces {
-        let (x, y) = piece.position();
-        if x >= board.width || y >= board.height {
+    for piece in board.pieces() {
+        let outside = piece
+            .footprint()
+            .into_iter()
+            .find(|&(x, y)| !board.in_bounds(x, y));
+        if let Some((x, y)) = outside {
             errors.push(ValidationError::OutOfBounds {
                 x,
                 y,
@@ -165,24 +372,27 @@ pub fn validate_board(board: &BoardState) -> ValidationResult {
         }
     }
 
-    // Check for overlapping pieces
-    let mut positions: Vec<(u32, u32)> = Vec::new();
-    for piece in &board.pieces {
-        let pos = piece.position();
-        if positions.contains(&pos) {
-            errors.push(ValidationError::OverlappingPieces { position: pos });
-        } else {
-            positions.push(pos);
+    // Check for overlapping pieces (every piece after the first in a cell)
+    // and pieces on obstacles
+    let index = board.spatial();
+    for (i, piece) in board.pieces().iter().enumerate() {
+        for pos in piece.footprint() {
+            if index.at(pos).first() != Some(&i) {
+                errors.push(ValidationError::OverlappingPieces { position: pos });
+            }
+            if board.is_obstacle(pos.0, pos.1) {
+                errors.push(ValidationError::OnObstacle { position: pos });
+            }
         }
     }
 
     // Check for at least one assumption and one goal
     let has_assumptions = board
-        .pieces
+        .pieces()
         .iter()
         .any(|p| matches!(p, LogicPiece::Assumption { .. }));
     let has_goals = board
-        .pieces
+        .pieces()
         .iter()
         .any(|p| matches!(p, LogicPiece::Goal { .. }));
 
@@ -193,40 +403,109 @@ pub fn validate_board(board: &BoardState) -> ValidationResult {
         errors.push(ValidationError::NoGoals);
     }
 
-    // Check for disconnected gates (warning only)
-    for piece in &board.pieces {
-        if let LogicPiece::AndIntro { position }
-        | LogicPiece::OrIntro { position }
-        | LogicPiece::ImpliesIntro { position }
-        | LogicPiece::NotIntro { position } = piece
-        {
-            let nearby = board.pieces_near(position.0, position.1, 2);
-            let has_input = nearby.iter().any(|p| {
-                matches!(
-                    p,
-                    LogicPiece::Assumption { .. }
-                        | LogicPiece::AndIntro { .. }
-                        | LogicPiece::OrIntro { .. }
-                )
-            });
-            if !has_input {
-                warnings.push(format!(
-                    "Gate at ({}, {}) has no nearby input pieces",
-                    position.0, position.1
-                ));
+    errors
+}
+
+/// Connectivity checks over the board's connection graph: every wire end
+/// must be attached to a port, every input port of a gate (one side of an
+/// OR) must be fed from the assumptions, every gate must feed some goal,
+/// and every goal must be reachable from the assumptions.
+pub fn check_connectivity(board: &BoardState) -> Vec<ValidationError> {
+    let graph = ConnectionGraph::from_board(board);
+    let fed = graph.reachable_from_assumptions();
+    let feeds = graph.reaches_goal();
+    let mut errors = Vec::new();
+
+    for piece in board.pieces() {
+        if let LogicPiece::Wire { from, to } = piece {
+            let attached = |end: (u32, u32)| {
+                graph
+                    .nodes
+                    .iter()
+                    .any(|n| n.ports().iter().any(|p| p.attach() == Some(end)))
+            };
+            if !attached(*from) || !attached(*to) {
+                errors.push(ValidationError::InvalidWire {
+                    from: *from,
+                    to: *to,
+                    reason: "Wire end is not attached to a port".to_string(),
+                });
+            }
+        }
+    }
+
+    for (i, piece) in graph.nodes.iter().enumerate() {
+        match piece {
+            LogicPiece::Goal { formula, position } => {
+                if !fed[i] {
+                    errors.push(ValidationError::UnreachableGoal {
+                        formula: formula.clone(),
+                        position: *position,
+                    });
+                }
+            }
+            LogicPiece::Assumption { .. } => {}
+            gate => {
+                let position = gate.position();
+                if !fed[i] {
+                    errors.push(ValidationError::DisconnectedGate {
+                        position,
+                        side: GateSide::Input,
+                    });
+                }
+                if !feeds[i] {
+                    errors.push(ValidationError::DisconnectedGate {
+                        position,
+                        side: GateSide::Output,
+                    });
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// Check a submitted solution against the level's pre-placed pieces and
+/// terrain: locked pieces must be untouched, non-deletable pieces must
+/// still be on the board (movable ones anywhere), and nothing may sit on
+/// an obstacle.
+pub fn check_level_rules(level: &Level, pieces: &[LogicPiece]) -> Vec<ValidationError> {
+    let board = &level.initial_state;
+    let mut errors = Vec::new();
+    let mut unmatched: Vec<&LogicPiece> = pieces.iter().collect();
+
+    for original in board.pieces() {
+        let position = original.position();
+        let flags = board.flags_at(position);
+        if flags.deletable {
+            continue;
+        }
+        let found = unmatched.iter().position(|&p| {
+            p == original
+                || (flags.movable && !matches!(p, LogicPiece::Wire { .. }) && {
+                    let mut moved = original.clone();
+                    moved.set_position(p.position());
+                    moved == *p
+                })
+        });
+        match found {
+            Some(i) => {
+                unmatched.swap_remove(i);
             }
+            None => errors.push(ValidationError::LockedPieceChanged { position }),
         }
     }
 
-    if errors.is_empty() {
-        let mut result = ValidationResult::valid();
-        result.warnings = warnings;
-        result
-    } else {
-        let mut result = ValidationResult::invalid(errors);
-        result.warnings = warnings;
-        result
+    for piece in pieces {
+        for (x, y) in piece.footprint() {
+            if board.is_obstacle(x, y) {
+                errors.push(ValidationError::OnObstacle { position: (x, y) });
+            }
+        }
     }
+
+    errors
 }
 
 /// Validate a level definition.
@@ -234,22 +513,32 @@ pub fn validate_level(level: &Level) -> ValidationResult {
     let mut errors = Vec::new();
     let mut warnings = Vec::new();
 
-    // Validate initial board state
-    let board_result = validate_board(&level.initial_state);
-    errors.extend(board_result.errors);
-    warnings.extend(board_result.warnings);
+    // Validate initial board state. A level ships unsolved, so goals are
+    // expected to be unreachable; pre-placed gates that aren't wired up
+    // yet are only worth a warning.
+    errors.extend(check_structure(&level.initial_state));
+    for error in check_connectivity(&level.initial_state) {
+        match error {
+            ValidationError::DisconnectedGate { position, side } => {
+                warnings.push(ValidationWarning::DisconnectedGate { position, side });
+            }
+            ValidationError::InvalidWire { .. } => errors.push(error),
+            _ => {}
+        }
+    }
 
     // Validate goal condition matches board
+    let board = &level.initial_state;
     match &level.goal_state {
         GoalCondition::ConnectNodes { start, end } => {
-            if start.0 >= level.initial_state.width || start.1 >= level.initial_state.height {
-                warnings.push(format!(
-                    "Goal start node {:?} is outside board bounds",
-                    start
-                ));
-            }
-            if end.0 >= level.initial_state.width || end.1 >= level.initial_state.height {
-                warnings.push(format!("Goal end node {:?} is outside board bounds", end));
+            for node in [start, end] {
+                if !board.in_bounds(node.0, node.1) {
+                    warnings.push(ValidationWarning::GoalOutOfBounds {
+                        position: *node,
+                        max_x: board.width.saturating_sub(1),
+                        max_y: board.height.saturating_sub(1),
+                    });
+                }
             }
         }
         GoalCondition::ProveFormula { formula } => {
@@ -257,25 +546,49 @@ pub fn validate_level(level: &Level) -> ValidationResult {
                 errors.push(ValidationError::InvalidFormula {
                     formula: formula.clone(),
                     reason: "Goal formula cannot be empty".to_string(),
+                    position: None,
                 });
             }
         }
         GoalCondition::BuildProofTree { depth } => {
             if *depth == 0 {
-                warnings.push("Proof tree depth of 0 is trivially satisfied".to_string());
+                warnings.push(ValidationWarning::TrivialProofTree { depth: *depth });
             }
         }
     }
 
-    if errors.is_empty() {
-        let mut result = ValidationResult::valid();
-        result.warnings = warnings;
-        result
-    } else {
-        let mut result = ValidationResult::invalid(errors);
-        result.warnings = warnings;
-        result
+    // Assumptions whose atoms never appear in the theorem can't contribute
+    // to the proof. Skipped while the theorem is still blank (new levels).
+    let theorem_atoms = formula_atoms(&level.theorem);
+    if !theorem_atoms.is_empty() {
+        for piece in board.pieces() {
+            if let LogicPiece::Assumption { formula, position } = piece {
+                if !formula_atoms(formula)
+                    .iter()
+                    .any(|atom| theorem_atoms.contains(atom))
+                {
+                    warnings.push(ValidationWarning::UnusedAssumption {
+                        formula: formula.clone(),
+                        position: *position,
+                    });
+                }
+            }
+        }
     }
+
+    ValidationResult::from_diagnostics(errors, warnings)
+}
+
+/// Extract the propositional atoms from a formula, skipping SMT-LIB2
+/// keywords and operators.
+pub(crate) fn formula_atoms(formula: &str) -> Vec<&str> {
+    const KEYWORDS: &[&str] = &[
+        "assert", "and", "or", "not", "forall", "exists", "true", "false", "Bool", "Int",
+    ];
+    formula
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|atom| !atom.is_empty() && !KEYWORDS.contains(atom))
+        .collect()
 }
 
 /// Check if a board state is ready for proof verification.
@@ -296,27 +609,37 @@ pub fn is_ready_for_verification(board: &BoardState) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::{Direction, PieceFlags};
 
     fn make_test_board() -> BoardState {
-        BoardState {
-            width: 10,
-            height: 10,
-            pieces: vec![
+        BoardState::with_pieces(
+            10,
+            10,
+            vec![
                 LogicPiece::Assumption {
                     formula: "P".to_string(),
                     position: (2, 5),
                 },
                 LogicPiece::Assumption {
                     formula: "Q".to_string(),
-                    position: (2, 3),
+                    position: (2, 4),
                 },
                 LogicPiece::Goal {
                     formula: "R".to_string(),
-                    position: (8, 4),
+                    position: (6, 4),
+                },
+                // P and Q feed its inputs directly; a wire carries its
+                // output to R
+                LogicPiece::AndIntro {
+                    position: (3, 4),
+                    orientation: Direction::East,
+                },
+                LogicPiece::Wire {
+                    from: (4, 4),
+                    to: (5, 4),
                 },
-                LogicPiece::AndIntro { position: (5, 4) },
             ],
-        }
+        )
     }
 
     #[test]
@@ -329,9 +652,10 @@ fn test_valid_board() {
     #[test]
     fn test_out_of_bounds() {
         let mut board = make_test_board();
-        board
-            .pieces
-            .push(LogicPiece::OrIntro { position: (15, 15) });
+        board.pieces_mut().push(LogicPiece::OrIntro {
+            position: (15, 15),
+            orientation: Direction::East,
+        });
 
         let result = validate_board(&board);
         assert!(!result.is_valid);
@@ -344,7 +668,10 @@ fn test_out_of_bounds() {
     #[test]
     fn test_overlapping_pieces() {
         let mut board = make_test_board();
-        board.pieces.push(LogicPiece::OrIntro { position: (2, 5) }); // Same as first assumption
+        board.pieces_mut().push(LogicPiece::OrIntro {
+            position: (2, 5),
+            orientation: Direction::East,
+        }); // Same as first assumption
 
         let result = validate_board(&board);
         assert!(!result.is_valid);
@@ -356,14 +683,14 @@ fn test_overlapping_pieces() {
 
     #[test]
     fn test_no_assumptions() {
-        let board = BoardState {
-            width: 10,
-            height: 10,
-            pieces: vec![LogicPiece::Goal {
+        let board = BoardState::with_pieces(
+            10,
+            10,
+            vec![LogicPiece::Goal {
                 formula: "R".to_string(),
                 position: (5, 5),
             }],
-        };
+        );
 
         let result = validate_board(&board);
         assert!(!result.is_valid);
@@ -390,4 +717,284 @@ fn test_ready_for_verification() {
         let board = make_test_board();
         assert!(is_ready_for_verification(&board));
     }
+
+    #[test]
+    fn test_disconnected_gate() {
+        let mut board = make_test_board();
+        board.pieces_mut().push(LogicPiece::NotIntro {
+            position: (9, 9),
+            orientation: Direction::East,
+        });
+
+        let result = validate_board(&board);
+        assert!(!result.is_valid);
+        let (errors, warnings) = result.diagnostics_at((9, 9));
+        assert_eq!(
+            errors,
+            vec![
+                &ValidationError::DisconnectedGate {
+                    position: (9, 9),
+                    side: GateSide::Input,
+                },
+                &ValidationError::DisconnectedGate {
+                    position: (9, 9),
+                    side: GateSide::Output,
+                },
+            ]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_gate_missing_output_only() {
+        let mut board = make_test_board();
+        // Fed by S, but feeding nothing.
+        board.pieces_mut().push(LogicPiece::Assumption {
+            formula: "S".to_string(),
+            position: (0, 7),
+        });
+        board.pieces_mut().push(LogicPiece::NotIntro {
+            position: (1, 7),
+            orientation: Direction::East,
+        });
+
+        let result = validate_board(&board);
+        assert_eq!(
+            result.diagnostics_at((1, 7)).0,
+            vec![&ValidationError::DisconnectedGate {
+                position: (1, 7),
+                side: GateSide::Output,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let mut board = make_test_board();
+        board.pieces_mut().push(LogicPiece::Goal {
+            formula: "S".to_string(),
+            position: (9, 0),
+        });
+
+        let result = validate_board(&board);
+        assert!(result.errors.contains(&ValidationError::UnreachableGoal {
+            formula: "S".to_string(),
+            position: (9, 0),
+        }));
+        // The connected goal is still fine.
+        assert!(result.diagnostics_at((6, 4)).0.is_empty());
+    }
+
+    #[test]
+    fn test_wire_connects_gate_to_goal() {
+        let mut board = make_test_board();
+        // Send the gate's output to S instead of R
+        board
+            .pieces_mut()
+            .retain(|p| !matches!(p, LogicPiece::Wire { .. }));
+        board.pieces_mut().push(LogicPiece::Goal {
+            formula: "S".to_string(),
+            position: (9, 0),
+        });
+        board.pieces_mut().push(LogicPiece::Wire {
+            from: (4, 4),
+            to: (8, 0),
+        });
+
+        let result = validate_board(&board);
+        assert!(result.diagnostics_at((9, 0)).0.is_empty());
+        assert!(result.diagnostics_at((3, 4)).0.is_empty());
+        assert_eq!(
+            result.diagnostics_at((6, 4)).0,
+            vec![&ValidationError::UnreachableGoal {
+                formula: "R".to_string(),
+                position: (6, 4),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_piece_on_obstacle_is_invalid() {
+        let mut board = make_test_board();
+        // Under the upper cell of the AND gate
+        board.set_obstacle((3, 5), true);
+
+        let result = validate_board(&board);
+        assert!(result
+            .errors
+            .contains(&ValidationError::OnObstacle { position: (3, 5) }));
+    }
+
+    #[test]
+    fn test_level_rules_protect_locked_pieces() {
+        let mut board = make_test_board();
+        board.pieces_mut().push(LogicPiece::OrIntro {
+            position: (8, 8),
+            orientation: Direction::East,
+        });
+        board.set_flags(
+            (3, 4),
+            PieceFlags {
+                movable: true,
+                deletable: false,
+            },
+        );
+        board.set_flags((8, 8), PieceFlags::FREE);
+        board.set_obstacle((0, 0), true);
+        let level = Level {
+            id: 1,
+            name: "Test".to_string(),
+            description: String::new(),
+            theorem: String::new(),
+            initial_state: board.clone(),
+            goal_state: GoalCondition::ProveFormula {
+                formula: "R".to_string(),
+            },
+            witness: None,
+            inventory: Default::default(),
+        };
+
+        // Movable gate moved, deletable gate removed: fine
+        let mut pieces = board.pieces().to_vec();
+        pieces.retain(|p| p.position() != (8, 8));
+        pieces.retain(|p| p.position() != (3, 4));
+        pieces.push(LogicPiece::AndIntro {
+            position: (5, 1),
+            orientation: Direction::East,
+        });
+        assert!(check_level_rules(&level, &pieces).is_empty());
+
+        // Locked assumption moved
+        let mut moved = pieces.clone();
+        moved[0].set_position((1, 5));
+        assert_eq!(
+            check_level_rules(&level, &moved),
+            vec![ValidationError::LockedPieceChanged { position: (2, 5) }]
+        );
+
+        // Player piece on an obstacle
+        pieces.push(LogicPiece::NotIntro {
+            position: (0, 0),
+            orientation: Direction::East,
+        });
+        assert_eq!(
+            check_level_rules(&level, &pieces),
+            vec![ValidationError::OnObstacle { position: (0, 0) }]
+        );
+    }
+
+    #[test]
+    fn test_dangling_wire_is_invalid() {
+        let mut board = make_test_board();
+        board.pieces_mut().push(LogicPiece::Wire {
+            from: (5, 3),
+            to: (9, 9),
+        });
+
+        let result = validate_board(&board);
+        assert_eq!(
+            result.diagnostics_at((5, 3)).0,
+            vec![&ValidationError::InvalidWire {
+                from: (5, 3),
+                to: (9, 9),
+                reason: "Wire end is not attached to a port".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_level_downgrades_disconnected_gates() {
+        let mut board = make_test_board();
+        board.pieces_mut().push(LogicPiece::NotIntro {
+            position: (9, 9),
+            orientation: Direction::East,
+        });
+        board.pieces_mut().push(LogicPiece::Goal {
+            formula: "S".to_string(),
+            position: (9, 0),
+        });
+        let level = Level {
+            id: 1,
+            name: "Test".to_string(),
+            description: String::new(),
+            theorem: String::new(),
+            initial_state: board,
+            goal_state: GoalCondition::ProveFormula {
+                formula: "R".to_string(),
+            },
+            witness: None,
+            inventory: Default::default(),
+        };
+
+        // Unsolved levels are fine; unwired gates are only warnings.
+        let result = validate_level(&level);
+        assert!(result.is_valid);
+        assert!(result
+            .warnings
+            .contains(&ValidationWarning::DisconnectedGate {
+                position: (9, 9),
+                side: GateSide::Input,
+            }));
+    }
+
+    #[test]
+    fn test_level_warnings_are_typed() {
+        let mut board = make_test_board();
+        board.pieces_mut().push(LogicPiece::Assumption {
+            formula: "Unrelated".to_string(),
+            position: (0, 0),
+        });
+        let level = Level {
+            id: 1,
+            name: "Test".to_string(),
+            description: String::new(),
+            theorem: "(assert (=> (and P Q) R))".to_string(),
+            initial_state: board,
+            goal_state: GoalCondition::ConnectNodes {
+                start: (0, 0),
+                end: (12, 3),
+            },
+            witness: None,
+            inventory: Default::default(),
+        };
+
+        let result = validate_level(&level);
+        assert!(result
+            .warnings
+            .contains(&ValidationWarning::UnusedAssumption {
+                formula: "Unrelated".to_string(),
+                position: (0, 0),
+            }));
+        assert!(result
+            .warnings
+            .contains(&ValidationWarning::GoalOutOfBounds {
+                position: (12, 3),
+                max_x: 9,
+                max_y: 9,
+            }));
+        // P and Q appear in the theorem, so they are not flagged.
+        assert!(result.diagnostics_at((2, 5)).1.is_empty());
+    }
+
+    #[test]
+    fn test_trivial_proof_tree_has_no_position() {
+        let level = Level {
+            id: 1,
+            name: "Test".to_string(),
+            description: String::new(),
+            theorem: String::new(),
+            initial_state: make_test_board(),
+            goal_state: GoalCondition::BuildProofTree { depth: 0 },
+            witness: None,
+            inventory: Default::default(),
+        };
+
+        let result = validate_level(&level);
+        let warning = result
+            .warnings
+            .iter()
+            .find(|w| matches!(w, ValidationWarning::TrivialProofTree { .. }))
+            .expect("depth 0 should warn");
+        assert_eq!(warning.position(), None);
+    }
 }
@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Wire drawing: snapping wire endpoints to piece ports, finding the wire
+//! under a cell, and laying out wire segments for rendering.
+//!
+//! A wire end attaches to a port's attach cell, the free cell just outside
+//! the side the port faces (see [`super::ports`]). A wire's `from`
+//! endpoint occupies its cell like any other piece position, so it must be
+//! free; `to` only has to avoid sitting on top of a piece.
+
+use bevy::prelude::*;
+
+use super::ports::{Port, PortKind};
+use super::validation::validate_piece_placement;
+use super::{BoardState, LogicPiece};
+
+/// Thickness of a rendered wire segment, in world units.
+pub const WIRE_THICKNESS: f32 = 8.0;
+
+/// Free attach cells of the ports of the piece at `position`, where a wire
+/// can attach.
+pub fn ports(board: &BoardState, position: (u32, u32)) -> Vec<(u32, u32)> {
+    let Some(piece) = node_at(board, position) else {
+        return Vec::new();
+    };
+    piece
+        .ports()
+        .iter()
+        .filter_map(Port::attach)
+        .filter(|&(px, py)| board.in_bounds(px, py) && !board.is_occupied(px, py))
+        .collect()
+}
+
+/// The non-wire piece covering `cell`, if any.
+fn node_at(board: &BoardState, cell: (u32, u32)) -> Option<&LogicPiece> {
+    board
+        .spatial()
+        .at(cell)
+        .iter()
+        .map(|&i| &board.pieces()[i])
+        .find(|p| !matches!(p, LogicPiece::Wire { .. }))
+}
+
+/// Kinds of the ports attaching at `cell`.
+fn port_kinds_at(board: &BoardState, cell: (u32, u32)) -> Vec<PortKind> {
+    board
+        .pieces_near(cell.0, cell.1, 1)
+        .into_iter()
+        .flat_map(|p| p.ports())
+        .filter(|p| p.attach() == Some(cell))
+        .map(|p| p.kind)
+        .collect()
+}
+
+/// Snap a dragged endpoint to a port. Dropping on a piece picks the port
+/// of that piece nearest `toward`; dropping on a port's attach cell keeps
+/// the cell. Returns `None` if no port is there.
+pub fn snap_endpoint(
+    board: &BoardState,
+    cell: (u32, u32),
+    toward: (u32, u32),
+) -> Option<(u32, u32)> {
+    if let Some(piece) = node_at(board, cell) {
+        return ports(board, piece.position())
+            .into_iter()
+            .min_by_key(|&(x, y)| x.abs_diff(toward.0).pow(2) + y.abs_diff(toward.1).pow(2));
+    }
+    (!port_kinds_at(board, cell).is_empty()).then_some(cell)
+}
+
+/// Build a wire for a drag from `start` to `end`, snapping both ends to
+/// ports and checking it against the board.
+pub fn snap_wire(
+    board: &BoardState,
+    start: (u32, u32),
+    end: (u32, u32),
+) -> Result<LogicPiece, String> {
+    let from = snap_endpoint(board, start, end)
+        .ok_or_else(|| "Wires must start next to a piece".to_string())?;
+    let to = snap_endpoint(board, end, from)
+        .ok_or_else(|| "Wires must end next to a piece".to_string())?;
+    let (starts, ends) = (port_kinds_at(board, from), port_kinds_at(board, to));
+    let joins = starts.iter().any(|a| ends.iter().any(|b| a != b));
+    if !joins {
+        return Err("Wires must join an output to an input".to_string());
+    }
+    let wire = LogicPiece::Wire { from, to };
+    validate_piece_placement(board, &wire).map_err(|e| e.to_string())?;
+    Ok(wire)
+}
+
+/// Move one end of `wire` to a new drop cell. `end` is the endpoint being
+/// dragged; the other end stays put.
+pub fn reroute(
+    board: &BoardState,
+    wire: &LogicPiece,
+    end: (u32, u32),
+    drop: (u32, u32),
+) -> Result<LogicPiece, String> {
+    let LogicPiece::Wire { from, to } = wire else {
+        return Err("Not a wire".to_string());
+    };
+    let mut rest = board.clone();
+    rest.pieces_mut().retain(|p| p != wire);
+    if end == *from {
+        snap_wire(&rest, drop, *to)
+    } else {
+        snap_wire(&rest, *from, drop)
+    }
+}
+
+/// The wire with an endpoint at `cell`, if any.
+pub fn wire_end_at(board: &BoardState, cell: (u32, u32)) -> Option<&LogicPiece> {
+    board
+        .pieces()
+        .iter()
+        .find(|p| matches!(p, LogicPiece::Wire { from, to } if *from == cell || *to == cell))
+}
+
+/// The wire whose segment passes through `cell`, if any.
+pub fn wire_at(board: &BoardState, cell: (u32, u32)) -> Option<&LogicPiece> {
+    board.pieces().iter().find(|p| wire_crosses(p, cell))
+}
+
+/// Whether `piece` is a wire whose segment passes within half a cell of
+/// the centre of `cell`.
+pub fn wire_crosses(piece: &LogicPiece, cell: (u32, u32)) -> bool {
+    let LogicPiece::Wire { from, to } = piece else {
+        return false;
+    };
+    let a = Vec2::new(from.0 as f32, from.1 as f32);
+    let b = Vec2::new(to.0 as f32, to.1 as f32);
+    let p = Vec2::new(cell.0 as f32, cell.1 as f32);
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    p.distance(a + ab * t) <= 0.5
+}
+
+/// Size and transform of a sprite drawing a straight segment between two
+/// world positions.
+pub fn segment(from: Vec2, to: Vec2, z: f32) -> (Vec2, Transform) {
+    let delta = to - from;
+    let midpoint = (from + to) / 2.0;
+    let transform = Transform::from_xyz(midpoint.x, midpoint.y, z)
+        .with_rotation(Quat::from_rotation_z(delta.y.atan2(delta.x)));
+    (Vec2::new(delta.length(), WIRE_THICKNESS), transform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+
+    fn board() -> BoardState {
+        BoardState::with_pieces(
+            10,
+            10,
+            vec![
+                LogicPiece::Assumption {
+                    formula: "P".to_string(),
+                    position: (1, 1),
+                },
+                LogicPiece::Goal {
+                    formula: "P".to_string(),
+                    position: (8, 1),
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_drag_between_pieces_snaps_to_facing_ports() {
+        let wire = snap_wire(&board(), (1, 1), (8, 1)).unwrap();
+        assert_eq!(
+            wire,
+            LogicPiece::Wire {
+                from: (2, 1),
+                to: (7, 1)
+            }
+        );
+    }
+
+    #[test]
+    fn test_drag_from_empty_space_is_rejected() {
+        assert!(snap_wire(&board(), (5, 5), (8, 1)).is_err());
+    }
+
+    #[test]
+    fn test_reroute_moves_one_end() {
+        let mut board = board();
+        board.pieces_mut().push(LogicPiece::AndIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        });
+        let wire = snap_wire(&board, (1, 1), (8, 1)).unwrap();
+        board.pieces_mut().push(wire.clone());
+
+        let rerouted = reroute(&board, &wire, (7, 1), (5, 5)).unwrap();
+        assert_eq!(
+            rerouted,
+            LogicPiece::Wire {
+                from: (2, 1),
+                to: (4, 5)
+            }
+        );
+    }
+
+    #[test]
+    fn test_wire_at_follows_the_segment() {
+        let wire = LogicPiece::Wire {
+            from: (2, 1),
+            to: (7, 1),
+        };
+        let board = BoardState::with_pieces(10, 10, vec![wire.clone()]);
+        assert_eq!(wire_at(&board, (4, 1)), Some(&wire));
+        assert_eq!(wire_at(&board, (4, 3)), None);
+        assert_eq!(wire_end_at(&board, (7, 1)), Some(&wire));
+    }
+}
@@ -2,31 +2,94 @@
 //! Game systems that run within the Bevy game loop.
 //! These are separated from the core game module because they depend on GameState.
 
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll};
 use bevy::prelude::*;
 
+use crate::editor::{EditorState, TestPlaySession};
+use crate::game::signal::CircuitState;
+use crate::game::wiring;
 use crate::game::{
-    BoardState, CurrentLevel, GameEntity, GoalCondition, Level, LogicPiece, PieceBundle,
-    PlaceablePiece, PlayerCursor, PlayerPlaced, PlayerStats, SelectedPieceType,
+    rotation_input, Action, ActionHistory, ActionState, BoardGrid, BoardState, CurrentLevel,
+    GameEntity, GoalCondition, Inventory, InventorySlot, Level, LevelKey, LiveCircuit, LogicPiece,
+    PieceBundle, PieceFlags, PlaceablePiece, PlayAction, PlayerCursor, PlayerPlaced, PlayerStats,
+    ResetLevelEvent, SelectedPieceType, SolvedLevel,
 };
+use crate::levels::{LevelPackManager, SavedSolution};
+use crate::settings::Settings;
 use crate::states::GameState;
+use crate::verification::VerifierBackend;
+
+/// Pieces as seen by the input systems.
+type PieceQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static LogicPiece,
+        &'static Transform,
+        Option<&'static PlayerPlaced>,
+        Option<&'static PieceFlags>,
+    ),
+    Without<PlayerCursor>,
+>;
 
 // Load level system
-pub fn load_level(mut commands: Commands, mut stats: ResMut<PlayerStats>) {
+pub fn load_level(
+    mut commands: Commands,
+    mut stats: ResMut<PlayerStats>,
+    mut history: ResMut<ActionHistory>,
+    test_play: Option<Res<TestPlaySession>>,
+    editor: Option<Res<EditorState>>,
+    pack_manager: Option<Res<LevelPackManager>>,
+) {
     info!("Loading level...");
 
     // Start timing
     stats.start_level();
+    history.clear();
+
+    // Test play from the editor runs the level being edited
+    if let (Some(test_play), Some(editor)) = (&test_play, &editor) {
+        if test_play.active {
+            let level = editor.build_level();
+            info!("  Test playing: {}", level.name);
+            info!("  Pieces: {}", level.initial_state.pieces().len());
+            commands.spawn((CurrentLevel(level), GameEntity));
+            return;
+        }
+    }
+
+    // Levels picked from a pack resume the player's unfinished solution,
+    // timer included
+    if let Some(pack_manager) = &pack_manager {
+        if let (Some(level), Some((pack_id, level_id))) = (
+            pack_manager.current_level(),
+            pack_manager.current_level_key(),
+        ) {
+            let key = LevelKey { pack_id, level_id };
+            if let Some(saved) = pack_manager.saved_solution(&key.pack_id, level_id) {
+                info!("  Resuming after {}s", saved.elapsed_secs);
+                stats.resume_level(saved.elapsed_secs);
+                *history = saved.history.clone();
+            } else {
+                stats.record_attempt(&key);
+            }
+            info!("  Level: {} ({} #{})", level.name, key.pack_id, level_id);
+            commands.spawn((CurrentLevel(level.clone()), key, GameEntity));
+            return;
+        }
+    }
 
-    // Create the vertical slice puzzle: P AND Q => R
+    // Fallback when no pack is loaded: the vertical slice puzzle: P AND Q => R
     let level = Level {
         id: 1,
         name: "First Steps".to_string(),
         description: "Place an AND gate to connect P and Q, then connect to R".to_string(),
         theorem: "(assert (=> (and P Q) R))".to_string(),
-        initial_state: BoardState {
-            width: 10,
-            height: 10,
-            pieces: vec![
+        initial_state: BoardState::with_pieces(
+            10,
+            10,
+            vec![
                 LogicPiece::Assumption {
                     formula: "P".to_string(),
                     position: (2, 5),
@@ -40,14 +103,31 @@ pub fn load_level(mut commands: Commands, mut stats: ResMut<PlayerStats>) {
                     position: (8, 4),
                 },
             ],
-        },
+        ),
         goal_state: GoalCondition::ProveFormula {
             formula: "R".to_string(),
         },
+        witness: None,
+        inventory: Inventory {
+            slots: vec![
+                InventorySlot {
+                    kind: PlaceablePiece::AndGate,
+                    count: Some(1),
+                },
+                InventorySlot {
+                    kind: PlaceablePiece::OrGate,
+                    count: Some(1),
+                },
+                InventorySlot {
+                    kind: PlaceablePiece::Wire,
+                    count: None,
+                },
+            ],
+        },
     };
 
     info!("  Level: {}", level.name);
-    info!("  Pieces: {}", level.initial_state.pieces.len());
+    info!("  Pieces: {}", level.initial_state.pieces().len());
     info!("  Hint: Place an AND gate between the assumptions and the goal!");
 
     commands.spawn((CurrentLevel(level), GameEntity));
@@ -56,49 +136,69 @@ pub fn load_level(mut commands: Commands, mut stats: ResMut<PlayerStats>) {
 // Spawn pieces system
 pub fn spawn_pieces(
     mut commands: Commands,
-    level_query: Query<&CurrentLevel, Added<CurrentLevel>>,
-    asset_server: Res<AssetServer>,
+    level_query: Query<(&CurrentLevel, Option<&LevelKey>), Added<CurrentLevel>>,
+    pack_manager: Option<Res<LevelPackManager>>,
 ) {
-    let Ok(current_level) = level_query.single() else {
+    let Ok((current_level, key)) = level_query.single() else {
         return;
     };
 
     info!(
         "Spawning {} pieces",
-        current_level.0.initial_state.pieces.len()
+        current_level.0.initial_state.pieces().len()
     );
+    let board = &current_level.0.initial_state;
+    let grid = BoardGrid::for_board(board);
+    commands.insert_resource(grid);
 
     // Spawn grid background
-    for x in 0..10 {
-        for y in 0..10 {
-            commands.spawn((
-                Sprite {
-                    color: if (x + y) % 2 == 0 {
-                        Color::srgba(0.15, 0.15, 0.2, 1.0)
-                    } else {
-                        Color::srgba(0.12, 0.12, 0.17, 1.0)
-                    },
-                    custom_size: Some(Vec2::new(78.0, 78.0)),
-                    ..default()
-                },
-                Transform::from_xyz((x as f32 - 4.5) * 80.0, (y as f32 - 4.5) * 80.0, -1.0),
-                GameEntity,
-            ));
+    for x in 0..grid.width {
+        for y in 0..grid.height {
+            commands.spawn((grid.cell_tile((x, y)), GameEntity));
         }
     }
 
     // Spawn each piece
-    for piece in &current_level.0.initial_state.pieces {
-        let (x, y) = piece.position();
-        let bundle = PieceBundle::new(piece.clone(), &asset_server);
-        let mut entity = commands.spawn((bundle, GameEntity));
+    for &(x, y) in &board.obstacles {
+        commands.spawn((
+            Sprite {
+                color: Color::srgba(0.05, 0.05, 0.07, 1.0),
+                custom_size: Some(grid.tile_size()),
+                ..default()
+            },
+            Transform::from_translation(grid.cell_to_world((x, y)).extend(-0.9)),
+            GameEntity,
+        ));
+    }
 
+    // The level's pieces, as an unfinished attempt left them if they were
+    // moved, turned or deleted
+    let saved = key
+        .zip(pack_manager.as_deref())
+        .and_then(|(key, pack_manager)| pack_manager.saved_solution(&key.pack_id, key.level_id));
+    let level_pieces = match saved.and_then(|saved| saved.level_pieces.clone()) {
+        Some(pieces) => pieces,
+        None => board
+            .pieces()
+            .iter()
+            .map(|piece| (piece.clone(), board.flags_at(piece.position())))
+            .collect(),
+    };
+    for (piece, flags) in level_pieces {
         // Offset to center the grid
-        entity.insert(Transform::from_xyz(
-            (x as f32 - 4.5) * 80.0,
-            (y as f32 - 4.5) * 80.0,
-            0.0,
-        ));
+        let (size, transform) = piece_layout(&grid, &piece);
+        let mut bundle = PieceBundle::new(piece);
+        bundle.sprite.custom_size = Some(size);
+        bundle.transform = transform;
+        commands.spawn((bundle, flags, GameEntity));
+    }
+
+    // Pieces from an unfinished attempt
+    if let Some(saved) = saved {
+        info!("Restoring {} saved pieces", saved.pieces.len());
+        for piece in &saved.pieces {
+            spawn_player_piece(&mut commands, &grid, piece.clone());
+        }
     }
 
     // Spawn player cursor
@@ -106,6 +206,8 @@ pub fn spawn_pieces(
         PlayerCursor {
             position: Vec2::ZERO,
             selected_piece: None,
+            selected_from: None,
+            wire_start: None,
         },
         Sprite {
             color: Color::srgba(0.5, 0.8, 1.0, 0.3),
@@ -119,94 +221,154 @@ pub fn spawn_pieces(
     info!("Pieces spawned successfully");
 }
 
-// Input handling system
-pub fn handle_input(
-    keyboard: Res<ButtonInput<KeyCode>>,
+/// Move the cursor to the cell under the mouse when the mouse is used, or
+/// a cell at a time with the cursor actions.
+pub fn move_cursor(
+    actions: Res<ActionState>,
     mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
     mut cursor_query: Query<(&mut PlayerCursor, &mut Transform)>,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
-    piece_query: Query<
-        (Entity, &LogicPiece, &Transform, Option<&PlayerPlaced>),
-        Without<PlayerCursor>,
-    >,
-    mut commands: Commands,
-    selected_piece_type: Option<Res<SelectedPieceType>>,
+    grid: Res<BoardGrid>,
 ) {
     let Ok((mut cursor, mut cursor_transform)) = cursor_query.single_mut() else {
         return;
     };
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let Ok((camera, camera_transform)) = camera_query.single() else {
+
+    let step = actions.cursor_step();
+    if step != IVec2::ZERO {
+        let (x, y) = grid.clamp_to_cell(cursor.position);
+        let cell = (
+            (x as i32 + step.x).clamp(0, grid.width as i32 - 1) as u32,
+            (y as i32 + step.y).clamp(0, grid.height as i32 - 1) as u32,
+        );
+        cursor.position = grid.cell_to_world(cell);
+    } else if motion.delta != Vec2::ZERO
+        || mouse.any_just_pressed([MouseButton::Left, MouseButton::Right])
+    {
+        // Get mouse position in world space
+        let world_pos = windows
+            .single()
+            .ok()
+            .and_then(Window::cursor_position)
+            .and_then(|screen_pos| {
+                let (camera, camera_transform) = camera_query.single().ok()?;
+                camera
+                    .viewport_to_world_2d(camera_transform, screen_pos)
+                    .ok()
+            });
+        if let Some(world_pos) = world_pos {
+            cursor.position = world_pos;
+        }
+    }
+
+    // Snap cursor to grid
+    let cell = grid.clamp_to_cell(cursor.position);
+    cursor_transform.translation = grid.cell_to_world(cell).extend(5.0);
+}
+
+// Input handling system
+#[allow(clippy::too_many_arguments)]
+pub fn handle_input(
+    actions: Res<ActionState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut cursor_query: Query<&mut PlayerCursor>,
+    piece_query: PieceQuery,
+    mut commands: Commands,
+    selected_piece_type: Option<Res<SelectedPieceType>>,
+    mut history: ResMut<ActionHistory>,
+    level_query: Query<&CurrentLevel>,
+    grid: Res<BoardGrid>,
+) {
+    let Ok(mut cursor) = cursor_query.single_mut() else {
         return;
     };
 
-    // Get mouse position in world space
-    if let Some(screen_pos) = window.cursor_position() {
-        if let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, screen_pos) {
-            cursor.position = world_pos;
+    let cursor_cell = grid.clamp_to_cell(cursor.position);
+    let wire_selected = selected_piece_type
+        .as_ref()
+        .is_some_and(|s| s.piece_type == Some(PlaceablePiece::Wire));
+    let (inventory, obstacles) = level_query
+        .single()
+        .map(|level| {
+            (
+                level.0.inventory.clone(),
+                level.0.initial_state.obstacles.clone(),
+            )
+        })
+        .unwrap_or_default();
+    let placed: Vec<LogicPiece> = piece_query
+        .iter()
+        .filter(|(_, _, _, player_placed, _)| player_placed.is_some())
+        .map(|(_, piece, _, _, _)| piece.clone())
+        .collect();
 
-            // Snap cursor to grid
-            let grid_x = ((world_pos.x / 80.0).round() as i32).clamp(-4, 5);
-            let grid_y = ((world_pos.y / 80.0).round() as i32).clamp(-4, 5);
-            cursor_transform.translation =
-                Vec3::new(grid_x as f32 * 80.0, grid_y as f32 * 80.0, 5.0);
+    // Wires are drawn by right-dragging between pieces, or by placing at
+    // each end in turn; starting from the end of a placed wire reroutes it
+    let keyed = actions.just_pressed(Action::Place);
+    if wire_selected {
+        let start_wire =
+            mouse.just_pressed(MouseButton::Right) || (keyed && cursor.wire_start.is_none());
+        let end_wire =
+            mouse.just_released(MouseButton::Right) || (keyed && cursor.wire_start.is_some());
+        if start_wire {
+            cursor.wire_start = Some(cursor_cell);
+        }
+        if end_wire {
+            if let Some(start) = cursor.wire_start.take() {
+                if start != cursor_cell {
+                    let can_add = inventory.can_place(PlaceablePiece::Wire, placed.iter());
+                    draw_wire(
+                        start,
+                        cursor_cell,
+                        can_add,
+                        &grid,
+                        &obstacles,
+                        &piece_query,
+                        &mut commands,
+                        &mut history,
+                    );
+                }
+            }
         }
     }
 
+    // Placing from the keyboard puts down a carried piece, places the
+    // chosen piece on a free cell, or else picks up the piece there
+    let carrying = cursor.selected_piece.is_some();
+    let cell_free = !piece_query
+        .iter()
+        .any(|(_, p, _, _, _)| p.occupies(cursor_cell));
+    let keyed_place = keyed && !wire_selected && !carrying && cell_free;
+    let keyed_pick = keyed && !wire_selected && !keyed_place;
+
     // Handle right-click to place new piece
-    if mouse.just_pressed(MouseButton::Right) {
+    if (mouse.just_pressed(MouseButton::Right) && !wire_selected) || keyed_place {
         if let Some(selected) = &selected_piece_type {
-            if let Some(piece_type) = &selected.piece_type {
-                let grid_x = ((cursor.position.x / 80.0).round() as i32 + 4) as u32;
-                let grid_y = ((cursor.position.y / 80.0).round() as i32 + 4) as u32;
-
-                // Check if position is empty
-                let mut occupied = false;
-                for (_entity, piece, _transform, _) in piece_query.iter() {
-                    if piece.position() == (grid_x, grid_y) {
-                        occupied = true;
-                        break;
-                    }
-                }
+            if let (Some(piece_type), Some((grid_x, grid_y))) =
+                (&selected.piece_type, grid.world_to_cell(cursor.position))
+            {
+                // Check that every cell the piece would cover is free
+                let fits = |piece: &LogicPiece| {
+                    piece.footprint().into_iter().all(|cell| {
+                        grid.contains(cell)
+                            && !obstacles.contains(&cell)
+                            && !piece_query.iter().any(|(_, p, _, _, _)| p.occupies(cell))
+                    })
+                };
 
-                if !occupied && grid_x < 10 && grid_y < 10 {
-                    let new_piece = match piece_type {
-                        PlaceablePiece::AndGate => LogicPiece::AndIntro {
-                            position: (grid_x, grid_y),
-                        },
-                        PlaceablePiece::OrGate => LogicPiece::OrIntro {
-                            position: (grid_x, grid_y),
-                        },
-                        PlaceablePiece::Wire => LogicPiece::Wire {
-                            from: (grid_x, grid_y),
-                            to: (grid_x + 1, grid_y),
-                        },
-                    };
-
-                    let color = match piece_type {
-                        PlaceablePiece::AndGate => Color::srgb(0.5, 0.5, 0.9),
-                        PlaceablePiece::OrGate => Color::srgb(0.9, 0.5, 0.5),
-                        PlaceablePiece::Wire => Color::srgb(0.6, 0.6, 0.6),
-                    };
-
-                    commands.spawn((
-                        new_piece,
-                        Sprite {
-                            color,
-                            custom_size: Some(Vec2::new(64.0, 64.0)),
-                            ..default()
-                        },
-                        Transform::from_xyz(
-                            (grid_x as f32 - 4.5) * 80.0,
-                            (grid_y as f32 - 4.5) * 80.0,
-                            0.0,
-                        ),
-                        GameEntity,
-                        PlayerPlaced,
-                    ));
+                if !inventory.can_place(*piece_type, placed.iter()) {
+                    info!("No {} pieces left", piece_type.name());
+                } else if let Some(new_piece) = piece_type
+                    .to_logic_piece((grid_x, grid_y))
+                    .filter(|piece| fits(piece))
+                {
+                    spawn_player_piece(&mut commands, &grid, new_piece.clone());
+                    history.record(PlayAction::Place {
+                        piece: new_piece,
+                        level_flags: None,
+                    });
 
                     info!("Placed {:?} at ({}, {})", piece_type, grid_x, grid_y);
                 }
@@ -215,21 +377,25 @@ pub fn handle_input(
     }
 
     // Handle left-click for piece selection
-    if mouse.just_pressed(MouseButton::Left) {
-        let cursor_pos = cursor.position;
+    if mouse.just_pressed(MouseButton::Left) || keyed_pick {
+        let clicked = grid.world_to_cell(cursor.position);
 
-        // Check if we clicked on a movable piece (player-placed only)
-        for (entity, _piece, transform, player_placed) in piece_query.iter() {
-            if player_placed.is_some() {
-                let piece_pos = transform.translation.truncate();
-                let distance = cursor_pos.distance(piece_pos);
+        // Check if we clicked on a piece the player may move or delete
+        for (entity, piece, _, _, flags) in piece_query.iter() {
+            if !flags.copied().unwrap_or_default().is_locked() {
+                let hit = match piece {
+                    LogicPiece::Wire { .. } => wiring::wire_crosses(piece, cursor_cell),
+                    _ => clicked.is_some_and(|cell| piece.occupies(cell)),
+                };
 
-                if distance < 40.0 {
+                if hit {
+                    finish_move(&mut cursor, &piece_query, &mut history);
                     if cursor.selected_piece == Some(entity) {
                         cursor.selected_piece = None;
                         info!("Piece deselected");
                     } else {
                         cursor.selected_piece = Some(entity);
+                        cursor.selected_from = Some(piece.position());
                         info!("Piece selected: {:?}", entity);
                     }
                     break;
@@ -238,14 +404,19 @@ pub fn handle_input(
         }
     }
 
-    // Delete selected piece with Delete/Backspace
-    if keyboard.just_pressed(KeyCode::Delete) || keyboard.just_pressed(KeyCode::Backspace) {
+    // Delete selected piece (Delete/Backspace by default)
+    if actions.just_pressed(Action::Delete) {
         if let Some(selected_entity) = cursor.selected_piece {
-            // Only delete player-placed pieces
-            if let Ok((_, _, _, player_placed)) = piece_query.get(selected_entity) {
-                if player_placed.is_some() {
+            // Only delete player-placed or deletable pre-placed pieces
+            if let Ok((_, piece, _, player_placed, flags)) = piece_query.get(selected_entity) {
+                if flags.is_some_and(|f| f.deletable) {
+                    let piece = piece.clone();
+                    // A level piece keeps its flags for undo, wherever it has moved to
+                    let level_flags = flags.copied().filter(|_| player_placed.is_none());
+                    finish_move(&mut cursor, &piece_query, &mut history);
                     commands.entity(selected_entity).despawn();
                     cursor.selected_piece = None;
+                    history.record(PlayAction::Delete { piece, level_flags });
                     info!("Piece deleted");
                 }
             }
@@ -253,63 +424,504 @@ pub fn handle_input(
     }
 }
 
-// Update board system
-pub fn update_board() {
-    // Reserved for future board state updates
+/// Record the move of the currently selected piece, if it moved since it
+/// was picked up. The piece stays selected.
+fn finish_move(cursor: &mut PlayerCursor, piece_query: &PieceQuery, history: &mut ActionHistory) {
+    let (Some(entity), Some(from)) = (cursor.selected_piece, cursor.selected_from.take()) else {
+        return;
+    };
+    if let Ok((_, piece, _, _, _)) = piece_query.get(entity) {
+        let to = piece.position();
+        if to != from {
+            let mut piece = piece.clone();
+            piece.set_position(from);
+            history.record(PlayAction::Move { piece, to });
+        }
+    }
+}
+
+/// Finish a wire drag in play mode, drawing a new wire or rerouting one
+/// the player placed earlier.
+#[allow(clippy::too_many_arguments)]
+fn draw_wire(
+    start: (u32, u32),
+    end: (u32, u32),
+    can_add: bool,
+    grid: &BoardGrid,
+    obstacles: &[(u32, u32)],
+    piece_query: &PieceQuery,
+    commands: &mut Commands,
+    history: &mut ActionHistory,
+) {
+    let mut board = BoardState::with_pieces(
+        grid.width,
+        grid.height,
+        piece_query
+            .iter()
+            .map(|(_, p, _, _, _)| p.clone())
+            .collect(),
+    );
+    board.obstacles = obstacles.to_vec();
+    let rerouting = piece_query.iter().find(|(_, piece, _, _, flags)| {
+        flags.is_some_and(|f| f.movable) && wiring::wire_end_at(&board, start) == Some(*piece)
+    });
+
+    let result = match rerouting {
+        Some((entity, old, _, _, _)) => wiring::reroute(&board, old, start, end).map(|new| {
+            (
+                Some(entity),
+                PlayAction::Reroute {
+                    old: old.clone(),
+                    new: new.clone(),
+                },
+                new,
+            )
+        }),
+        None if !can_add => Err("No wires left".to_string()),
+        None => wiring::snap_wire(&board, start, end).map(|piece| {
+            (
+                None,
+                PlayAction::Place {
+                    piece: piece.clone(),
+                    level_flags: None,
+                },
+                piece,
+            )
+        }),
+    };
+
+    match result {
+        Ok((replaced, action, wire)) => {
+            if let Some(entity) = replaced {
+                commands.entity(entity).despawn();
+            }
+            info!("Wire {:?}", wire);
+            spawn_player_piece(commands, grid, wire);
+            history.record(action);
+        }
+        Err(reason) => info!("Cannot draw wire: {}", reason),
+    }
+}
+
+/// Sprite size and transform for a piece: a tile spanning its footprint,
+/// or a line segment for wires.
+fn piece_layout(grid: &BoardGrid, piece: &LogicPiece) -> (Vec2, Transform) {
+    let to_world = |cell| grid.cell_to_world(cell);
+    match piece {
+        LogicPiece::Wire { from, to } => wiring::segment(to_world(*from), to_world(*to), -0.5),
+        _ => piece.tile(to_world),
+    }
+}
+
+/// Spawn a player-placed piece entity at its grid position.
+fn spawn_player_piece(commands: &mut Commands, grid: &BoardGrid, piece: LogicPiece) {
+    let color = piece.color();
+    let (size, transform) = piece_layout(grid, &piece);
+    commands.spawn((
+        piece,
+        Sprite {
+            color,
+            custom_size: Some(size),
+            ..default()
+        },
+        transform,
+        GameEntity,
+        PlayerPlaced,
+        PieceFlags::FREE,
+    ));
+}
+
+/// Spawn a piece belonging to the level, with its level-defined flags.
+fn spawn_level_piece(
+    commands: &mut Commands,
+    board: &BoardState,
+    piece: &LogicPiece,
+    flags: PieceFlags,
+) {
+    let (size, transform) = piece_layout(&BoardGrid::for_board(board), piece);
+    commands.spawn((
+        piece.clone(),
+        Sprite {
+            color: piece.color(),
+            custom_size: Some(size),
+            ..default()
+        },
+        transform,
+        flags,
+        GameEntity,
+    ));
+}
+
+/// Choose palette pieces with the number keys, or step through the
+/// palette with the next and previous piece actions.
+pub fn handle_palette_actions(
+    actions: Res<ActionState>,
+    level_query: Query<&CurrentLevel>,
+    placed: Query<&LogicPiece, With<PlayerPlaced>>,
+    mut selected: ResMut<SelectedPieceType>,
+) {
+    let Ok(level) = level_query.single() else {
+        return;
+    };
+    let inventory = &level.0.inventory;
+    let palette = inventory.palette();
+    let available: Vec<PlaceablePiece> = palette
+        .iter()
+        .map(|slot| slot.kind)
+        .filter(|&kind| inventory.can_place(kind, placed.iter()))
+        .collect();
+
+    if let Some(kind) = (0..palette.len() as u8)
+        .find(|&slot| actions.just_pressed(Action::PickPiece(slot)))
+        .map(|slot| palette[slot as usize].kind)
+    {
+        if selected.piece_type == Some(kind) {
+            selected.piece_type = None;
+        } else if available.contains(&kind) {
+            selected.piece_type = Some(kind);
+        }
+        return;
+    }
+
+    let step = match (
+        actions.just_pressed(Action::NextPiece),
+        actions.just_pressed(Action::PreviousPiece),
+    ) {
+        (true, false) => 1,
+        (false, true) => available.len().saturating_sub(1),
+        _ => return,
+    };
+    if available.is_empty() {
+        return;
+    }
+    let next = match selected
+        .piece_type
+        .and_then(|kind| available.iter().position(|&k| k == kind))
+    {
+        Some(index) => (index + step) % available.len(),
+        None if step == 1 => 0,
+        None => available.len() - 1,
+    };
+    selected.piece_type = Some(available[next]);
+}
+
+/// Undo (Ctrl+Z, or the undo action) and redo (Ctrl+Y, Ctrl+Shift+Z, or
+/// the redo action) the player's edits.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_undo_redo(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    mut history: ResMut<ActionHistory>,
+    mut cursor_query: Query<&mut PlayerCursor>,
+    mut piece_query: Query<(Entity, &mut LogicPiece, &mut Transform), With<PieceFlags>>,
+    level_query: Query<&CurrentLevel>,
+    grid: Res<BoardGrid>,
+    mut commands: Commands,
+) {
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let undo = (ctrl && keyboard.just_pressed(KeyCode::KeyZ) && !shift)
+        || actions.just_pressed(Action::Undo);
+    let redo = (ctrl
+        && (keyboard.just_pressed(KeyCode::KeyY)
+            || (keyboard.just_pressed(KeyCode::KeyZ) && shift)))
+        || actions.just_pressed(Action::Redo);
+    if !undo && !redo {
+        return;
+    }
+
+    // Drop the selection so the piece doesn't snap back to the cursor,
+    // committing any move in progress so it is undone first.
+    if let Ok(mut cursor) = cursor_query.single_mut() {
+        if let (Some(entity), Some(from)) = (cursor.selected_piece, cursor.selected_from) {
+            if let Ok((_, piece, _)) = piece_query.get(entity) {
+                if piece.position() != from {
+                    let mut before = piece.clone();
+                    before.set_position(from);
+                    history.record(PlayAction::Move {
+                        piece: before,
+                        to: piece.position(),
+                    });
+                }
+            }
+        }
+        cursor.selected_piece = None;
+        cursor.selected_from = None;
+    }
+
+    let action = if undo { history.undo() } else { history.redo() };
+    let Some(action) = action else {
+        return;
+    };
+
+    // Replay it on the board as it stands first; an action that no longer
+    // applies is dropped rather than left to put the pieces out of step
+    let mut board = BoardState::with_pieces(
+        grid.width,
+        grid.height,
+        piece_query.iter().map(|(_, p, _)| p.clone()).collect(),
+    );
+    if let Ok(level) = level_query.single() {
+        board.obstacles = level.0.initial_state.obstacles.clone();
+    }
+    if !action.apply(&mut board) {
+        if undo {
+            history.discard_undone();
+        } else {
+            history.discard_redone();
+        }
+        info!("Can't {} {:?}", if undo { "undo" } else { "redo" }, action);
+        return;
+    }
+
+    match &action {
+        PlayAction::Place { piece, level_flags } => {
+            // A deleted pre-placed piece comes back as a level piece
+            let board = level_query.single().map(|level| &level.0.initial_state);
+            match (board, level_flags) {
+                (Ok(board), Some(flags)) => spawn_level_piece(&mut commands, board, piece, *flags),
+                _ => spawn_player_piece(&mut commands, &grid, piece.clone()),
+            }
+        }
+        PlayAction::Move { piece: before, to } => {
+            if let Some((_, mut piece, mut transform)) = piece_query
+                .iter_mut()
+                .find(|(_, piece, _)| **piece == *before)
+            {
+                piece.set_position(*to);
+                let (_, layout) = piece_layout(&grid, &piece);
+                transform.translation.x = layout.translation.x;
+                transform.translation.y = layout.translation.y;
+            }
+        }
+        PlayAction::Delete { piece, .. } => {
+            if let Some((entity, _, _)) = piece_query.iter().find(|(_, p, _)| **p == *piece) {
+                commands.entity(entity).despawn();
+            }
+        }
+        PlayAction::Reroute { old, new } => {
+            if let Some((entity, _, _)) = piece_query.iter().find(|(_, p, _)| **p == *old) {
+                commands.entity(entity).despawn();
+            }
+            spawn_player_piece(&mut commands, &grid, new.clone());
+        }
+        PlayAction::Rotate { position, from, to } => {
+            if let Some((_, mut piece, mut transform)) =
+                piece_query.iter_mut().find(|(_, piece, _)| {
+                    piece.position() == *position && piece.orientation() == Some(*from)
+                })
+            {
+                piece.set_orientation(*to);
+                let (_, layout) = piece_layout(&grid, &piece);
+                transform.translation.x = layout.translation.x;
+                transform.translation.y = layout.translation.y;
+                transform.rotation = layout.rotation;
+            }
+        }
+    }
+
+    info!("{} {:?}", if undo { "Undo" } else { "Redo" }, action);
+}
+
+/// Turn the selected gate with R (Shift+R turns it back), the turn actions
+/// or the mouse wheel, if its turned footprint fits on the board.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_rotation(
+    actions: Res<ActionState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    scroll: Res<AccumulatedMouseScroll>,
+    mut cursor_query: Query<&mut PlayerCursor>,
+    mut piece_query: Query<(Entity, &mut LogicPiece, &mut Transform, Option<&PieceFlags>)>,
+    level_query: Query<&CurrentLevel>,
+    grid: Res<BoardGrid>,
+    mut history: ResMut<ActionHistory>,
+) {
+    let Some(clockwise) = rotation_input(&actions, &keyboard, &scroll) else {
+        return;
+    };
+    let Ok(mut cursor) = cursor_query.single_mut() else {
+        return;
+    };
+    let Some(selected) = cursor.selected_piece else {
+        return;
+    };
+    let Ok((_, piece, _, flags)) = piece_query.get(selected) else {
+        return;
+    };
+    let Some(from) = piece
+        .orientation()
+        .filter(|_| flags.is_some_and(|f| f.movable))
+    else {
+        return;
+    };
+    let to = if clockwise {
+        from.clockwise()
+    } else {
+        from.counter_clockwise()
+    };
+    let mut turned = piece.clone();
+    turned.set_orientation(to);
+
+    // The turned gate must fit among the other pieces
+    let mut board = BoardState::with_pieces(
+        grid.width,
+        grid.height,
+        piece_query
+            .iter()
+            .filter(|(entity, _, _, _)| *entity != selected)
+            .map(|(_, p, _, _)| p.clone())
+            .collect(),
+    );
+    if let Ok(level) = level_query.single() {
+        board.obstacles = level.0.initial_state.obstacles.clone();
+    }
+    if !board.fits(&turned, &[]) {
+        info!("No room to turn the piece");
+        return;
+    }
+
+    // Commit a move in progress first, so undo replays them in order
+    let position = turned.position();
+    if let Some(picked_up) = cursor.selected_from.replace(position) {
+        if picked_up != position {
+            let mut before = piece.clone();
+            before.set_position(picked_up);
+            history.record(PlayAction::Move {
+                piece: before,
+                to: position,
+            });
+        }
+    }
+
+    let Ok((_, mut piece, mut transform, _)) = piece_query.get_mut(selected) else {
+        return;
+    };
+    let (_, layout) = piece_layout(&grid, &turned);
+    *piece = turned;
+    transform.translation.x = layout.translation.x;
+    transform.translation.y = layout.translation.y;
+    transform.rotation = layout.rotation;
+    history.record(PlayAction::Rotate { position, from, to });
+    info!("Turned piece to face {:?}", to);
+}
+
+/// Re-evaluate the circuit whenever a piece is added, changed or removed.
+pub fn update_board(
+    piece_query: Query<(Entity, Ref<LogicPiece>), With<GameEntity>>,
+    mut removed: RemovedComponents<LogicPiece>,
+    mut live: ResMut<LiveCircuit>,
+) {
+    let removed_any = removed.read().count() > 0;
+    let changed = piece_query.iter().any(|(_, piece)| piece.is_changed());
+    if !removed_any && !changed {
+        return;
+    }
+    let (entities, pieces): (Vec<Entity>, Vec<LogicPiece>) = piece_query
+        .iter()
+        .map(|(entity, piece)| (entity, (*piece).clone()))
+        .unzip();
+    live.state = CircuitState::evaluate(&pieces);
+    live.entities = entities;
 }
 
 // Update piece positions system
 pub fn update_piece_positions(
     cursor_query: Query<&PlayerCursor>,
-    mut piece_query: Query<(&mut Transform, &mut LogicPiece, Option<&PlayerPlaced>)>,
+    mut piece_query: Query<(Entity, &mut Transform, &mut LogicPiece, Option<&PieceFlags>)>,
+    level_query: Query<&CurrentLevel>,
+    grid: Res<BoardGrid>,
 ) {
     let Ok(cursor) = cursor_query.single() else {
         return;
     };
+    let Some(selected) = cursor.selected_piece else {
+        return;
+    };
+    let Ok((_, _, piece, flags)) = piece_query.get(selected) else {
+        return;
+    };
+    // Only move movable pieces; wires are rerouted by dragging an end
+    if !flags.is_some_and(|f| f.movable) || matches!(piece, LogicPiece::Wire { .. }) {
+        return;
+    }
 
-    if let Some(selected_entity) = cursor.selected_piece {
-        if let Ok((mut transform, mut piece, player_placed)) = piece_query.get_mut(selected_entity)
-        {
-            // Only move player-placed pieces
-            if player_placed.is_some() {
-                // Snap to grid
-                let grid_x = ((cursor.position.x / 80.0).round() as i32 + 4).clamp(0, 9) as u32;
-                let grid_y = ((cursor.position.y / 80.0).round() as i32 + 4).clamp(0, 9) as u32;
-
-                // Update piece position
-                piece.set_position((grid_x, grid_y));
-
-                // Update visual position
-                transform.translation.x = (grid_x as f32 - 4.5) * 80.0;
-                transform.translation.y = (grid_y as f32 - 4.5) * 80.0;
-            }
-        }
+    // Snap to grid
+    let mut moved = piece.clone();
+    moved.set_position(grid.clamp_to_cell(cursor.position));
+    if moved == *piece {
+        return;
+    }
+
+    // Never off the board, onto blocked terrain or onto another piece
+    let mut board = BoardState::with_pieces(
+        grid.width,
+        grid.height,
+        piece_query
+            .iter()
+            .filter(|(entity, _, _, _)| *entity != selected)
+            .map(|(_, _, p, _)| p.clone())
+            .collect(),
+    );
+    if let Ok(level) = level_query.single() {
+        board.obstacles = level.0.initial_state.obstacles.clone();
     }
+    if !board.fits(&moved, &[]) {
+        return;
+    }
+
+    let Ok((_, mut transform, mut piece, _)) = piece_query.get_mut(selected) else {
+        return;
+    };
+    // Update piece position
+    *piece = moved;
+
+    // Update visual position
+    let (_, layout) = piece_layout(&grid, &piece);
+    transform.translation.x = layout.translation.x;
+    transform.translation.y = layout.translation.y;
 }
 
-// Check connections system
-pub fn check_connections() {
-    // Reserved for visual connection feedback
+/// Colour the board by signal: wires carrying a formula light up, and
+/// gates and goals nothing reaches are dimmed.
+pub fn check_connections(live: Res<LiveCircuit>, mut sprites: Query<(&LogicPiece, &mut Sprite)>) {
+    if !live.is_changed() {
+        return;
+    }
+    for (i, &entity) in live.entities.iter().enumerate() {
+        let Ok((piece, mut sprite)) = sprites.get_mut(entity) else {
+            continue;
+        };
+        let carries = live.state.carries(i);
+        sprite.color = match piece {
+            LogicPiece::Wire { .. } if carries => Color::srgb(1.0, 0.85, 0.3),
+            _ if carries => piece.color(),
+            _ => piece.color().with_alpha(0.45),
+        };
+    }
 }
 
 // Check solution system
+#[allow(clippy::too_many_arguments)]
 pub fn check_solution(
-    level_query: Query<&CurrentLevel>,
-    piece_query: Query<&LogicPiece>,
+    mut commands: Commands,
+    level_query: Query<(&CurrentLevel, Option<&LevelKey>)>,
+    piece_query: Query<(&LogicPiece, Has<PlayerPlaced>)>,
     mut next_state: ResMut<NextState<GameState>>,
     mut stats: ResMut<PlayerStats>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    settings: Res<Settings>,
+    test_play: Option<ResMut<TestPlaySession>>,
 ) {
-    let Ok(current_level) = level_query.single() else {
+    let Ok((current_level, key)) = level_query.single() else {
         return;
     };
 
-    // Manual trigger for verification (Space bar)
-    if keyboard.just_pressed(KeyCode::Space) {
+    // Manual trigger for verification (Space bar by default)
+    if actions.just_pressed(Action::Verify) {
         info!("Verifying solution...");
 
         // Collect all pieces
-        let pieces: Vec<LogicPiece> = piece_query.iter().cloned().collect();
+        let pieces: Vec<LogicPiece> = piece_query.iter().map(|(p, _)| p.clone()).collect();
 
         info!("  Pieces on board: {}", pieces.len());
         for piece in &pieces {
@@ -317,27 +929,128 @@ pub fn check_solution(
         }
 
         // Verify the solution
-        use crate::verification::VerificationVerdict;
-        match crate::verification::verify_level_solution(&current_level.0, &pieces) {
+        use crate::verification::{verify_level_solution, VerificationVerdict};
+        match verify_level_solution(&current_level.0, &pieces, &settings.verifier) {
             VerificationVerdict::Verified => {
                 info!("PROOF VERIFIED - Solution is correct!");
+                if let Some(mut test_play) = test_play {
+                    if test_play.active {
+                        test_play.solution = Some(pieces.clone());
+                    }
+                }
                 stats.complete_level();
+                commands.insert_resource(SolvedLevel {
+                    level: current_level.0.clone(),
+                    key: key.cloned(),
+                    pieces_used: piece_query.iter().filter(|(_, placed)| *placed).count() as u32,
+                    pieces,
+                });
                 next_state.set(GameState::LevelComplete);
             }
             VerificationVerdict::Rejected => {
+                if let Some(key) = key {
+                    stats.record_failure(key);
+                }
                 warn!("Solution incomplete - keep trying!");
-                warn!("Hint: Place an AND gate adjacent to P and Q, and adjacent to R");
+                warn!("Hint: feed each gate input from a facing output or a wire, and run an output into the goal");
             }
-            VerificationVerdict::CannotVerify => {
-                warn!(
+            VerificationVerdict::CannotVerify => match settings.verifier.backend {
+                VerifierBackend::Z3 => warn!(
                     "Cannot verify in this build: rebuild with \
                      `cargo run --features z3-verify` to verify solutions."
-                );
-            }
+                ),
+                VerifierBackend::Disabled => {
+                    warn!("Cannot verify: the verifier is disabled in the settings.")
+                }
+            },
         }
     }
 }
 
+/// Put the level back to its starting layout, dropping the player's pieces
+/// and restarting the timer.
+pub fn handle_reset_level(
+    mut commands: Commands,
+    mut events: MessageReader<ResetLevelEvent>,
+    level_query: Query<(&CurrentLevel, Option<&LevelKey>)>,
+    piece_query: Query<Entity, (With<LogicPiece>, Without<PlayerCursor>)>,
+    mut cursor_query: Query<&mut PlayerCursor>,
+    mut history: ResMut<ActionHistory>,
+    mut stats: ResMut<PlayerStats>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    let Ok((current_level, key)) = level_query.single() else {
+        return;
+    };
+
+    for entity in piece_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    let board = &current_level.0.initial_state;
+    for piece in board.pieces() {
+        spawn_level_piece(
+            &mut commands,
+            board,
+            piece,
+            board.flags_at(piece.position()),
+        );
+    }
+    if let Ok(mut cursor) = cursor_query.single_mut() {
+        cursor.selected_piece = None;
+        cursor.selected_from = None;
+        cursor.wire_start = None;
+    }
+    history.clear();
+    stats.pause_level();
+    stats.start_level();
+    if let Some(key) = key {
+        stats.record_attempt(key);
+    }
+    info!("Level reset");
+}
+
+/// Save the board and elapsed time when leaving a pack level, so the
+/// attempt can be resumed: the player's pieces, and the level's own pieces
+/// if they were moved, turned or deleted. Solved levels drop their save
+/// instead.
+#[allow(clippy::type_complexity)]
+pub fn save_level_in_progress(
+    level_query: Query<(&LevelKey, &CurrentLevel)>,
+    placed: Query<&LogicPiece, With<PlayerPlaced>>,
+    level_pieces: Query<(&LogicPiece, &PieceFlags), (With<GameEntity>, Without<PlayerPlaced>)>,
+    mut stats: ResMut<PlayerStats>,
+    history: Res<ActionHistory>,
+    pack_manager: Option<ResMut<LevelPackManager>>,
+) {
+    let (Ok((key, current_level)), Some(mut pack_manager)) = (level_query.single(), pack_manager)
+    else {
+        return;
+    };
+
+    if stats.is_level_running() {
+        let current = level_pieces
+            .iter()
+            .map(|(piece, flags)| (piece.clone(), *flags))
+            .collect();
+        let solution = SavedSolution {
+            pieces: placed.iter().cloned().collect(),
+            level_pieces: SavedSolution::level_changes(&current_level.0.initial_state, current),
+            elapsed_secs: stats.pause_level(),
+            history: history.clone(),
+        };
+        pack_manager.store_solution(&key.pack_id, key.level_id, solution);
+    } else {
+        pack_manager.clear_solution(&key.pack_id, key.level_id);
+    }
+
+    let path = pack_manager.solutions_path();
+    if let Err(e) = pack_manager.save_solutions(&path) {
+        warn!("Failed to save level in progress: {}", e);
+    }
+}
+
 // Cleanup system
 pub fn cleanup_level(mut commands: Commands, entities: Query<Entity, With<GameEntity>>) {
     info!("Cleaning up level...");
@@ -348,3 +1061,197 @@ pub fn cleanup_level(mut commands: Commands, entities: Query<Entity, With<GameEn
 
     info!("Level cleaned up");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::{ui, TestLevelEvent};
+    use crate::game::{Binding, Direction, InputMap};
+    use bevy::state::app::StatesPlugin;
+
+    /// A level with P and Q to combine into the goal, and a board that
+    /// solves it
+    fn testable_state() -> (EditorState, Vec<LogicPiece>) {
+        let mut state = EditorState::default();
+        state.level.name = "Combine".to_string();
+        state.add_piece(LogicPiece::Assumption {
+            formula: "P".to_string(),
+            position: (3, 5),
+        });
+        state.add_piece(LogicPiece::Assumption {
+            formula: "Q".to_string(),
+            position: (3, 4),
+        });
+        state.add_piece(LogicPiece::Goal {
+            formula: "Goal".to_string(),
+            position: (9, 4),
+        });
+        state.dirty = false;
+
+        let mut solution = state.level.initial_state.pieces().to_vec();
+        solution.push(LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        });
+        solution.push(LogicPiece::Wire {
+            from: (5, 4),
+            to: (8, 4),
+        });
+        (state, solution)
+    }
+
+    #[test]
+    fn test_test_play_round_trip() {
+        let (state, solution) = testable_state();
+        let before = state.level.clone();
+
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .init_state::<GameState>()
+            .add_message::<TestLevelEvent>()
+            .insert_resource(state)
+            .init_resource::<TestPlaySession>()
+            .init_resource::<PlayerStats>()
+            .init_resource::<ActionHistory>()
+            .init_resource::<ActionState>()
+            .init_resource::<Settings>()
+            .add_systems(Update, ui::handle_test_level)
+            .add_systems(OnEnter(GameState::Playing), load_level)
+            .add_systems(Update, check_solution.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Editor), ui::end_test_play);
+
+        // Testing plays the level being edited
+        app.world_mut().write_message(TestLevelEvent);
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<GameState>>().get(),
+            GameState::Playing
+        );
+        assert!(app.world().resource::<TestPlaySession>().active);
+        let loaded = app
+            .world_mut()
+            .query::<&CurrentLevel>()
+            .single(app.world())
+            .unwrap()
+            .0
+            .clone();
+        assert_eq!(loaded, before);
+
+        // Build the solution and ask for it to be verified
+        for piece in &solution {
+            if before.initial_state.pieces().contains(piece) {
+                app.world_mut().spawn((piece.clone(), GameEntity));
+            } else {
+                app.world_mut()
+                    .spawn((piece.clone(), PlayerPlaced, GameEntity));
+            }
+        }
+        let map = InputMap::default();
+        let verify: Vec<Binding> = map.bindings_for(Action::Verify).collect();
+        app.world_mut()
+            .resource_mut::<ActionState>()
+            .update(&map, |binding| {
+                let pressed = verify.contains(&binding);
+                (pressed, pressed)
+            });
+        app.update();
+
+        // A verified test play offers its board back as the witness;
+        // without a verifier nothing is recorded
+        let recorded = app.world().resource::<TestPlaySession>().solution.clone();
+        #[cfg(feature = "z3-verify")]
+        {
+            let recorded = recorded.as_ref().unwrap();
+            assert_eq!(recorded.len(), solution.len());
+            assert!(recorded.iter().all(|piece| solution.contains(piece)));
+            assert!(app.world().get_resource::<SolvedLevel>().is_some());
+        }
+        #[cfg(not(feature = "z3-verify"))]
+        assert_eq!(recorded, None);
+
+        // The player heads back to an untouched editor
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Editor);
+        app.update();
+        assert!(!app.world().resource::<TestPlaySession>().active);
+        let mut editor = app.world_mut().resource_mut::<EditorState>();
+        assert_eq!(editor.level, before);
+        assert!(!editor.dirty);
+
+        // Saving the witness is an edit like any other, and nothing else
+        // about the level changes
+        let witness = recorded.unwrap_or(solution);
+        editor.set_witness(witness.clone());
+        assert!(editor.dirty);
+        assert!(editor.undo());
+        assert_eq!(editor.level, before);
+        assert!(editor.redo());
+        assert_eq!(editor.level.witness, Some(witness));
+    }
+
+    #[test]
+    fn test_undo_that_no_longer_applies_is_dropped() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ActionState>()
+            .init_resource::<ActionHistory>()
+            .init_resource::<BoardGrid>()
+            .add_systems(Update, handle_undo_redo);
+        let undo = |app: &mut App| {
+            let map = InputMap::default();
+            let undo: Vec<Binding> = map.bindings_for(Action::Undo).collect();
+            app.world_mut()
+                .resource_mut::<ActionState>()
+                .update(&map, |binding| {
+                    let pressed = undo.contains(&binding);
+                    (pressed, pressed)
+                });
+            app.update();
+        };
+        let pieces = |app: &mut App| {
+            let mut query = app.world_mut().query::<&LogicPiece>();
+            query.iter(app.world()).cloned().collect::<Vec<_>>()
+        };
+
+        // An OR placed at (6, 6), after an AND deleted from (4, 4) whose
+        // cell a NOT has taken since
+        let not_gate = LogicPiece::NotIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        let or_gate = LogicPiece::OrIntro {
+            position: (6, 6),
+            orientation: Direction::East,
+        };
+        app.world_mut()
+            .spawn((not_gate.clone(), PieceFlags::FREE, Transform::default()));
+        app.world_mut()
+            .spawn((or_gate.clone(), PieceFlags::FREE, Transform::default()));
+        let mut history = app.world_mut().resource_mut::<ActionHistory>();
+        history.record(PlayAction::Delete {
+            piece: LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            },
+            level_flags: None,
+        });
+        history.record(PlayAction::Place {
+            piece: or_gate,
+            level_flags: None,
+        });
+
+        undo(&mut app);
+        assert_eq!(pieces(&mut app), vec![not_gate.clone()]);
+        assert_eq!(app.world().resource::<ActionHistory>().len(), 1);
+
+        // Bringing the AND back would overlap the NOT, so nothing changes
+        // and the step is forgotten
+        undo(&mut app);
+        assert_eq!(pieces(&mut app), vec![not_gate]);
+        let history = app.world().resource::<ActionHistory>();
+        assert!(!history.can_undo());
+        assert_eq!(history.len(), 0);
+    }
+}
@@ -1,14 +1,26 @@
 // SPDX-License-Identifier: MPL-2.0
 //! Level pack management - loading, saving, and organizing levels.
 
+pub mod storage;
 pub mod ui;
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::game::{BoardState, GoalCondition, Level, LogicPiece};
+use crate::game::{ActionHistory, BoardState, GoalCondition, Level, LogicPiece, PieceFlags};
+
+/// File in the packs directory holding completion progress
+pub const PROGRESS_FILE: &str = "progress.json";
+
+/// File in the packs directory holding in-progress solutions
+pub const SOLUTIONS_FILE: &str = "solutions.json";
+
+/// Format written into saved packs. Packs without one (format 0) were
+/// saved before pieces had footprints and ports, and are brought up to
+/// date when loaded.
+pub const PACK_FORMAT: u32 = 1;
 
 /// A collection of levels bundled together
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +41,9 @@ pub struct LevelPack {
     pub tags: Vec<String>,
     /// The levels in this pack
     pub levels: Vec<Level>,
+    /// File format the pack was saved in
+    #[serde(default)]
+    pub format: u32,
 }
 
 impl Default for LevelPack {
@@ -42,6 +57,7 @@ fn default() -> Self {
             difficulty: 1,
             tags: vec![],
             levels: vec![],
+            format: PACK_FORMAT,
         }
     }
 }
@@ -134,11 +150,27 @@ pub fn check_difficulty_in_range(&self) -> Result<(), LevelPackError> {
     pub fn load(path: &Path) -> Result<Self, LevelPackError> {
         let content =
             fs::read_to_string(path).map_err(|e| LevelPackError::IoError(e.to_string()))?;
-        let pack: Self = serde_json::from_str(&content)
+        let mut pack: Self = serde_json::from_str(&content)
             .map_err(|e| LevelPackError::DeserializationError(e.to_string()))?;
         pack.check_difficulty_in_range()?;
+        pack.upgrade();
         Ok(pack)
     }
+
+    /// Bring a pack saved in an older format up to date. Format 0 gates
+    /// are moved where they no longer fit (see
+    /// [`BoardState::fit_pre_ports_gates`]), and witnesses are dropped:
+    /// they were recorded when any two nearby pieces connected, so they
+    /// prove nothing about wiring through ports.
+    pub fn upgrade(&mut self) {
+        if self.format == 0 {
+            for level in &mut self.levels {
+                level.initial_state.fit_pre_ports_gates();
+                level.witness = None;
+            }
+        }
+        self.format = PACK_FORMAT;
+    }
 }
 
 /// Errors that can occur when working with level packs
@@ -149,6 +181,8 @@ pub enum LevelPackError {
     SerializationError(String),
     DeserializationError(String),
     NotFound(String),
+    /// A name or argument the operation can't accept
+    Invalid(String),
     /// Pack-level difficulty outside the documented 1..=5 range
     /// (I5 `DifficultyInRange`; see src/abi/ProofOfWork/ABI/Invariants.idr).
     DifficultyOutOfRange {
@@ -163,6 +197,7 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             Self::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             Self::DeserializationError(msg) => write!(f, "Deserialization error: {}", msg),
             Self::NotFound(msg) => write!(f, "Not found: {}", msg),
+            Self::Invalid(msg) => write!(f, "Invalid: {}", msg),
             Self::DifficultyOutOfRange { found } => write!(
                 f,
                 "pack difficulty {} outside documented 1..=5 range (I5 DifficultyInRange)",
@@ -190,15 +225,60 @@ pub struct LevelCompletion {
     pub times_completed: u32,
 }
 
+/// A level the player left part-way through
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSolution {
+    /// Pieces the player had placed
+    pub pieces: Vec<LogicPiece>,
+    /// The level's own pieces as the player left them, if any were moved,
+    /// turned or deleted; `None` means they are as the level sets them out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level_pieces: Option<Vec<(LogicPiece, PieceFlags)>>,
+    /// Seconds already spent on the level
+    pub elapsed_secs: u64,
+    /// The player's undo and redo steps, picked up again on resuming
+    #[serde(default, skip_serializing_if = "ActionHistory::is_empty")]
+    pub history: ActionHistory,
+}
+
+impl SavedSolution {
+    /// The level pieces to save, given the level's starting board and the
+    /// pieces still on the board: `None` if nothing has changed.
+    pub fn level_changes(
+        initial: &BoardState,
+        current: Vec<(LogicPiece, PieceFlags)>,
+    ) -> Option<Vec<(LogicPiece, PieceFlags)>> {
+        let unchanged = current.len() == initial.pieces().len()
+            && initial
+                .pieces()
+                .iter()
+                .all(|p| current.contains(&(p.clone(), initial.flags_at(p.position()))));
+        (!unchanged).then_some(current)
+    }
+
+    /// Whether the save records nothing the level doesn't already start with
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty() && self.level_pieces.is_none()
+    }
+}
+
 /// Manager for loading and tracking level packs
 #[derive(Debug, Default, bevy::prelude::Resource)]
 pub struct LevelPackManager {
-    /// Directory where level packs are stored
+    /// Directory where the player's own packs are stored
     pub packs_dir: PathBuf,
+    /// Read-only directory of packs shipped with the game
+    pub builtin_dir: Option<PathBuf>,
+    /// Ids of the built-in packs, which user packs can't replace
+    pub builtin_ids: HashSet<String>,
+    /// Directory holding the active profile's progress and saved solutions
+    pub save_dir: PathBuf,
     /// Loaded level packs
     pub packs: Vec<LevelPack>,
     /// Progress for each pack (by pack id)
     pub progress: HashMap<String, PackProgress>,
+    /// Unfinished solutions (by pack id, then level id)
+    pub solutions: HashMap<String, HashMap<u32, SavedSolution>>,
     /// Currently selected pack index
     pub current_pack_index: Option<usize>,
     /// Currently selected level index within the pack
@@ -210,17 +290,29 @@ impl LevelPackManager {
     /// Create a new manager with the specified packs directory
     pub fn new(packs_dir: PathBuf) -> Self {
         Self {
+            save_dir: packs_dir.clone(),
             packs_dir,
+            builtin_dir: None,
+            builtin_ids: HashSet::new(),
             packs: vec![],
             progress: HashMap::new(),
+            solutions: HashMap::new(),
             current_pack_index: None,
             current_level_index: None,
         }
     }
 
-    /// Load all level packs from the packs directory
+    /// Also load the packs shipped in `dir` as built-in packs
+    pub fn with_builtin_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.builtin_dir = dir;
+        self
+    }
+
+    /// Load the built-in packs, then the player's packs. A user pack whose
+    /// id is taken by a built-in pack is skipped.
     pub fn load_all(&mut self) -> Result<(), LevelPackError> {
         self.packs.clear();
+        self.builtin_ids.clear();
 
         // Create directory if it doesn't exist
         if !self.packs_dir.exists() {
@@ -228,29 +320,53 @@ pub fn load_all(&mut self) -> Result<(), LevelPackError> {
                 .map_err(|e| LevelPackError::IoError(e.to_string()))?;
         }
 
-        // Add built-in tutorial pack
-        self.packs.push(create_builtin_tutorial_pack());
-
-        // Load packs from directory
-        if let Ok(entries) = fs::read_dir(&self.packs_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().map(|e| e == "json").unwrap_or(false) {
-                    match LevelPack::load(&path) {
-                        Ok(pack) => {
-                            self.packs.push(pack);
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to load pack {:?}: {}", path, e);
-                        }
-                    }
-                }
+        // Built-in packs: the tutorial, then any shipped with the game
+        let mut builtin = vec![(PathBuf::new(), create_builtin_tutorial_pack())];
+        if let Some(dir) = &self.builtin_dir {
+            builtin.extend(read_pack_dir(dir));
+        }
+        for (path, pack) in builtin {
+            if self.builtin_ids.insert(pack.id.clone()) {
+                self.packs.push(pack);
+            } else {
+                tracing::warn!(
+                    "Skipping built-in pack {:?}: duplicate id '{}'",
+                    path,
+                    pack.id
+                );
+            }
+        }
+
+        for (path, pack) in read_pack_dir(&self.packs_dir) {
+            if self.is_builtin(&pack.id) {
+                tracing::warn!(
+                    "Skipping pack {:?}: '{}' is the id of a built-in pack",
+                    path,
+                    pack.id
+                );
+            } else {
+                self.packs.push(pack);
             }
         }
 
         Ok(())
     }
 
+    /// Whether the pack with this id ships with the game and is read-only
+    pub fn is_builtin(&self, pack_id: &str) -> bool {
+        self.builtin_ids.contains(pack_id)
+    }
+
+    /// Where completion progress is saved
+    pub fn progress_path(&self) -> PathBuf {
+        self.save_dir.join(PROGRESS_FILE)
+    }
+
+    /// Where unfinished solutions are saved
+    pub fn solutions_path(&self) -> PathBuf {
+        self.save_dir.join(SOLUTIONS_FILE)
+    }
+
     /// Get the current level based on selected indices
     pub fn current_level(&self) -> Option<&Level> {
         let pack_idx = self.current_pack_index?;
@@ -258,6 +374,13 @@ pub fn current_level(&self) -> Option<&Level> {
         self.packs.get(pack_idx)?.levels.get(level_idx)
     }
 
+    /// Pack id and level id of the current level
+    pub fn current_level_key(&self) -> Option<(String, u32)> {
+        let pack = self.packs.get(self.current_pack_index?)?;
+        let level = pack.levels.get(self.current_level_index?)?;
+        Some((pack.id.clone(), level.id))
+    }
+
     /// Select a pack by index
     pub fn select_pack(&mut self, index: usize) {
         if index < self.packs.len() {
@@ -334,32 +457,103 @@ pub fn is_level_completed(&self, pack_id: &str, level_id: u32) -> bool {
             .unwrap_or(false)
     }
 
-    /// Save a user-created pack
+    /// The unfinished solution saved for a level, if any
+    pub fn saved_solution(&self, pack_id: &str, level_id: u32) -> Option<&SavedSolution> {
+        self.solutions.get(pack_id)?.get(&level_id)
+    }
+
+    /// Remember an unfinished solution, replacing any earlier one. A
+    /// board left as the level starts clears the save instead.
+    pub fn store_solution(&mut self, pack_id: &str, level_id: u32, solution: SavedSolution) {
+        if solution.is_empty() {
+            self.clear_solution(pack_id, level_id);
+            return;
+        }
+        self.solutions
+            .entry(pack_id.to_string())
+            .or_default()
+            .insert(level_id, solution);
+    }
+
+    /// Forget the unfinished solution for a level
+    pub fn clear_solution(&mut self, pack_id: &str, level_id: u32) {
+        if let Some(pack) = self.solutions.get_mut(pack_id) {
+            pack.remove(&level_id);
+            if pack.is_empty() {
+                self.solutions.remove(pack_id);
+            }
+        }
+    }
+
+    /// Save a user-created pack. Built-in packs are read-only.
     pub fn save_pack(&self, pack: &LevelPack) -> Result<PathBuf, LevelPackError> {
+        if self.is_builtin(&pack.id) {
+            return Err(LevelPackError::Invalid(format!(
+                "'{}' is a built-in pack and can't be changed",
+                pack.id
+            )));
+        }
         let filename = format!("{}.json", pack.id);
         let path = self.packs_dir.join(filename);
         pack.save(&path)?;
         Ok(path)
     }
 
-    /// Save progress to disk
+    /// Save progress to disk, keeping backups of earlier saves
     pub fn save_progress(&self, path: &Path) -> Result<(), LevelPackError> {
-        let json = serde_json::to_string_pretty(&self.progress)
-            .map_err(|e| LevelPackError::SerializationError(e.to_string()))?;
-        fs::write(path, json).map_err(|e| LevelPackError::IoError(e.to_string()))?;
-        Ok(())
+        storage::save(path, &self.progress)
     }
 
-    /// Load progress from disk
+    /// Load progress from disk, recovering from a backup if it is corrupt
     pub fn load_progress(&mut self, path: &Path) -> Result<(), LevelPackError> {
-        if path.exists() {
-            let content =
-                fs::read_to_string(path).map_err(|e| LevelPackError::IoError(e.to_string()))?;
-            self.progress = serde_json::from_str(&content)
-                .map_err(|e| LevelPackError::DeserializationError(e.to_string()))?;
+        if let Some(progress) = storage::load(path)? {
+            self.progress = progress;
         }
         Ok(())
     }
+
+    /// Save unfinished solutions to disk, keeping backups of earlier saves
+    pub fn save_solutions(&self, path: &Path) -> Result<(), LevelPackError> {
+        storage::save(path, &self.solutions)
+    }
+
+    /// Load unfinished solutions, recovering from a backup if corrupt
+    pub fn load_solutions(&mut self, path: &Path) -> Result<(), LevelPackError> {
+        if let Some(solutions) = storage::load(path)? {
+            self.solutions = solutions;
+        }
+        Ok(())
+    }
+}
+
+/// Every pack file in `dir`, skipping save files and logging packs that
+/// fail to load
+fn read_pack_dir(dir: &Path) -> Vec<(PathBuf, LevelPack)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let is_save_file = path
+                .file_name()
+                .is_some_and(|name| name == PROGRESS_FILE || name == SOLUTIONS_FILE);
+            !is_save_file && path.extension().is_some_and(|e| e == "json")
+        })
+        .collect();
+    // Directory order varies between platforms
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| match LevelPack::load(&path) {
+            Ok(pack) => Some((path, pack)),
+            Err(e) => {
+                tracing::warn!("Failed to load pack {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect()
 }
 
 /// Create the built-in tutorial level pack
@@ -372,16 +566,17 @@ pub fn create_builtin_tutorial_pack() -> LevelPack {
         version: "1.0.0".to_string(),
         difficulty: 1,
         tags: vec!["tutorial".to_string(), "beginner".to_string()],
+        format: PACK_FORMAT,
         levels: vec![
             Level {
                 id: 1,
                 name: "First Steps".to_string(),
-                description: "Place an AND gate to connect P and Q, then connect to R".to_string(),
+                description: "Wire P and Q into the inputs on the back of an AND gate, then wire its output to R".to_string(),
                 theorem: "(assert (=> (and P Q) R))".to_string(),
-                initial_state: BoardState {
-                    width: 10,
-                    height: 10,
-                    pieces: vec![
+                initial_state: BoardState::with_pieces(
+                    10,
+                    10,
+                    vec![
                         LogicPiece::Assumption {
                             formula: "P".to_string(),
                             position: (2, 5),
@@ -395,20 +590,22 @@ pub fn create_builtin_tutorial_pack() -> LevelPack {
                             position: (8, 4),
                         },
                     ],
-                },
+                ),
                 goal_state: GoalCondition::ProveFormula {
                     formula: "R".to_string(),
                 },
+                witness: None,
+                inventory: Default::default(),
             },
             Level {
                 id: 2,
                 name: "Either Way".to_string(),
-                description: "Use OR introduction to prove A ∨ B from A".to_string(),
+                description: "Wire A into either input of an OR gate and its output to the goal to prove A ∨ B".to_string(),
                 theorem: "(assert (=> A (or A B)))".to_string(),
-                initial_state: BoardState {
-                    width: 10,
-                    height: 10,
-                    pieces: vec![
+                initial_state: BoardState::with_pieces(
+                    10,
+                    10,
+                    vec![
                         LogicPiece::Assumption {
                             formula: "A".to_string(),
                             position: (2, 5),
@@ -418,20 +615,22 @@ pub fn create_builtin_tutorial_pack() -> LevelPack {
                             position: (8, 5),
                         },
                     ],
-                },
+                ),
                 goal_state: GoalCondition::ProveFormula {
                     formula: "(or A B)".to_string(),
                 },
+                witness: None,
+                inventory: Default::default(),
             },
             Level {
                 id: 3,
                 name: "Conjunction Junction".to_string(),
-                description: "Combine X, Y, and Z using multiple AND gates".to_string(),
+                description: "Wire X and Y into one AND gate, then its output and Z into a second".to_string(),
                 theorem: "(assert (=> (and (and X Y) Z) Result))".to_string(),
-                initial_state: BoardState {
-                    width: 10,
-                    height: 10,
-                    pieces: vec![
+                initial_state: BoardState::with_pieces(
+                    10,
+                    10,
+                    vec![
                         LogicPiece::Assumption {
                             formula: "X".to_string(),
                             position: (1, 7),
@@ -449,20 +648,22 @@ pub fn create_builtin_tutorial_pack() -> LevelPack {
                             position: (9, 5),
                         },
                     ],
-                },
+                ),
                 goal_state: GoalCondition::ProveFormula {
                     formula: "Result".to_string(),
                 },
+                witness: None,
+                inventory: Default::default(),
             },
             Level {
                 id: 4,
                 name: "Chain of Logic".to_string(),
-                description: "Build a chain: A → (A ∧ B) → Goal".to_string(),
+                description: "Build a chain: wire A and B into an AND gate, then its output on to Goal".to_string(),
                 theorem: "(assert (=> (and A B) Goal))".to_string(),
-                initial_state: BoardState {
-                    width: 10,
-                    height: 10,
-                    pieces: vec![
+                initial_state: BoardState::with_pieces(
+                    10,
+                    10,
+                    vec![
                         LogicPiece::Assumption {
                             formula: "A".to_string(),
                             position: (1, 6),
@@ -476,10 +677,12 @@ pub fn create_builtin_tutorial_pack() -> LevelPack {
                             position: (9, 5),
                         },
                     ],
-                },
+                ),
                 goal_state: GoalCondition::ProveFormula {
                     formula: "Goal".to_string(),
                 },
+                witness: None,
+                inventory: Default::default(),
             },
         ],
     }
@@ -488,6 +691,8 @@ pub fn create_builtin_tutorial_pack() -> LevelPack {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::validation::{validate_level, ValidationError};
+    use crate::game::{Direction, PlayAction};
 
     #[test]
     fn test_level_pack_creation() {
@@ -506,14 +711,12 @@ fn test_add_level() {
             name: "Test Level".to_string(),
             description: "Test".to_string(),
             theorem: "".to_string(),
-            initial_state: BoardState {
-                width: 10,
-                height: 10,
-                pieces: vec![],
-            },
+            initial_state: BoardState::new(10, 10),
             goal_state: GoalCondition::ProveFormula {
                 formula: "X".to_string(),
             },
+            witness: None,
+            inventory: Default::default(),
         });
         assert_eq!(pack.level_count(), 1);
     }
@@ -635,4 +838,212 @@ fn i5_load_accepts_in_range_pack() {
         let loaded = result.expect("load should succeed for difficulty=3");
         assert_eq!(loaded.difficulty, 3);
     }
+
+    #[test]
+    fn test_user_packs_cannot_shadow_builtin_packs() {
+        let root = std::env::temp_dir().join(format!(
+            "pow-builtin-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let builtin_dir = root.join("builtin");
+        let user_dir = root.join("user");
+        fs::create_dir_all(&builtin_dir).unwrap();
+        fs::create_dir_all(&user_dir).unwrap();
+        LevelPack::new("extra", "Extra", "Shipped")
+            .save(&builtin_dir.join("extra.json"))
+            .unwrap();
+        LevelPack::new("extra", "Fake Extra", "Player")
+            .save(&user_dir.join("extra.json"))
+            .unwrap();
+        LevelPack::new("tutorial", "Fake Tutorial", "Player")
+            .save(&user_dir.join("tutorial.json"))
+            .unwrap();
+        LevelPack::new("mine", "Mine", "Player")
+            .save(&user_dir.join("mine.json"))
+            .unwrap();
+
+        let mut manager = LevelPackManager::new(user_dir).with_builtin_dir(Some(builtin_dir));
+        manager.load_all().unwrap();
+        let packs: Vec<(&str, &str)> = manager
+            .packs
+            .iter()
+            .map(|p| (p.id.as_str(), p.author.as_str()))
+            .collect();
+        assert_eq!(
+            packs,
+            vec![
+                ("tutorial", "Proof of Work Team"),
+                ("extra", "Shipped"),
+                ("mine", "Player")
+            ]
+        );
+        assert!(manager.is_builtin("extra"));
+        assert!(!manager.is_builtin("mine"));
+
+        // Built-in packs can't be written back
+        assert!(manager.save_pack(&manager.packs[0].clone()).is_err());
+        assert!(manager.save_pack(&manager.packs[2].clone()).is_ok());
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_saved_solutions_round_trip() {
+        let dir = std::env::temp_dir().join(format!(
+            "pow-solutions-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SOLUTIONS_FILE);
+
+        let mut manager = LevelPackManager::new(dir.clone());
+        let mut solution = SavedSolution {
+            pieces: vec![LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            }],
+            level_pieces: None,
+            elapsed_secs: 42,
+            history: ActionHistory::default(),
+        };
+        // Undo steps come back with the board
+        solution.history.record(PlayAction::Place {
+            piece: solution.pieces[0].clone(),
+            level_flags: None,
+        });
+        manager.store_solution("tutorial", 1, solution.clone());
+        manager.save_solutions(&path).unwrap();
+
+        let mut reloaded = LevelPackManager::new(dir.clone());
+        reloaded.load_solutions(&path).unwrap();
+        assert_eq!(reloaded.saved_solution("tutorial", 1), Some(&solution));
+
+        // An empty board clears the save, and save files aren't packs
+        reloaded.store_solution(
+            "tutorial",
+            1,
+            SavedSolution {
+                pieces: vec![],
+                level_pieces: None,
+                elapsed_secs: 50,
+                history: ActionHistory::default(),
+            },
+        );
+        assert!(reloaded.solutions.is_empty());
+        reloaded.load_all().unwrap();
+        assert_eq!(reloaded.packs.len(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_level_piece_changes_are_saved() {
+        let assumption = LogicPiece::Assumption {
+            formula: "P".to_string(),
+            position: (1, 1),
+        };
+        let gate = LogicPiece::NotIntro {
+            position: (3, 1),
+            orientation: Direction::East,
+        };
+        let mut board = BoardState::with_pieces(10, 10, vec![assumption.clone(), gate.clone()]);
+        board.set_flags((3, 1), PieceFlags::FREE);
+        let locked = PieceFlags::default();
+
+        // The same pieces in any order are no change
+        let current = vec![
+            (gate.clone(), PieceFlags::FREE),
+            (assumption.clone(), locked),
+        ];
+        assert_eq!(SavedSolution::level_changes(&board, current), None);
+
+        let mut turned = gate.clone();
+        assert!(turned.set_orientation(Direction::North));
+        let current = vec![(assumption.clone(), locked), (turned, PieceFlags::FREE)];
+        assert_eq!(
+            SavedSolution::level_changes(&board, current.clone()),
+            Some(current)
+        );
+        let current = vec![(assumption, locked)];
+        let changes = SavedSolution::level_changes(&board, current.clone());
+        assert_eq!(changes, Some(current));
+
+        // Deleting a level piece is worth saving even with nothing placed
+        let mut manager = LevelPackManager::default();
+        manager.store_solution(
+            "tutorial",
+            1,
+            SavedSolution {
+                pieces: vec![],
+                level_pieces: changes,
+                elapsed_secs: 5,
+                history: ActionHistory::default(),
+            },
+        );
+        assert!(manager.saved_solution("tutorial", 1).is_some());
+    }
+
+    /// A pack as saved before pieces had footprints: no format, and gates
+    /// without an orientation
+    const PRE_PORTS_PACK: &str = r#"{
+        "id": "old", "name": "Old", "author": "Someone",
+        "description": "", "version": "1.0.0", "difficulty": 1, "tags": [],
+        "levels": [{
+            "id": 1, "name": "Crowded", "description": "",
+            "theorem": "(assert P)",
+            "initial_state": {
+                "width": 5, "height": 5,
+                "pieces": [
+                    {"Assumption": {"formula": "P", "position": [0, 2]}},
+                    {"AndIntro": {"position": [2, 4]}},
+                    {"OrIntro": {"position": [3, 1]}},
+                    {"ImpliesIntro": {"position": [3, 2]}},
+                    {"NotIntro": {"position": [1, 4]}},
+                    {"Goal": {"formula": "P", "position": [4, 2]}}
+                ],
+                "flags": [{"position": [2, 4], "movable": true}]
+            },
+            "goal_state": {"ProveFormula": {"formula": "P"}},
+            "witness": [{"Assumption": {"formula": "P", "position": [0, 2]}}]
+        }]
+    }"#;
+
+    #[test]
+    fn test_pre_ports_packs_load_with_gates_that_fit() {
+        let path = std::env::temp_dir().join(format!(
+            "pow-pre-ports-{}.json",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::write(&path, PRE_PORTS_PACK).unwrap();
+        let pack = LevelPack::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(pack.format, PACK_FORMAT);
+        let level = &pack.levels[0];
+        let board = &level.initial_state;
+        assert!(board
+            .pieces()
+            .iter()
+            .all(|p| board.fits(p, &[p.position()])));
+        // The AND on the top row and the OR under the IMPLIES move down a
+        // row, the AND keeping its flags; single-cell pieces stay put
+        let positions: Vec<_> = board.pieces().iter().map(|p| p.position()).collect();
+        assert_eq!(positions, [(0, 2), (2, 3), (3, 0), (3, 2), (1, 4), (4, 2)]);
+        assert!(board.flags_at((2, 3)).movable);
+        assert!(board.flags_at((2, 4)).is_locked());
+        assert!(validate_level(level).errors.iter().all(|e| !matches!(
+            e,
+            ValidationError::OutOfBounds { .. } | ValidationError::OverlappingPieces { .. }
+        )));
+        assert_eq!(level.witness, None);
+    }
 }
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Crash-safe JSON save files.
+//!
+//! Saves are written to a temporary file, flushed, and renamed over the
+//! old file, so a crash mid-write never leaves a half-written save. The
+//! payload is wrapped with a SHA-256 checksum, and the previous few
+//! versions are kept as numbered backups (`progress.json.bak1`, ...).
+//! Loading falls back to the newest backup that passes its checksum.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::LevelPackError;
+
+/// Number of older versions kept next to each save file
+pub const BACKUP_COUNT: usize = 3;
+
+/// A payload and the checksum of its JSON encoding
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    checksum: String,
+    data: serde_json::Value,
+}
+
+/// Path of the `n`th backup of a save file (1 is the newest)
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    with_suffix(path, &format!(".bak{}", n))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn checksum(data: &serde_json::Value) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(data.to_string());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Write `value` to `path` atomically, keeping the previous versions as
+/// backups
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), LevelPackError> {
+    let data = serde_json::to_value(value)
+        .map_err(|e| LevelPackError::SerializationError(e.to_string()))?;
+    let envelope = Envelope {
+        checksum: checksum(&data),
+        data,
+    };
+    let json = serde_json::to_string_pretty(&envelope)
+        .map_err(|e| LevelPackError::SerializationError(e.to_string()))?;
+
+    let io = |e: std::io::Error| LevelPackError::IoError(e.to_string());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io)?;
+    }
+    let tmp = with_suffix(path, ".tmp");
+    let mut file = fs::File::create(&tmp).map_err(io)?;
+    file.write_all(json.as_bytes()).map_err(io)?;
+    file.sync_all().map_err(io)?;
+    drop(file);
+
+    // Only a save that still passes its checksum is worth keeping as a backup
+    if read_valid::<serde_json::Value>(path).is_some() {
+        for n in (1..BACKUP_COUNT).rev() {
+            let older = backup_path(path, n);
+            if older.exists() {
+                fs::rename(&older, backup_path(path, n + 1)).map_err(io)?;
+            }
+        }
+        fs::copy(path, backup_path(path, 1)).map_err(io)?;
+    }
+    fs::rename(&tmp, path).map_err(io)
+}
+
+/// Read `path`, falling back to the newest good backup if it is missing
+/// or corrupt. Returns `None` if there is nothing to load. A save with no
+/// good copy left is moved aside to `<name>.corrupt` rather than failing,
+/// so the player starts fresh without the bad file being overwritten.
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, LevelPackError> {
+    if let Some(value) = read_valid(path) {
+        return Ok(Some(value));
+    }
+    for n in 1..=BACKUP_COUNT {
+        let backup = backup_path(path, n);
+        if let Some(value) = read_valid(&backup) {
+            if path.exists() {
+                tracing::warn!(
+                    "{} is corrupt; restored from backup {}",
+                    path.display(),
+                    backup.display()
+                );
+            }
+            return Ok(Some(value));
+        }
+    }
+    if path.exists() {
+        let aside = with_suffix(path, ".corrupt");
+        tracing::warn!(
+            "{} is corrupt and has no good backup; moved it to {}",
+            path.display(),
+            aside.display()
+        );
+        fs::rename(path, &aside).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+    }
+    Ok(None)
+}
+
+/// Parse a save file, checking its checksum. Files from before checksums
+/// were added hold the bare payload and are accepted as they are.
+fn read_valid<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = fs::read_to_string(path).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let data = match serde_json::from_value::<Envelope>(value.clone()) {
+        Ok(envelope) if checksum(&envelope.data) == envelope.checksum => envelope.data,
+        Ok(_) => return None,
+        Err(_) => value,
+    };
+    serde_json::from_value(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("pow-storage-{}-{}", tag, nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn scores(n: u32) -> HashMap<String, u32> {
+        HashMap::from([("level".to_string(), n)])
+    }
+
+    #[test]
+    fn test_save_round_trips_and_rotates_backups() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("progress.json");
+        for n in 1..=5 {
+            save(&path, &scores(n)).unwrap();
+        }
+
+        assert_eq!(
+            load::<HashMap<String, u32>>(&path).unwrap(),
+            Some(scores(5))
+        );
+        assert_eq!(
+            load::<HashMap<String, u32>>(&backup_path(&path, 1)).unwrap(),
+            Some(scores(4))
+        );
+        assert!(backup_path(&path, BACKUP_COUNT).exists());
+        assert!(!backup_path(&path, BACKUP_COUNT + 1).exists());
+        assert!(!with_suffix(&path, ".tmp").exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_corrupt_save_falls_back_to_backup() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join("progress.json");
+        save(&path, &scores(1)).unwrap();
+        save(&path, &scores(2)).unwrap();
+
+        // A truncated write
+        fs::write(&path, "{\"checksum\": \"ab").unwrap();
+        assert_eq!(
+            load::<HashMap<String, u32>>(&path).unwrap(),
+            Some(scores(1))
+        );
+
+        // Edited data no longer matches its checksum
+        let tampered = fs::read_to_string(backup_path(&path, 1))
+            .unwrap()
+            .replace(": 1", ": 99");
+        fs::write(&path, tampered).unwrap();
+        fs::remove_file(backup_path(&path, 1)).unwrap();
+        assert_eq!(load::<HashMap<String, u32>>(&path).unwrap(), None);
+        assert!(with_suffix(&path, ".corrupt").exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_loads_saves_written_before_checksums() {
+        let dir = temp_dir("legacy");
+        let path = dir.join("progress.json");
+        fs::write(&path, serde_json::to_string(&scores(7)).unwrap()).unwrap();
+        assert_eq!(
+            load::<HashMap<String, u32>>(&path).unwrap(),
+            Some(scores(7))
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -233,10 +233,9 @@ pub fn level_select_ui_system(
                         egui::Button::new(egui::RichText::new("Play Selected").size(18.0)),
                     )
                     .clicked()
+                    && has_level_selected
                 {
-                    if has_level_selected {
-                        play_level = true;
-                    }
+                    play_level = true;
                 }
             } else {
                 columns[1].label("Select a pack to see levels");
@@ -262,12 +261,6 @@ pub fn init_level_packs(mut pack_manager: ResMut<LevelPackManager>) {
         warn!("Failed to load level packs: {}", e);
     }
 
-    // Load progress
-    let progress_path = pack_manager.packs_dir.join("progress.json");
-    if let Err(e) = pack_manager.load_progress(&progress_path) {
-        warn!("Failed to load progress: {}", e);
-    }
-
     // Select first pack if available
     if !pack_manager.packs.is_empty() {
         pack_manager.select_pack(0);
@@ -278,7 +271,7 @@ pub fn init_level_packs(mut pack_manager: ResMut<LevelPackManager>) {
 
 /// Save progress when exiting level select
 pub fn save_level_progress(pack_manager: Res<LevelPackManager>) {
-    let progress_path = pack_manager.packs_dir.join("progress.json");
+    let progress_path = pack_manager.progress_path();
     if let Err(e) = pack_manager.save_progress(&progress_path) {
         warn!("Failed to save progress: {}", e);
     }
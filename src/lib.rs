@@ -7,11 +7,15 @@
 pub mod editor;
 pub mod game;
 pub mod levels;
+pub mod profiles;
+pub mod protocol;
+pub mod server;
+pub mod settings;
 pub mod states;
 pub mod verification;
 
 // Re-export commonly used types
 pub use editor::EditorState;
-pub use game::{BoardState, GoalCondition, Level, LogicPiece};
+pub use game::{BoardState, Direction, GoalCondition, Level, LogicPiece};
 pub use levels::{LevelPack, LevelPackManager};
 pub use verification::ExportedProof;
@@ -1,13 +1,15 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use bevy::input::InputSystems;
 use bevy::prelude::*;
 use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
-use std::path::PathBuf;
 
 mod editor;
 mod game;
 mod game_systems;
 mod levels;
+mod profiles;
+mod settings;
 mod states;
 mod ui;
 mod verification;
@@ -16,19 +18,43 @@
 
 #[cfg(feature = "network")]
 mod network;
+#[cfg(feature = "network")]
+#[allow(dead_code)]
+mod protocol;
 
 #[cfg(feature = "steam")]
 mod steam;
 
-use editor::{EditorState, SaveLevelEvent, TestLevelEvent};
-use game::{CurrentLevel, PlayerStats, SelectedPieceType};
+use editor::{
+    EditorAutosave, EditorDiagnostics, EditorState, SaveLevelEvent, TestLevelEvent, TestPlaySession,
+};
+use game::{
+    ActionHistory, ActionState, BoardGrid, LiveCircuit, PlayerStats, ResetLevelEvent,
+    SelectedPieceType, SolvedLevel,
+};
 use levels::LevelPackManager;
+use profiles::ui::ProfilePickerState;
+use profiles::ProfileManager;
+use settings::paths::{CliArgs, GamePaths};
+use settings::SettingsFile;
 use verification::ExportedProof;
 
 #[cfg(feature = "steam")]
 use steam::SteamManager;
 
 fn main() {
+    let cli = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(cli) if cli.help => {
+            println!("{}", settings::paths::USAGE);
+            return;
+        }
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, settings::paths::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     // Initialize Steam first (before Bevy) - only if feature enabled
     #[cfg(feature = "steam")]
     let steam_manager: Option<SteamManager> = match SteamManager::new() {
@@ -45,18 +71,21 @@ fn main() {
         }
     };
 
+    // Settings can say where everything else lives, so load them first
+    let env = |name: &str| std::env::var(name).ok();
+    let config_dir = GamePaths::config_dir(&cli, env);
+    let settings_file = SettingsFile::new(config_dir.join(settings::SETTINGS_FILE));
+    let settings = settings_file.load();
+    let paths = GamePaths::resolve(&cli, &settings, env);
+    let primary_window = settings.window.window("Proof of Work - Logic Puzzle Game");
+
     // Build and run the app
     let mut app = App::new();
 
     app
         // Core Bevy plugins
         .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Proof of Work - Logic Puzzle Game".into(),
-                resolution: (1280, 720).into(),
-                resizable: true,
-                ..default()
-            }),
+            primary_window: Some(primary_window),
             ..default()
         }))
         // Egui plugin for UI
@@ -67,13 +96,32 @@ fn main() {
         .insert_resource(PlayerStats::default())
         // Selected piece type resource
         .insert_resource(SelectedPieceType::default())
+        // Rebindable input bindings and the actions they trigger
+        .insert_resource(settings.bindings.clone())
+        .init_resource::<ActionState>()
+        // Undo/redo history for play mode
+        .init_resource::<ActionHistory>()
+        // Board dimensions for the camera and cell mapping
+        .init_resource::<BoardGrid>()
+        // What the board's circuit carries, for live feedback
+        .init_resource::<LiveCircuit>()
         // Level pack manager
-        .insert_resource(LevelPackManager::new(PathBuf::from("./data/packs")))
+        .insert_resource(
+            LevelPackManager::new(paths.user_packs_dir())
+                .with_builtin_dir(paths.builtin_packs_dir.clone()),
+        )
+        // Local player profiles
+        .insert_resource(ProfileManager::new(paths.profiles_dir()))
+        .init_resource::<ProfilePickerState>()
         // Editor state
         .insert_resource(EditorState::default())
+        .init_resource::<EditorDiagnostics>()
+        .init_resource::<TestPlaySession>()
+        .insert_resource(EditorAutosave::new(paths.editor_autosave_file()))
         // Editor events (messages in Bevy 0.17)
         .add_message::<TestLevelEvent>()
-        .add_message::<SaveLevelEvent>();
+        .add_message::<SaveLevelEvent>()
+        .add_message::<ResetLevelEvent>();
 
     // Insert Steam as a resource (if available)
     #[cfg(feature = "steam")]
@@ -88,24 +136,76 @@ fn main() {
             if let Some(steam) = steam {
                 let steam_id = steam.get_steam_id();
                 let api_key = format!("steam_{}", steam_id.raw());
-                app.insert_resource(network::NetworkClient::new(api_key));
+                app.insert_resource(network::NetworkClient::new(
+                    api_key,
+                    settings.server_url.clone(),
+                ));
             } else {
-                app.insert_resource(network::NetworkClient::new("offline_mode".to_string()));
+                app.insert_resource(network::NetworkClient::new(
+                    "offline_mode".to_string(),
+                    settings.server_url.clone(),
+                ));
             }
         }
         #[cfg(not(feature = "steam"))]
-        app.insert_resource(network::NetworkClient::new("offline_mode".to_string()));
+        app.insert_resource(network::NetworkClient::new(
+            "offline_mode".to_string(),
+            settings.server_url.clone(),
+        ));
+        app.add_systems(Update, network::apply_server_url)
+            .init_resource::<network::leaderboard::OnlineLeaderboard>()
+            .add_systems(
+                Update,
+                ui::leaderboard::fetch_leaderboard.run_if(in_state(GameState::Leaderboard)),
+            );
     }
 
+    // Machine-wide settings and the settings screen
+    info!("Data directory: {}", paths.data_dir.display());
+    info!("Settings file: {}", paths.settings_file().display());
+    app.insert_resource(settings)
+        .insert_resource(settings_file)
+        .insert_resource(paths)
+        .init_resource::<settings::ui::SettingsScreenState>()
+        .add_systems(
+            Update,
+            (
+                settings::apply_window_settings,
+                settings::apply_input_bindings,
+            ),
+        )
+        .add_systems(EguiPrimaryContextPass, settings::ui::apply_profile_settings)
+        .add_systems(OnEnter(GameState::Settings), settings::ui::open_settings)
+        .add_systems(
+            EguiPrimaryContextPass,
+            settings::ui::settings_screen_system.run_if(in_state(GameState::Settings)),
+        )
+        .add_systems(
+            OnExit(GameState::Settings),
+            (settings::save_settings, settings::ui::save_player_settings),
+        );
+
     app
         // Startup systems (run once at launch)
-        .add_systems(Startup, (setup_camera, levels::ui::init_level_packs))
+        .add_systems(
+            Startup,
+            (
+                setup_camera,
+                (levels::ui::init_level_packs, profiles::ui::init_profiles).chain(),
+            ),
+        )
+        // Read bound inputs into actions before any state's systems run
+        .add_systems(
+            PreUpdate,
+            game::actions::update_action_state.after(InputSystems),
+        )
         // Systems that run every frame in MainMenu state.
         // egui-drawing systems must run in the EguiPrimaryContextPass schedule
         // (bevy_egui 0.41); input/logic stays in Update.
         .add_systems(
             EguiPrimaryContextPass,
-            ui::main_menu_system.run_if(in_state(GameState::MainMenu)),
+            (ui::main_menu_system, profiles::ui::profile_picker_system)
+                .run_if(in_state(GameState::MainMenu)),
         )
         .add_systems(
             Update,
@@ -121,7 +221,14 @@ fn main() {
             levels::ui::save_level_progress,
         )
         // Editor state
-        .add_systems(OnEnter(GameState::Editor), editor::ui::spawn_editor_grid)
+        .add_systems(
+            OnEnter(GameState::Editor),
+            (
+                editor::ui::end_test_play,
+                editor::ui::offer_editor_recovery,
+                editor::ui::spawn_editor_grid,
+            ),
+        )
         .add_systems(
             EguiPrimaryContextPass,
             editor::ui::editor_ui_system.run_if(in_state(GameState::Editor)),
@@ -131,12 +238,23 @@ fn main() {
             (
                 editor::ui::editor_input_system,
                 editor::ui::update_editor_pieces,
+                editor::ui::update_editor_selection,
+                (
+                    editor::ui::refresh_editor_diagnostics,
+                    editor::ui::update_editor_diagnostics,
+                )
+                    .chain(),
+                editor::ui::update_editor_obstacles,
                 editor::ui::handle_test_level,
                 editor::ui::handle_save_level,
+                editor::ui::autosave_editor,
             )
                 .run_if(in_state(GameState::Editor)),
         )
-        .add_systems(OnExit(GameState::Editor), editor::ui::cleanup_editor)
+        .add_systems(
+            OnExit(GameState::Editor),
+            (editor::ui::autosave_on_exit, editor::ui::cleanup_editor),
+        )
         // Systems when entering Playing state
         .add_systems(
             OnEnter(GameState::Playing),
@@ -146,17 +264,30 @@ fn main() {
         .add_systems(
             Update,
             (
-                game_systems::handle_input,
-                game_systems::update_board,
+                (game_systems::move_cursor, game_systems::handle_input).chain(),
+                game_systems::handle_palette_actions,
+                game_systems::handle_undo_redo,
+                game_systems::handle_rotation,
+                game_systems::handle_reset_level,
                 game_systems::update_piece_positions,
-                game_systems::check_connections,
+                (game_systems::update_board, game_systems::check_connections).chain(),
                 game_systems::check_solution,
             )
                 .run_if(in_state(GameState::Playing)),
         )
         .add_systems(
             EguiPrimaryContextPass,
-            ui::update_hud.run_if(in_state(GameState::Playing)),
+            (ui::update_hud, ui::circuit_labels).run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            game::camera::camera_controls
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Editor))),
+        )
+        .add_systems(
+            EguiPrimaryContextPass,
+            ui::piece_labels
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Editor))),
         );
 
     // Steam callbacks (if available)
@@ -164,8 +295,21 @@ fn main() {
     app.add_systems(Update, steam_callbacks.run_if(in_state(GameState::Playing)));
 
     app
+        // Leaderboard screen
+        .init_resource::<ui::leaderboard::LeaderboardScreen>()
+        .add_systems(
+            OnEnter(GameState::Leaderboard),
+            ui::leaderboard::open_leaderboard,
+        )
+        .add_systems(
+            EguiPrimaryContextPass,
+            ui::leaderboard::leaderboard_screen_system.run_if(in_state(GameState::Leaderboard)),
+        )
         // Systems when entering LevelComplete state
-        .add_systems(OnEnter(GameState::LevelComplete), on_level_complete)
+        .add_systems(
+            OnEnter(GameState::LevelComplete),
+            (on_level_complete, profiles::ui::save_active_profile).chain(),
+        )
         // Systems that run in LevelComplete state
         .add_systems(
             EguiPrimaryContextPass,
@@ -176,7 +320,15 @@ fn main() {
             ui::handle_completion_input.run_if(in_state(GameState::LevelComplete)),
         )
         // Systems when exiting Playing state
-        .add_systems(OnExit(GameState::Playing), game_systems::cleanup_level)
+        .add_systems(
+            OnExit(GameState::Playing),
+            (
+                game_systems::save_level_in_progress,
+                profiles::ui::save_active_profile,
+                game_systems::cleanup_level,
+            )
+                .chain(),
+        )
         // Run the app
         .run();
 }
@@ -199,22 +351,32 @@ fn steam_callbacks(steam: Option<Res<SteamManager>>) {
 fn on_level_complete(
     #[cfg(feature = "steam")] steam: Option<Res<SteamManager>>,
     mut stats: ResMut<PlayerStats>,
-    level_query: Query<&CurrentLevel>,
+    solved: Option<Res<SolvedLevel>>,
+    test_play: Res<TestPlaySession>,
+    profiles: Res<ProfileManager>,
     #[cfg(feature = "network")] network: Res<network::NetworkClient>,
 ) {
-    let Ok(current_level) = level_query.single() else {
-        error!("No current level found!");
+    // Designer test plays don't count towards stats, achievements or the leaderboard
+    if test_play.active {
+        info!("Test play verified");
+        return;
+    }
+
+    let Some(solved) = solved else {
+        error!("No solved level found!");
         return;
     };
 
     // Update player stats
-    stats.proofs_completed += 1;
-    stats.levels_completed += 1;
-    stats.total_playtime_secs += stats.last_level_time_secs;
+    stats.record_solve(
+        solved.key.as_ref(),
+        solved.pieces_used,
+        game::stats::current_day(),
+    );
 
     info!("========================================");
     info!("  LEVEL COMPLETE!");
-    info!("  Level: {}", current_level.0.name);
+    info!("  Level: {}", solved.level.name);
     info!("  Time: {}s", stats.last_level_time_secs);
     info!("  Total proofs: {}", stats.proofs_completed);
     info!("========================================");
@@ -251,20 +413,36 @@ fn on_level_complete(
     }
 
     // Export proof
-    let proof = ExportedProof::from_level(&current_level.0, stats.last_level_time_secs);
+    let mut proof =
+        ExportedProof::from_solution(&solved.level, &solved.pieces, stats.last_level_time_secs);
+    if let Some(profile) = profiles.active_profile() {
+        proof.player_id = profile.player_id.clone();
+    }
     info!("Proof exported: {} bytes SMT-LIB2", proof.proof_smt2.len());
 
     // Submit proof to server (async, non-blocking)
     #[cfg(feature = "network")]
+    if profiles
+        .active_profile()
+        .is_some_and(|profile| profile.settings.submit_proofs)
     {
         let network_clone = network.clone();
+        let player_name = profiles.active_profile().map(|p| p.name.clone());
         std::thread::spawn(move || {
             let Ok(rt) = tokio::runtime::Runtime::new() else {
                 warn!("Failed to create async runtime for proof submission");
                 return;
             };
             rt.block_on(async {
-                match network_clone.submit_proof(proof).await {
+                match network_clone.submit_proof(proof, player_name).await {
+                    Ok(response) if !response.accepted => {
+                        let reason = response
+                            .rejection()
+                            .map(|rejection| rejection.detail)
+                            .or(response.message)
+                            .unwrap_or_default();
+                        warn!("Proof rejected by the server: {}", reason);
+                    }
                     Ok(response) => {
                         info!("Proof submitted successfully!");
                         info!("  Points awarded: {}", response.points_awarded);
@@ -1,40 +1,56 @@
 // SPDX-License-Identifier: MPL-2.0
-use super::{LeaderboardEntry, ProofSubmission, ServerResponse};
+use super::{
+    LeaderboardEntry, LeaderboardPage, LeaderboardQuery, PlayerStatsResponse, ProofSubmission,
+    ServerResponse,
+};
+use crate::protocol::{sign_proof, LEADERBOARD_PATH, PLAYER_STATS_PATH, PROOFS_PATH};
 use crate::verification::ExportedProof;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use bevy::prelude::Resource;
+use reqwest::{Client, Url};
 
-const SERVER_URL: &str = "https://api.proofofwork.game";
-
-#[derive(Clone)]
+#[derive(Clone, Resource)]
 pub struct NetworkClient {
     client: Client,
     api_key: String,
+    /// Base URL of the proof server, without a trailing slash
+    server_url: String,
 }
 
 impl NetworkClient {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, server_url: String) -> Self {
         Self {
             client: Client::builder()
                 .timeout(std::time::Duration::from_secs(30))
                 .build()
                 .expect("Failed to create HTTP client"),
             api_key,
+            server_url: server_url.trim_end_matches('/').to_string(),
         }
     }
 
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    pub fn set_server_url(&mut self, server_url: &str) {
+        self.server_url = server_url.trim_end_matches('/').to_string();
+    }
+
+    /// Submit a verified proof, to be shown on the boards as `player_name`
     pub async fn submit_proof(
         &self,
         proof: ExportedProof,
+        player_name: Option<String>,
     ) -> Result<ServerResponse, Box<dyn std::error::Error>> {
         let submission = ProofSubmission {
-            proof: proof.clone(),
-            signature: Self::sign_proof(&proof, &self.api_key),
+            signature: sign_proof(&proof, &self.api_key),
+            proof,
+            player_name,
         };
 
         let response = self
             .client
-            .post(&format!("{}/api/v1/proofs", SERVER_URL))
+            .post(format!("{}{}", self.server_url, PROOFS_PATH))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .json(&submission)
             .send()
@@ -48,18 +64,22 @@ pub async fn submit_proof(
         Ok(server_response)
     }
 
+    /// One page of the global board, or of a level's board
     pub async fn get_leaderboard(
         &self,
-        limit: Option<u32>,
+        query: &LeaderboardQuery,
     ) -> Result<Vec<LeaderboardEntry>, Box<dyn std::error::Error>> {
-        let limit = limit.unwrap_or(100);
+        let mut params = vec![
+            ("offset", query.offset.to_string()),
+            ("limit", query.limit.to_string()),
+        ];
+        if let Some(level_id) = query.level_id {
+            params.push(("level_id", level_id.to_string()));
+        }
+        let url =
+            Url::parse_with_params(&format!("{}{}", self.server_url, LEADERBOARD_PATH), &params)?;
 
-        let response = self
-            .client
-            .get(&format!("{}/api/v1/leaderboard", SERVER_URL))
-            .query(&[("limit", limit)])
-            .send()
-            .await?;
+        let response = self.client.get(url).send().await?;
 
         if !response.status().is_success() {
             return Err(format!("Server returned error: {}", response.status()).into());
@@ -69,12 +89,19 @@ pub async fn get_leaderboard(
         Ok(leaderboard)
     }
 
+    /// A player's totals and global rank
     pub async fn get_player_stats(
         &self,
+        player_id: &str,
     ) -> Result<PlayerStatsResponse, Box<dyn std::error::Error>> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.server_url, PLAYER_STATS_PATH),
+            &[("player_id", player_id)],
+        )?;
+
         let response = self
             .client
-            .get(&format!("{}/api/v1/player/stats", SERVER_URL))
+            .get(url)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .send()
             .await?;
@@ -87,31 +114,196 @@ pub async fn get_player_stats(
         Ok(stats)
     }
 
-    // PROOF-OBLIGATION I6 (ASSUMPTION): submission-signature binding for
-    // leaderboard integrity rests on SHA-256 collision/2nd-preimage
-    // resistance — a stated cryptographic hardness assumption, not a
-    // theorem. Note this is a keyed *hash* (SHA-256 over serde_json(proof)
-    // ‖ api_key), not a MAC; length-extension or key-recovery concerns
-    // are out of seam scope but flagged in the invariant docstring.
-    // See: src/abi/ProofOfWork/ABI/Invariants.idr I6
-    //      (sha256CollisionResistant, signatureBindsPayload)
-    fn sign_proof(proof: &ExportedProof, api_key: &str) -> String {
-        use sha2::{Digest, Sha256};
-
-        let mut hasher = Sha256::new();
-        let proof_json =
-            serde_json::to_string(proof).unwrap_or_else(|_| format!("{:?}", proof.proof_smt2));
-        hasher.update(proof_json);
-        hasher.update(api_key.as_bytes());
-
-        format!("{:x}", hasher.finalize())
+    /// A page of a board, with the player's own standing if the server
+    /// knows them
+    pub async fn get_leaderboard_page(
+        &self,
+        query: LeaderboardQuery,
+        player_id: &str,
+    ) -> Result<LeaderboardPage, Box<dyn std::error::Error>> {
+        let entries = self.get_leaderboard(&query).await?;
+        let player = self.get_player_stats(player_id).await.ok();
+        Ok(LeaderboardPage {
+            query,
+            entries,
+            player,
+        })
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlayerStatsResponse {
-    pub total_proofs: u32,
-    pub total_points: u32,
-    pub global_rank: u32,
-    pub levels_completed: u32,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// A local HTTP server answering each request with the response for
+    /// the first route its path starts with, or 404. Returns the server's
+    /// URL and the request lines it has seen.
+    fn mock_server(routes: Vec<(&'static str, u16, String)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(vec![]));
+        let log = seen.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers; these requests have no body
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request_line.split(' ').nth(1).unwrap_or("").to_string();
+                log.lock().unwrap().push(path.clone());
+                let (status, body) = routes
+                    .iter()
+                    .find(|(prefix, _, _)| path.starts_with(prefix))
+                    .map(|(_, status, body)| (*status, body.clone()))
+                    .unwrap_or((404, String::new()));
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let mut stream = &stream;
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, seen)
+    }
+
+    fn entry(rank: u32, name: &str, player_id: &str, proofs: u32) -> LeaderboardEntry {
+        LeaderboardEntry {
+            player_name: name.to_string(),
+            player_id: Some(player_id.to_string()),
+            steam_id: None,
+            proofs_completed: proofs,
+            total_points: proofs * 10,
+            best_time_secs: None,
+            rank,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_leaderboard_page_from_mock_server() {
+        let entries = vec![
+            entry(21, "Ada", "local-ada", 9),
+            entry(22, "Bo", "local-bo", 8),
+        ];
+        let stats = PlayerStatsResponse {
+            total_proofs: 8,
+            total_points: 80,
+            global_rank: 22,
+            levels_completed: 5,
+        };
+        let (url, seen) = mock_server(vec![
+            (
+                "/api/v1/leaderboard",
+                200,
+                serde_json::to_string(&entries).unwrap(),
+            ),
+            (
+                "/api/v1/player/stats",
+                200,
+                serde_json::to_string(&stats).unwrap(),
+            ),
+        ]);
+
+        let client = NetworkClient::new("key".to_string(), format!("{}/", url));
+        let query = LeaderboardQuery {
+            level_id: Some(3),
+            offset: 20,
+            limit: 20,
+        };
+        let page = client
+            .get_leaderboard_page(query, "local-bo")
+            .await
+            .unwrap();
+        assert_eq!(page.query, query);
+        assert_eq!(page.entries, entries);
+        assert_eq!(page.player, Some(stats));
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            *seen,
+            vec![
+                "/api/v1/leaderboard?offset=20&limit=20&level_id=3".to_string(),
+                "/api/v1/player/stats?player_id=local-bo".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_leaderboard_errors_are_reported() {
+        // A player unknown to the server still gets the board
+        let (url, _) = mock_server(vec![("/api/v1/leaderboard", 200, "[]".to_string())]);
+        let client = NetworkClient::new("key".to_string(), url);
+        let page = client
+            .get_leaderboard_page(LeaderboardQuery::default(), "local-new")
+            .await
+            .unwrap();
+        assert!(page.entries.is_empty());
+        assert_eq!(page.player, None);
+
+        let (url, _) = mock_server(vec![("/api/v1/leaderboard", 500, String::new())]);
+        let client = NetworkClient::new("key".to_string(), url);
+        assert!(client
+            .get_leaderboard(&LeaderboardQuery::default())
+            .await
+            .is_err());
+
+        // Nothing listening
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = NetworkClient::new("key".to_string(), format!("http://127.0.0.1:{}", port));
+        assert!(client
+            .get_leaderboard(&LeaderboardQuery::default())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_with_reference_server() {
+        use crate::protocol::RejectionReason;
+        use proof_of_work::levels::create_builtin_tutorial_pack as server_tutorial;
+        use proof_of_work::server::{store::ScoreStore, ProofServer};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = ProofServer::new(ScoreStore::in_memory(), vec![server_tutorial()]);
+        std::thread::spawn(move || Arc::new(server).serve(listener));
+
+        // The level as it starts, which proves nothing yet
+        let client = NetworkClient::new("key".to_string(), url);
+        let level = &crate::levels::create_builtin_tutorial_pack().levels[0];
+        let mut proof = ExportedProof::from_solution(level, level.initial_state.pieces(), 30);
+        proof.player_id = "local-ada".to_string();
+        let response = client
+            .submit_proof(proof, Some("Ada".to_string()))
+            .await
+            .unwrap();
+        assert!(!response.accepted);
+        assert_eq!(
+            response.rejection().map(|r| r.reason),
+            Some(RejectionReason::IncompleteCircuit)
+        );
+
+        let query = LeaderboardQuery {
+            level_id: Some(level.id),
+            ..LeaderboardQuery::default()
+        };
+        let page = client
+            .get_leaderboard_page(query, "local-ada")
+            .await
+            .unwrap();
+        assert!(page.entries.is_empty());
+        assert_eq!(page.player, None);
+    }
 }
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Fetching leaderboards without holding up the frame.
+//!
+//! Like proof submission, each fetch runs on its own thread with its own
+//! async runtime. The leaderboard screen polls for the result every frame.
+
+use bevy::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use super::{LeaderboardPage, LeaderboardQuery, NetworkClient};
+
+/// Where the online board has got to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum OnlineState {
+    /// Nothing asked for yet
+    #[default]
+    Idle,
+    Loading,
+    Loaded(LeaderboardPage),
+    /// The server couldn't be reached or gave a bad answer
+    Failed(String),
+}
+
+/// The online board being shown and any fetch in flight
+#[derive(Debug, Default, Resource)]
+pub struct OnlineLeaderboard {
+    pub state: OnlineState,
+    /// What was last asked for, loaded or not
+    pub requested: Option<LeaderboardQuery>,
+    fetch: Option<LeaderboardFetch>,
+}
+
+impl OnlineLeaderboard {
+    /// Fetch `query` unless it was the last thing asked for
+    pub fn request(&mut self, client: &NetworkClient, query: LeaderboardQuery, player_id: &str) {
+        if self.requested == Some(query) {
+            return;
+        }
+        self.requested = Some(query);
+        self.state = OnlineState::Loading;
+        self.fetch = Some(LeaderboardFetch::start(client.clone(), query, player_id));
+    }
+
+    /// Fetch the last query again
+    pub fn refresh(&mut self) {
+        self.requested = None;
+    }
+
+    /// Pick up the result of the fetch in flight, if it has finished. A
+    /// newer request replaces the fetch, so an older page never shows.
+    pub fn poll(&mut self) {
+        let Some(result) = self.fetch.as_ref().and_then(LeaderboardFetch::poll) else {
+            return;
+        };
+        self.fetch = None;
+        self.state = match result {
+            Ok(page) => OnlineState::Loaded(page),
+            Err(e) => OnlineState::Failed(e),
+        };
+    }
+}
+
+/// A fetch running on a background thread
+#[derive(Debug)]
+struct LeaderboardFetch {
+    result: Arc<Mutex<Option<Result<LeaderboardPage, String>>>>,
+}
+
+impl LeaderboardFetch {
+    fn start(client: NetworkClient, query: LeaderboardQuery, player_id: &str) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let slot = result.clone();
+        let player_id = player_id.to_string();
+        std::thread::spawn(move || {
+            let fetched = match tokio::runtime::Runtime::new() {
+                Ok(rt) => rt.block_on(async {
+                    client
+                        .get_leaderboard_page(query, &player_id)
+                        .await
+                        .map_err(|e| e.to_string())
+                }),
+                Err(e) => Err(format!("Failed to create async runtime: {}", e)),
+            };
+            if let Ok(mut slot) = slot.lock() {
+                *slot = Some(fetched);
+            }
+        });
+        Self { result }
+    }
+
+    fn poll(&self) -> Option<Result<LeaderboardPage, String>> {
+        self.result.lock().ok()?.take()
+    }
+}
@@ -1,29 +1,46 @@
 // SPDX-License-Identifier: MPL-2.0
 pub mod client;
+pub mod leaderboard;
 
+pub use crate::protocol::{LeaderboardEntry, PlayerStatsResponse, ProofSubmission, ServerResponse};
 pub use client::NetworkClient;
 
-use serde::{Deserialize, Serialize};
+use bevy::prelude::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProofSubmission {
-    pub proof: crate::verification::ExportedProof,
-    pub signature: String,
+use crate::settings::Settings;
+
+/// Point the client at the server chosen in the settings
+pub fn apply_server_url(settings: Res<Settings>, mut client: ResMut<NetworkClient>) {
+    if settings.is_changed() && client.server_url() != settings.server_url.trim_end_matches('/') {
+        client.set_server_url(&settings.server_url);
+    }
+}
+
+/// Which page of which board to fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardQuery {
+    /// A level's board, or the global board if `None`
+    pub level_id: Option<u32>,
+    /// Entries to skip
+    pub offset: u32,
+    /// Entries per page
+    pub limit: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ServerResponse {
-    pub accepted: bool,
-    pub points_awarded: u32,
-    pub global_rank: Option<u32>,
-    pub message: Option<String>,
+impl Default for LeaderboardQuery {
+    fn default() -> Self {
+        Self {
+            level_id: None,
+            offset: 0,
+            limit: 20,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LeaderboardEntry {
-    pub player_name: String,
-    pub steam_id: Option<String>,
-    pub proofs_completed: u32,
-    pub total_points: u32,
-    pub rank: u32,
+/// A page of a board as fetched, with the player's own standing
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardPage {
+    pub query: LeaderboardQuery,
+    pub entries: Vec<LeaderboardEntry>,
+    pub player: Option<PlayerStatsResponse>,
 }
@@ -0,0 +1,595 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Local player profiles, so several people can share one machine.
+//!
+//! Each profile is a directory under the profiles root holding its
+//! `profile.json` plus that player's progress, saved solutions and stats.
+//! Switching profile saves the outgoing player's files and points the
+//! level pack manager at the incoming player's directory. Profile files
+//! are written through [`storage`], so they survive crashes mid-save;
+//! export files are plain JSON.
+
+pub mod ui;
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::game::PlayerStats;
+use crate::levels::storage;
+use crate::levels::{
+    LevelPackError, LevelPackManager, PackProgress, SavedSolution, PROGRESS_FILE, SOLUTIONS_FILE,
+};
+
+/// Profile metadata file inside each profile directory
+pub const PROFILE_FILE: &str = "profile.json";
+
+/// Player stats file inside each profile directory
+pub const STATS_FILE: &str = "stats.json";
+
+/// File in the profiles root naming the last active profile
+pub const ACTIVE_FILE: &str = "active";
+
+/// Name of the profile created on first launch
+pub const DEFAULT_PROFILE_NAME: &str = "Player";
+
+/// A named local player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Directory name, derived from the name when the profile is created
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Identifies this player's submitted proofs
+    pub player_id: String,
+    /// This player's preferences
+    #[serde(default)]
+    pub settings: ProfileSettings,
+}
+
+/// Preferences that follow a player rather than the machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    /// Scale applied to menus and the HUD
+    pub ui_scale: f32,
+    /// Colour theme for menus and the HUD
+    pub theme: Theme,
+    /// Whether verified proofs are submitted to the proof server
+    pub submit_proofs: bool,
+}
+
+impl Default for ProfileSettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            theme: Theme::default(),
+            submit_proofs: false,
+        }
+    }
+}
+
+/// Colour theme for the UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// Follow the operating system's preference
+    System,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::System];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::System => "System",
+        }
+    }
+}
+
+impl Profile {
+    /// A new profile with a fresh player id
+    pub fn new(id: &str, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            player_id: generate_player_id(name),
+            settings: ProfileSettings::default(),
+        }
+    }
+}
+
+/// Everything belonging to one profile, bundled into a single file for
+/// moving a player between machines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileExport {
+    pub profile: Profile,
+    #[serde(default)]
+    pub progress: HashMap<String, PackProgress>,
+    #[serde(default)]
+    pub solutions: HashMap<String, HashMap<u32, SavedSolution>>,
+    #[serde(default)]
+    pub stats: PlayerStats,
+}
+
+/// The profiles on this machine and which one is playing
+#[derive(Debug, Default, Resource)]
+pub struct ProfileManager {
+    /// Directory containing one subdirectory per profile
+    pub root: PathBuf,
+    /// Known profiles, sorted by name
+    pub profiles: Vec<Profile>,
+    /// Index of the active profile
+    pub active: Option<usize>,
+}
+
+#[allow(dead_code)]
+impl ProfileManager {
+    /// Create a manager for profiles stored under `root`
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            profiles: vec![],
+            active: None,
+        }
+    }
+
+    /// Read every profile directory under the root
+    pub fn load_all(&mut self) -> Result<(), LevelPackError> {
+        self.profiles.clear();
+        self.active = None;
+        fs::create_dir_all(&self.root).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+
+        let entries =
+            fs::read_dir(&self.root).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+        for entry in entries.flatten() {
+            let path = entry.path().join(PROFILE_FILE);
+            if !path.exists() {
+                continue;
+            }
+            match storage::load::<Profile>(&path) {
+                Ok(Some(profile)) => self.profiles.push(profile),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to load profile {:?}: {}", path, e),
+            }
+        }
+        self.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    /// The active profile
+    pub fn active_profile(&self) -> Option<&Profile> {
+        self.profiles.get(self.active?)
+    }
+
+    /// Directory holding a profile's files
+    pub fn profile_dir(&self, profile: &Profile) -> PathBuf {
+        self.root.join(&profile.id)
+    }
+
+    /// Id of the profile that was active when the game last ran
+    pub fn last_active_id(&self) -> Option<String> {
+        fs::read_to_string(self.root.join(ACTIVE_FILE))
+            .ok()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+    }
+
+    /// Index of the profile with the given id
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.profiles.iter().position(|p| p.id == id)
+    }
+
+    /// Create a profile and return its index. Names are trimmed and must
+    /// be unique, ignoring case.
+    pub fn create(&mut self, name: &str) -> Result<usize, LevelPackError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(LevelPackError::Invalid("Profile name is empty".to_string()));
+        }
+        if self
+            .profiles
+            .iter()
+            .any(|p| p.name.eq_ignore_ascii_case(name))
+        {
+            return Err(LevelPackError::Invalid(format!(
+                "A profile named '{}' already exists",
+                name
+            )));
+        }
+        let profile = Profile::new(&self.unique_id(name), name);
+        self.add(profile)
+    }
+
+    /// Delete a profile and its files. The active profile can't be deleted.
+    pub fn delete(&mut self, index: usize) -> Result<(), LevelPackError> {
+        if self.active == Some(index) {
+            return Err(LevelPackError::Invalid(
+                "Switch to another profile before deleting this one".to_string(),
+            ));
+        }
+        let profile = self
+            .profiles
+            .get(index)
+            .ok_or_else(|| LevelPackError::NotFound(format!("profile #{}", index)))?;
+        let dir = self.profile_dir(profile);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+        }
+        self.profiles.remove(index);
+        if let Some(active) = self.active.filter(|&active| active > index) {
+            self.active = Some(active - 1);
+        }
+        Ok(())
+    }
+
+    /// Change a profile's settings and write them to its profile file
+    pub fn update_settings(
+        &mut self,
+        index: usize,
+        settings: ProfileSettings,
+    ) -> Result<(), LevelPackError> {
+        let profile = self
+            .profiles
+            .get_mut(index)
+            .ok_or_else(|| LevelPackError::NotFound(format!("profile #{}", index)))?;
+        profile.settings = settings;
+        let path = self.root.join(&profile.id).join(PROFILE_FILE);
+        storage::save(&path, profile)
+    }
+
+    /// Save the active profile's progress and stats from the running game
+    pub fn save_active(
+        &self,
+        pack_manager: &LevelPackManager,
+        stats: &PlayerStats,
+    ) -> Result<(), LevelPackError> {
+        let Some(profile) = self.active_profile() else {
+            return Ok(());
+        };
+        let dir = self.profile_dir(profile);
+        pack_manager.save_progress(&dir.join(PROGRESS_FILE))?;
+        pack_manager.save_solutions(&dir.join(SOLUTIONS_FILE))?;
+        storage::save(&dir.join(STATS_FILE), stats)
+    }
+
+    /// Switch to another profile: save the current player's files, then
+    /// load the new player's progress and stats into the running game.
+    pub fn activate(
+        &mut self,
+        index: usize,
+        pack_manager: &mut LevelPackManager,
+        stats: &mut PlayerStats,
+    ) -> Result<(), LevelPackError> {
+        let profile = self
+            .profiles
+            .get(index)
+            .ok_or_else(|| LevelPackError::NotFound(format!("profile #{}", index)))?;
+        let dir = self.profile_dir(profile);
+        if self.active.is_some() && self.active != Some(index) {
+            self.save_active(pack_manager, stats)?;
+        }
+
+        pack_manager.save_dir = dir.clone();
+        pack_manager.progress.clear();
+        pack_manager.solutions.clear();
+        pack_manager.load_progress(&dir.join(PROGRESS_FILE))?;
+        pack_manager.load_solutions(&dir.join(SOLUTIONS_FILE))?;
+        *stats = load_or_default(&dir.join(STATS_FILE))?;
+
+        fs::write(self.root.join(ACTIVE_FILE), &profile.id)
+            .map_err(|e| LevelPackError::IoError(e.to_string()))?;
+        self.active = Some(index);
+        Ok(())
+    }
+
+    /// Bundle a profile's files into one export file
+    pub fn export(&self, index: usize, path: &Path) -> Result<(), LevelPackError> {
+        let profile = self
+            .profiles
+            .get(index)
+            .ok_or_else(|| LevelPackError::NotFound(format!("profile #{}", index)))?;
+        let dir = self.profile_dir(profile);
+        let export = ProfileExport {
+            profile: profile.clone(),
+            progress: load_or_default(&dir.join(PROGRESS_FILE))?,
+            solutions: load_or_default(&dir.join(SOLUTIONS_FILE))?,
+            stats: load_or_default(&dir.join(STATS_FILE))?,
+        };
+        write_json(path, &export)
+    }
+
+    /// Add a profile from an export file and return its index. The
+    /// profile keeps its player id, but gets a new directory and a
+    /// numbered name if it clashes with an existing profile.
+    pub fn import(&mut self, path: &Path) -> Result<usize, LevelPackError> {
+        let export: ProfileExport = read_json(path)?;
+        let mut profile = export.profile;
+        let base_name = profile.name.trim().to_string();
+        let mut name = base_name.clone();
+        let mut n = 2;
+        while self
+            .profiles
+            .iter()
+            .any(|p| p.name.eq_ignore_ascii_case(&name))
+        {
+            name = format!("{} ({})", base_name, n);
+            n += 1;
+        }
+        profile.id = self.unique_id(&name);
+        profile.name = name;
+
+        let dir = self.root.join(&profile.id);
+        storage::save(&dir.join(PROGRESS_FILE), &export.progress)?;
+        storage::save(&dir.join(SOLUTIONS_FILE), &export.solutions)?;
+        storage::save(&dir.join(STATS_FILE), &export.stats)?;
+        self.add(profile)
+    }
+
+    /// Create the default profile on first launch, taking over progress
+    /// saved before profiles existed from `legacy_dir`.
+    pub fn ensure_default(&mut self, legacy_dir: &Path) -> Result<(), LevelPackError> {
+        if !self.profiles.is_empty() {
+            return Ok(());
+        }
+        let index = self.create(DEFAULT_PROFILE_NAME)?;
+        let dir = self.profile_dir(&self.profiles[index]);
+        for file in [PROGRESS_FILE, SOLUTIONS_FILE] {
+            let legacy = legacy_dir.join(file);
+            if legacy.exists() {
+                fs::rename(&legacy, dir.join(file))
+                    .map_err(|e| LevelPackError::IoError(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a new profile's metadata and insert it in name order
+    fn add(&mut self, profile: Profile) -> Result<usize, LevelPackError> {
+        storage::save(&self.profile_dir(&profile).join(PROFILE_FILE), &profile)?;
+        let active_id = self.active_profile().map(|p| p.id.clone());
+        let id = profile.id.clone();
+        self.profiles.push(profile);
+        self.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        self.active = active_id.and_then(|id| self.index_of(&id));
+        Ok(self.index_of(&id).unwrap_or(0))
+    }
+
+    /// A directory name for `name` that no profile uses yet
+    fn unique_id(&self, name: &str) -> String {
+        let slug: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let slug = slug.trim_matches('-');
+        let slug = if slug.is_empty() { "profile" } else { slug };
+
+        let mut id = slug.to_string();
+        let mut n = 2;
+        while self.index_of(&id).is_some() || self.root.join(&id).exists() {
+            id = format!("{}-{}", slug, n);
+            n += 1;
+        }
+        id
+    }
+}
+
+/// A player id unlikely to collide with another machine's: a hash of the
+/// name and the creation time.
+fn generate_player_id(name: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut hasher = Sha256::new();
+    hasher.update(name.as_bytes());
+    hasher.update(nanos.to_le_bytes());
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("local-{}", &digest[..16])
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, LevelPackError> {
+    let content = fs::read_to_string(path).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| LevelPackError::DeserializationError(e.to_string()))
+}
+
+/// Load a profile save file, recovering from backups as needed
+fn load_or_default<T: DeserializeOwned + Default>(path: &Path) -> Result<T, LevelPackError> {
+    Ok(storage::load(path)?.unwrap_or_default())
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), LevelPackError> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| LevelPackError::SerializationError(e.to_string()))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| LevelPackError::IoError(e.to_string()))?;
+    }
+    fs::write(path, json).map_err(|e| LevelPackError::IoError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::LevelCompletion;
+
+    fn temp_root(tag: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("pow-profiles-{}-{}", tag, nanos))
+    }
+
+    fn complete_level(pack_manager: &mut LevelPackManager, level_id: u32) {
+        pack_manager
+            .progress
+            .entry("tutorial".to_string())
+            .or_default()
+            .completed
+            .insert(
+                level_id,
+                LevelCompletion {
+                    best_time_secs: 10,
+                    times_completed: 1,
+                },
+            );
+    }
+
+    #[test]
+    fn test_profiles_keep_separate_progress_and_stats() {
+        let root = temp_root("switch");
+        let mut profiles = ProfileManager::new(root.clone());
+        profiles.load_all().unwrap();
+        let alice = profiles.create("Alice").unwrap();
+        let bob = profiles.create("Bob").unwrap();
+        assert!(profiles.create("alice").is_err());
+
+        let mut pack_manager = LevelPackManager::new(root.join("packs"));
+        let mut stats = PlayerStats::default();
+        profiles
+            .activate(alice, &mut pack_manager, &mut stats)
+            .unwrap();
+        complete_level(&mut pack_manager, 1);
+        stats.proofs_completed = 3;
+
+        profiles
+            .activate(bob, &mut pack_manager, &mut stats)
+            .unwrap();
+        assert!(!pack_manager.is_level_completed("tutorial", 1));
+        assert_eq!(stats.proofs_completed, 0);
+
+        profiles
+            .activate(alice, &mut pack_manager, &mut stats)
+            .unwrap();
+        assert!(pack_manager.is_level_completed("tutorial", 1));
+        assert_eq!(stats.proofs_completed, 3);
+
+        // The last active profile is remembered across launches
+        let mut next_launch = ProfileManager::new(root.clone());
+        next_launch.load_all().unwrap();
+        assert_eq!(next_launch.profiles.len(), 2);
+        assert_eq!(next_launch.last_active_id().as_deref(), Some("alice"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_settings_are_saved_with_the_profile() {
+        let root = temp_root("settings");
+        let mut profiles = ProfileManager::new(root.clone());
+        profiles.load_all().unwrap();
+        let dave = profiles.create("Dave").unwrap();
+        let erin = profiles.create("Erin").unwrap();
+        assert_eq!(profiles.profiles[dave].settings, ProfileSettings::default());
+
+        let settings = ProfileSettings {
+            ui_scale: 1.5,
+            theme: Theme::Light,
+            submit_proofs: true,
+        };
+        profiles.update_settings(dave, settings.clone()).unwrap();
+
+        let mut next_launch = ProfileManager::new(root.clone());
+        next_launch.load_all().unwrap();
+        assert_eq!(next_launch.profiles[dave].settings, settings);
+        assert_eq!(
+            next_launch.profiles[erin].settings,
+            ProfileSettings::default()
+        );
+
+        // Profiles saved before settings existed still load
+        let old = root.join("old");
+        fs::create_dir_all(&old).unwrap();
+        fs::write(
+            old.join(PROFILE_FILE),
+            r#"{"id":"old","name":"Old","player_id":"local-0"}"#,
+        )
+        .unwrap();
+        next_launch.load_all().unwrap();
+        let index = next_launch.index_of("old").unwrap();
+        assert_eq!(
+            next_launch.profiles[index].settings,
+            ProfileSettings::default()
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_export_and_import_profile() {
+        let root = temp_root("export");
+        let mut profiles = ProfileManager::new(root.clone());
+        profiles.load_all().unwrap();
+        let index = profiles.create("Carol").unwrap();
+
+        let mut pack_manager = LevelPackManager::new(root.join("packs"));
+        let mut stats = PlayerStats::default();
+        profiles
+            .activate(index, &mut pack_manager, &mut stats)
+            .unwrap();
+        complete_level(&mut pack_manager, 2);
+        stats.levels_completed = 1;
+        profiles.save_active(&pack_manager, &stats).unwrap();
+
+        let file = root.join("carol-export.json");
+        profiles.export(index, &file).unwrap();
+        let imported = profiles.import(&file).unwrap();
+
+        let original = profiles.active_profile().unwrap().clone();
+        let copy = profiles.profiles[imported].clone();
+        assert_eq!(copy.name, "Carol (2)");
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.player_id, original.player_id);
+
+        profiles
+            .activate(imported, &mut pack_manager, &mut stats)
+            .unwrap();
+        assert!(pack_manager.is_level_completed("tutorial", 2));
+        assert_eq!(stats.levels_completed, 1);
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_first_launch_adopts_legacy_progress() {
+        let root = temp_root("legacy");
+        let legacy = root.join("packs");
+        fs::create_dir_all(&legacy).unwrap();
+        let mut pack_manager = LevelPackManager::new(legacy.clone());
+        complete_level(&mut pack_manager, 1);
+        pack_manager
+            .save_progress(&legacy.join(PROGRESS_FILE))
+            .unwrap();
+
+        let mut profiles = ProfileManager::new(root.join("profiles"));
+        profiles.load_all().unwrap();
+        profiles.ensure_default(&legacy).unwrap();
+        assert_eq!(profiles.profiles.len(), 1);
+        assert!(!legacy.join(PROGRESS_FILE).exists());
+
+        let mut pack_manager = LevelPackManager::new(legacy);
+        let mut stats = PlayerStats::default();
+        profiles.activate(0, &mut pack_manager, &mut stats).unwrap();
+        assert!(pack_manager.is_level_completed("tutorial", 1));
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Profile picker shown on the main menu.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::path::PathBuf;
+
+use super::ProfileManager;
+use crate::game::PlayerStats;
+use crate::levels::LevelPackManager;
+
+/// Text fields and feedback for the profile picker
+#[derive(Debug, Default, Resource)]
+pub struct ProfilePickerState {
+    /// Name typed for a new profile
+    pub new_name: String,
+    /// File to export to or import from
+    pub transfer_path: String,
+    /// Result of the last action
+    pub status: String,
+}
+
+/// Load profiles and activate the one used last, creating a default
+/// profile on first launch
+pub fn init_profiles(
+    mut profiles: ResMut<ProfileManager>,
+    mut pack_manager: ResMut<LevelPackManager>,
+    mut stats: ResMut<PlayerStats>,
+) {
+    if let Err(e) = profiles.load_all() {
+        warn!("Failed to load profiles: {}", e);
+    }
+    let legacy_dir = pack_manager.packs_dir.clone();
+    if let Err(e) = profiles.ensure_default(&legacy_dir) {
+        warn!("Failed to create default profile: {}", e);
+    }
+
+    let index = profiles
+        .last_active_id()
+        .and_then(|id| profiles.index_of(&id))
+        .unwrap_or(0);
+    if let Err(e) = profiles.activate(index, &mut pack_manager, &mut stats) {
+        warn!("Failed to activate profile: {}", e);
+        return;
+    }
+    if let Some(profile) = profiles.active_profile() {
+        info!("Playing as {} ({})", profile.name, profile.player_id);
+    }
+}
+
+/// Render the profile picker window
+pub fn profile_picker_system(
+    mut contexts: EguiContexts,
+    mut profiles: ResMut<ProfileManager>,
+    mut pack_manager: ResMut<LevelPackManager>,
+    mut stats: ResMut<PlayerStats>,
+    mut picker: ResMut<ProfilePickerState>,
+) {
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    // Track actions to perform after UI rendering
+    let mut switch_to: Option<usize> = None;
+    let mut delete: Option<usize> = None;
+    let mut create = false;
+    let mut export = false;
+    let mut import = false;
+
+    egui::Window::new("Profile")
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 16.0))
+        .resizable(false)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            for (index, profile) in profiles.profiles.iter().enumerate() {
+                let is_active = profiles.active == Some(index);
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(is_active, &profile.name).clicked() && !is_active {
+                        switch_to = Some(index);
+                    }
+                    if !is_active && ui.small_button("Delete").clicked() {
+                        delete = Some(index);
+                    }
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut picker.new_name);
+                create = ui.button("New profile").clicked();
+            });
+
+            ui.separator();
+            ui.label("Profile file:");
+            ui.text_edit_singleline(&mut picker.transfer_path);
+            ui.horizontal(|ui| {
+                export = ui.button("Export").clicked();
+                import = ui.button("Import").clicked();
+            });
+
+            if !picker.status.is_empty() {
+                ui.separator();
+                ui.label(&picker.status);
+            }
+        });
+
+    // Apply actions after UI rendering
+    if let Some(index) = switch_to {
+        picker.status = match profiles.activate(index, &mut pack_manager, &mut stats) {
+            Ok(()) => format!("Playing as {}", profiles.profiles[index].name),
+            Err(e) => format!("Could not switch profile: {}", e),
+        };
+    }
+    if let Some(index) = delete {
+        let name = profiles.profiles[index].name.clone();
+        picker.status = match profiles.delete(index) {
+            Ok(()) => format!("Deleted {}", name),
+            Err(e) => format!("Could not delete {}: {}", name, e),
+        };
+    }
+    if create {
+        picker.status = match profiles.create(&picker.new_name) {
+            Ok(index) => {
+                picker.new_name.clear();
+                format!("Created {}", profiles.profiles[index].name)
+            }
+            Err(e) => format!("Could not create profile: {}", e),
+        };
+    }
+    if export || import {
+        let path = PathBuf::from(picker.transfer_path.trim());
+        picker.status = if picker.transfer_path.trim().is_empty() {
+            "Enter a file path first".to_string()
+        } else if export {
+            let Some(index) = profiles.active else {
+                return;
+            };
+            let saved = profiles.save_active(&pack_manager, &stats);
+            match saved.and_then(|()| profiles.export(index, &path)) {
+                Ok(()) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Export failed: {}", e),
+            }
+        } else {
+            match profiles.import(&path) {
+                Ok(index) => format!("Imported {}", profiles.profiles[index].name),
+                Err(e) => format!("Import failed: {}", e),
+            }
+        };
+    }
+}
+
+/// Save the active profile's progress and stats
+pub fn save_active_profile(
+    profiles: Res<ProfileManager>,
+    pack_manager: Res<LevelPackManager>,
+    stats: Res<PlayerStats>,
+) {
+    if let Err(e) = profiles.save_active(&pack_manager, &stats) {
+        warn!("Failed to save profile: {}", e);
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MPL-2.0
+//! What the game and the proof server send each other.
+//!
+//! The game's client (the `network` feature) and the reference server in
+//! [`crate::server`] share these types, so a change to the wire format
+//! changes both ends.
+
+use serde::{Deserialize, Serialize};
+
+use crate::verification::ExportedProof;
+
+/// Submit a proof (POST, JSON [`ProofSubmission`])
+pub const PROOFS_PATH: &str = "/api/v1/proofs";
+
+/// A page of a board (GET, `offset`, `limit` and optional `level_id`)
+pub const LEADERBOARD_PATH: &str = "/api/v1/leaderboard";
+
+/// A player's totals (GET, `player_id`)
+pub const PLAYER_STATS_PATH: &str = "/api/v1/player/stats";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofSubmission {
+    pub proof: ExportedProof,
+    pub signature: String,
+    /// Name to show on the boards, if not the player id
+    #[serde(default)]
+    pub player_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerResponse {
+    pub accepted: bool,
+    pub points_awarded: u32,
+    pub global_rank: Option<u32>,
+    pub message: Option<String>,
+}
+
+impl ServerResponse {
+    /// A refusal, with the reason as JSON in `message`
+    pub fn rejected(rejection: &Rejection) -> Self {
+        Self {
+            accepted: false,
+            points_awarded: 0,
+            global_rank: None,
+            message: serde_json::to_string(rejection).ok(),
+        }
+    }
+
+    /// Why the proof was refused, if it was and the server said why
+    pub fn rejection(&self) -> Option<Rejection> {
+        if self.accepted {
+            return None;
+        }
+        serde_json::from_str(self.message.as_deref()?).ok()
+    }
+}
+
+/// Why the server refused a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// The signature wasn't made with the API key the proof came with
+    BadSignature,
+    /// The API key belongs to another player, or the player to another key
+    WrongPlayer,
+    /// The server has no level with the proof's level id
+    UnknownLevel,
+    /// The proof doesn't include the pieces of the solved board
+    MissingSolution,
+    /// The solution moves locked pieces, builds on obstacles or uses pieces
+    /// the level doesn't allow
+    BrokenRules,
+    /// Some goal isn't fed by a complete circuit
+    IncompleteCircuit,
+    /// The SMT-LIB2 isn't what the solution's circuit proves
+    ProofMismatch,
+    /// The verifier found the circuit doesn't prove the goal
+    NotProved,
+    /// The server has no verifier, so it can't accept anything
+    CannotVerify,
+}
+
+/// A refusal as sent in [`ServerResponse::message`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rejection {
+    pub reason: RejectionReason,
+    /// For people: what exactly was wrong
+    pub detail: String,
+}
+
+impl Rejection {
+    pub fn new(reason: RejectionReason, detail: impl Into<String>) -> Self {
+        Self {
+            reason,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.detail)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub player_name: String,
+    /// Profile player id, for finding the player on the board
+    #[serde(default)]
+    pub player_id: Option<String>,
+    pub steam_id: Option<String>,
+    pub proofs_completed: u32,
+    pub total_points: u32,
+    /// Fastest verified solve, on a level's board
+    #[serde(default)]
+    pub best_time_secs: Option<u64>,
+    pub rank: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStatsResponse {
+    pub total_proofs: u32,
+    pub total_points: u32,
+    pub global_rank: u32,
+    pub levels_completed: u32,
+}
+
+// PROOF-OBLIGATION I6 (ASSUMPTION): submission-signature binding for
+// leaderboard integrity rests on SHA-256 collision/2nd-preimage
+// resistance — a stated cryptographic hardness assumption, not a
+// theorem. Note this is a keyed *hash* (SHA-256 over serde_json(proof)
+// ‖ api_key), not a MAC; length-extension or key-recovery concerns
+// are out of seam scope but flagged in the invariant docstring.
+// See: src/abi/ProofOfWork/ABI/Invariants.idr I6
+//      (sha256CollisionResistant, signatureBindsPayload)
+/// Signature binding a proof to the API key it is submitted with, as
+/// lowercase hex
+pub fn sign_proof(proof: &ExportedProof, api_key: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    let proof_json =
+        serde_json::to_string(proof).unwrap_or_else(|_| format!("{:?}", proof.proof_smt2));
+    hasher.update(proof_json);
+    hasher.update(api_key.as_bytes());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::create_builtin_tutorial_pack;
+
+    #[test]
+    fn test_signature_is_hex_sha256_bound_to_key() {
+        let proof = ExportedProof::from_level(&create_builtin_tutorial_pack().levels[0], 42);
+        let signature = sign_proof(&proof, "key");
+        assert_eq!(signature.len(), 64);
+        assert!(signature
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+        assert_eq!(signature, sign_proof(&proof, "key"));
+        assert_ne!(signature, sign_proof(&proof, "other key"));
+
+        let mut changed = proof.clone();
+        changed.time_taken_secs = 1;
+        assert_ne!(signature, sign_proof(&changed, "key"));
+    }
+
+    #[test]
+    fn test_rejection_round_trips_through_message() {
+        let rejection = Rejection::new(RejectionReason::ProofMismatch, "goal (8, 4) differs");
+        let response = ServerResponse::rejected(&rejection);
+        assert_eq!(
+            response.message.as_deref(),
+            Some(r#"{"reason":"proof_mismatch","detail":"goal (8, 4) differs"}"#)
+        );
+        let response: ServerResponse =
+            serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+        assert_eq!(response.rejection(), Some(rejection));
+
+        // Older servers send plain text
+        let plain = ServerResponse {
+            message: Some("no".to_string()),
+            ..response
+        };
+        assert_eq!(plain.rejection(), None);
+    }
+}
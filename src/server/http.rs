@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Just enough HTTP/1.1 for the proof server: one request per
+//! connection, bodies sized by `Content-Length`, JSON responses.
+
+use serde::Serialize;
+use std::io::{self, BufRead, Read, Write};
+
+/// Largest request body read, in bytes
+pub const MAX_BODY: usize = 1 << 20;
+
+/// Longest request line or header line read, in bytes
+const MAX_LINE: usize = 8 * 1024;
+
+/// Most header lines read in one request
+const MAX_HEADERS: usize = 100;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    /// Decoded query parameters, in order
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Read one request. Malformed requests are `InvalidData` errors.
+    pub fn read(reader: &mut impl BufRead) -> io::Result<Self> {
+        let request_line = read_line(reader)?;
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target), Some(_version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid("malformed request line"));
+        };
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, parse_query(query)),
+            None => (target, vec![]),
+        };
+
+        let mut headers = vec![];
+        loop {
+            let line = read_line(reader)?;
+            if line.is_empty() {
+                break;
+            }
+            if headers.len() == MAX_HEADERS {
+                return Err(invalid("too many headers"));
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid("malformed header"))?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+
+        let mut request = Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            query,
+            headers,
+            body: vec![],
+        };
+        let length = match request.header("Content-Length") {
+            Some(length) => length
+                .parse::<usize>()
+                .map_err(|_| invalid("bad Content-Length"))?,
+            None => 0,
+        };
+        if length > MAX_BODY {
+            return Err(invalid("request body too large"));
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body)?;
+        Ok(request)
+    }
+
+    /// A header's value, matching its name in any case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The token from an `Authorization: Bearer` header
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.header("Authorization")?
+            .strip_prefix("Bearer ")
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// JSON body
+    pub body: String,
+}
+
+impl Response {
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self { status, body },
+            Err(e) => Self::error(500, &format!("failed to encode response: {}", e)),
+        }
+    }
+
+    /// An error response, with the message as `{"error": ...}`
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason(self.status),
+            self.body.len(),
+            self.body
+        )?;
+        writer.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Read a line without its line ending
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    let read = reader.by_ref().take(MAX_LINE as u64).read_line(&mut line)?;
+    if read == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed mid-request",
+        ));
+    }
+    if !line.ends_with('\n') {
+        return Err(invalid("line too long"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` for space, leaving bad escapes as they are
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = text.get(i + 1..i + 3);
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /api/v1/leaderboard?level_id=3&name=Ada%20L+B HTTP/1.1\r\n\
+                   Host: localhost\r\n\
+                   authorization: Bearer  key-1 \r\n\
+                   Content-Length: 4\r\n\
+                   \r\n\
+                   {}\r\nextra";
+        let request = Request::read(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/v1/leaderboard");
+        assert_eq!(request.query_param("level_id"), Some("3"));
+        assert_eq!(request.query_param("name"), Some("Ada L B"));
+        assert_eq!(request.bearer_token(), Some("key-1"));
+        assert_eq!(request.body, b"{}\r\n");
+
+        assert!(Request::read(&mut "nonsense\r\n\r\n".as_bytes()).is_err());
+        let too_big = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        assert!(Request::read(&mut too_big.as_bytes()).is_err());
+
+        let crowded = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-Filler: 1\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let error = Request::read(&mut crowded.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let enough = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-Filler: 1\r\n".repeat(MAX_HEADERS)
+        );
+        assert!(Request::read(&mut enough.as_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Which player each API key belongs to.
+//!
+//! A key is bound to the player it first submits for, and a player to the
+//! first key they submit with, so a signature made with one player's key
+//! can't score for another. Keys are kept only as SHA-256 digests.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::levels::{storage, LevelPackError};
+
+/// Keys file name in the server's data directory
+pub const KEYS_FILE: &str = "keys.json";
+
+/// Why a key can't submit for a player
+#[derive(Debug)]
+pub enum KeyError {
+    /// The key is bound to another player
+    OtherPlayer,
+    /// The player is bound to another key
+    OtherKey,
+    /// Binding the key failed to save
+    Save(LevelPackError),
+}
+
+impl std::fmt::Display for KeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OtherPlayer => write!(f, "the API key belongs to another player"),
+            Self::OtherKey => write!(f, "the player submits with another API key"),
+            Self::Save(e) => write!(f, "failed to save the API key: {}", e),
+        }
+    }
+}
+
+/// Player id for each key digest, saved after each new one
+#[derive(Debug, Default)]
+pub struct KeyStore {
+    /// Where the keys are saved, or `None` to keep them in memory
+    path: Option<PathBuf>,
+    players: HashMap<String, String>,
+}
+
+impl KeyStore {
+    /// Open the keys in `data_dir`, starting empty if there are none
+    pub fn open(data_dir: &Path) -> Result<Self, LevelPackError> {
+        let path = data_dir.join(KEYS_FILE);
+        let players = storage::load(&path)?.unwrap_or_default();
+        Ok(Self {
+            path: Some(path),
+            players,
+        })
+    }
+
+    /// Keys that are never saved
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Number of keys bound to a player
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Whether no key is bound yet
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Check that `api_key` may submit for `player_id`, binding the two if
+    /// neither has been seen before
+    pub fn claim(&mut self, api_key: &str, player_id: &str) -> Result<(), KeyError> {
+        let digest = digest(api_key);
+        match self.players.get(&digest) {
+            Some(owner) if owner == player_id => return Ok(()),
+            Some(_) => return Err(KeyError::OtherPlayer),
+            None if self.players.values().any(|owner| owner == player_id) => {
+                return Err(KeyError::OtherKey)
+            }
+            None => {}
+        }
+
+        self.players.insert(digest.clone(), player_id.to_string());
+        if let Some(path) = &self.path {
+            if let Err(e) = storage::save(path, &self.players) {
+                self.players.remove(&digest);
+                return Err(KeyError::Save(e));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 of `api_key`, as lowercase hex
+fn digest(api_key: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(api_key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_bind_to_one_player() {
+        let mut keys = KeyStore::in_memory();
+        assert!(keys.claim("ada-key", "local-ada").is_ok());
+        assert!(keys.claim("ada-key", "local-ada").is_ok());
+        assert!(matches!(
+            keys.claim("ada-key", "local-bob"),
+            Err(KeyError::OtherPlayer)
+        ));
+        assert!(matches!(
+            keys.claim("bob-key", "local-ada"),
+            Err(KeyError::OtherKey)
+        ));
+        assert!(keys.claim("bob-key", "local-bob").is_ok());
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn test_keys_persist_as_digests() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("pow-keys-{}", nanos));
+
+        let mut keys = KeyStore::open(&dir).unwrap();
+        keys.claim("ada-key", "local-ada").unwrap();
+        let saved = std::fs::read_to_string(dir.join(KEYS_FILE)).unwrap();
+        assert!(!saved.contains("ada-key"));
+
+        let mut reopened = KeyStore::open(&dir).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert!(matches!(
+            reopened.claim("ada-key", "local-bob"),
+            Err(KeyError::OtherPlayer)
+        ));
+    }
+}
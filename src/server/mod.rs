@@ -0,0 +1,563 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Reference proof server.
+//!
+//! A small HTTP server implementing the API in [`crate::protocol`], for
+//! running a private leaderboard, say for a classroom, and for testing the
+//! game's client without the public service. Accepted proofs are kept in
+//! a JSON file in the server's data directory. The `proof-server` binary
+//! runs it.
+//!
+//! A submission is accepted if its signature matches the API key it was
+//! sent with, that key belongs to the player the proof is for (see
+//! [`keys`]), and [`verify`] finds that its solution proves the level.
+//! Refusals say why in [`ServerResponse::message`] (see
+//! [`crate::protocol::Rejection`]).
+
+pub mod http;
+pub mod keys;
+pub mod store;
+pub mod verify;
+
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::levels::LevelPack;
+use crate::protocol::{
+    sign_proof, ProofSubmission, Rejection, RejectionReason, ServerResponse, LEADERBOARD_PATH,
+    PLAYER_STATS_PATH, PROOFS_PATH,
+};
+use crate::verification::VerifierOptions;
+use http::{Request, Response};
+use keys::{KeyError, KeyStore};
+use store::ScoreStore;
+
+/// Overrides the address the server listens on
+pub const ADDR_VAR: &str = "PROOF_OF_WORK_SERVER_ADDR";
+
+/// Overrides the server's data directory
+pub const DATA_DIR_VAR: &str = "PROOF_OF_WORK_SERVER_DATA_DIR";
+
+/// Overrides the directory of packs proofs are checked against
+pub const PACKS_VAR: &str = "PROOF_OF_WORK_SERVER_PACKS";
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+pub const DEFAULT_DATA_DIR: &str = "./server-data";
+
+/// Most entries returned on one page of a board
+pub const MAX_PAGE: usize = 100;
+
+/// Entries on a page of a board when the request doesn't say
+const DEFAULT_PAGE: usize = 20;
+
+pub const USAGE: &str = "\
+Usage: proof-server [OPTIONS]
+
+Options:
+  --addr <ADDR>      Address to listen on [default: 127.0.0.1:8080]
+                     [env: PROOF_OF_WORK_SERVER_ADDR]
+  --data-dir <DIR>   Where accepted proofs are saved [default: ./server-data]
+                     [env: PROOF_OF_WORK_SERVER_DATA_DIR]
+  --packs <DIR>      Level packs to check proofs against, as well as the
+                     tutorial and <data-dir>/packs
+                     [env: PROOF_OF_WORK_SERVER_PACKS]
+  -h, --help         Print this help";
+
+/// How the server was asked to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerArgs {
+    pub addr: String,
+    pub data_dir: PathBuf,
+    pub packs_dir: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl ServerArgs {
+    /// Parse the arguments after the program name, falling back to the
+    /// environment and then the defaults
+    pub fn parse(
+        args: impl IntoIterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let mut addr = None;
+        let mut data_dir = None;
+        let mut packs_dir = None;
+        let mut help = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let slot = match flag.as_str() {
+                "-h" | "--help" => {
+                    help = true;
+                    continue;
+                }
+                "--addr" => &mut addr,
+                "--data-dir" => &mut data_dir,
+                "--packs" => &mut packs_dir,
+                _ => return Err(format!("unknown argument '{}'", flag)),
+            };
+            let value = inline
+                .or_else(|| args.next())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("{} needs a value", flag))?;
+            *slot = Some(value);
+        }
+        let env = |name: &str| env(name).filter(|value| !value.is_empty());
+        Ok(Self {
+            addr: addr
+                .or_else(|| env(ADDR_VAR))
+                .unwrap_or_else(|| DEFAULT_ADDR.to_string()),
+            data_dir: data_dir
+                .or_else(|| env(DATA_DIR_VAR))
+                .unwrap_or_else(|| DEFAULT_DATA_DIR.to_string())
+                .into(),
+            packs_dir: packs_dir.or_else(|| env(PACKS_VAR)).map(PathBuf::from),
+            help,
+        })
+    }
+}
+
+/// Answers API requests from the scores it keeps
+pub struct ProofServer {
+    scores: Mutex<ScoreStore>,
+    /// The player each API key submits for
+    keys: Mutex<KeyStore>,
+    /// Levels that proofs are checked against
+    packs: Vec<LevelPack>,
+    verifier: VerifierOptions,
+}
+
+impl ProofServer {
+    pub fn new(scores: ScoreStore, packs: Vec<LevelPack>) -> Self {
+        Self {
+            scores: Mutex::new(scores),
+            keys: Mutex::new(KeyStore::in_memory()),
+            packs,
+            verifier: VerifierOptions::default(),
+        }
+    }
+
+    /// Bind API keys to players in `keys` instead of in memory
+    pub fn with_keys(mut self, keys: KeyStore) -> Self {
+        self.keys = Mutex::new(keys);
+        self
+    }
+
+    /// Check proofs with these options instead of the defaults
+    pub fn with_verifier(mut self, verifier: VerifierOptions) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Answer one request
+    pub fn handle(&self, request: &Request) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", PROOFS_PATH) => self.submit(request),
+            ("GET", LEADERBOARD_PATH) => self.leaderboard(request),
+            ("GET", PLAYER_STATS_PATH) => self.player_stats(request),
+            (_, PROOFS_PATH | LEADERBOARD_PATH | PLAYER_STATS_PATH) => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    /// Accept connections for as long as the server runs, answering each
+    /// on its own thread
+    pub fn serve(self: Arc<Self>, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            // A failed accept (e.g. out of file descriptors) only loses
+            // that connection; pause so a persistent failure doesn't spin
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept a connection: {}", e);
+                    std::thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+            };
+            let server = self.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = server.answer(&stream) {
+                    tracing::debug!("Dropped connection: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn answer(&self, stream: &TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let response = match Request::read(&mut BufReader::new(stream)) {
+            Ok(request) => {
+                let response = self.handle(&request);
+                tracing::info!("{} {} -> {}", request.method, request.path, response.status);
+                response
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                Response::error(400, &e.to_string())
+            }
+            Err(e) => return Err(e),
+        };
+        response.write_to(&mut &*stream)
+    }
+
+    fn submit(&self, request: &Request) -> Response {
+        let Some(api_key) = request.bearer_token() else {
+            return Response::error(401, "missing API key");
+        };
+        let submission: ProofSubmission = match serde_json::from_slice(&request.body) {
+            Ok(submission) => submission,
+            Err(e) => return Response::error(400, &format!("malformed submission: {}", e)),
+        };
+        // Rejections are answers, not errors, so the client reads them
+        if submission.signature != sign_proof(&submission.proof, api_key) {
+            let rejection = Rejection::new(
+                RejectionReason::BadSignature,
+                "the signature does not match the proof",
+            );
+            return Response::json(200, &ServerResponse::rejected(&rejection));
+        }
+        let claimed = self
+            .keys
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .claim(api_key, &submission.proof.player_id);
+        match claimed {
+            Ok(()) => {}
+            Err(KeyError::Save(e)) => {
+                tracing::error!("Failed to save API keys: {}", e);
+                return Response::error(500, "failed to save the API key");
+            }
+            Err(e) => {
+                let rejection = Rejection::new(RejectionReason::WrongPlayer, e.to_string());
+                return Response::json(200, &ServerResponse::rejected(&rejection));
+            }
+        }
+        // Checked before taking the lock, as the verifier can be slow
+        if let Err(rejection) = verify::check_proof(&self.packs, &submission.proof, &self.verifier)
+        {
+            tracing::info!(
+                "Rejected level {} from {}: {}",
+                submission.proof.level_id,
+                submission.proof.player_id,
+                rejection
+            );
+            return Response::json(200, &ServerResponse::rejected(&rejection));
+        }
+
+        let mut scores = self.lock_scores();
+        let submitted_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        match scores.record(&submission, submitted_at) {
+            Ok(points) => Response::json(
+                200,
+                &ServerResponse {
+                    accepted: true,
+                    points_awarded: points,
+                    global_rank: scores
+                        .player_stats(&submission.proof.player_id)
+                        .map(|s| s.global_rank),
+                    message: None,
+                },
+            ),
+            Err(e) => {
+                tracing::error!("Failed to save scores: {}", e);
+                Response::error(500, "failed to save the proof")
+            }
+        }
+    }
+
+    fn leaderboard(&self, request: &Request) -> Response {
+        let param = |name: &str| request.query_param(name).map(str::parse::<usize>);
+        let offset = match param("offset") {
+            None => 0,
+            Some(Ok(offset)) => offset,
+            Some(Err(_)) => return Response::error(400, "offset must be a number"),
+        };
+        let limit = match param("limit") {
+            None => DEFAULT_PAGE,
+            Some(Ok(limit)) => limit.min(MAX_PAGE),
+            Some(Err(_)) => return Response::error(400, "limit must be a number"),
+        };
+        let level_id = match request.query_param("level_id").map(str::parse::<u32>) {
+            None => None,
+            Some(Ok(level_id)) => Some(level_id),
+            Some(Err(_)) => return Response::error(400, "level_id must be a number"),
+        };
+        let entries = self.lock_scores().leaderboard(level_id, offset, limit);
+        Response::json(200, &entries)
+    }
+
+    fn player_stats(&self, request: &Request) -> Response {
+        let Some(player_id) = request.query_param("player_id") else {
+            return Response::error(400, "player_id is required");
+        };
+        match self.lock_scores().player_stats(player_id) {
+            Some(stats) => Response::json(200, &stats),
+            None => Response::error(404, "no proofs from this player"),
+        }
+    }
+
+    fn lock_scores(&self) -> std::sync::MutexGuard<'_, ScoreStore> {
+        // A panic mid-request can't leave the records half-written
+        self.scores
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardState, Direction, GoalCondition, Level, LogicPiece, PlaceablePiece};
+    use crate::protocol::LeaderboardEntry;
+    use crate::verification::ExportedProof;
+    use std::io::{Read, Write};
+
+    /// P and Q to the left of an R goal, with room for an AND gate between
+    pub fn pack() -> LevelPack {
+        let mut pack = LevelPack::new("class", "Class", "Teacher");
+        let mut level = Level {
+            id: 7,
+            name: "And".to_string(),
+            description: String::new(),
+            theorem: "(assert (=> (and P Q) R))".to_string(),
+            initial_state: BoardState::with_pieces(
+                10,
+                10,
+                vec![
+                    LogicPiece::Assumption {
+                        formula: "P".to_string(),
+                        position: (3, 5),
+                    },
+                    LogicPiece::Assumption {
+                        formula: "Q".to_string(),
+                        position: (3, 4),
+                    },
+                    LogicPiece::Goal {
+                        formula: "R".to_string(),
+                        position: (5, 4),
+                    },
+                ],
+            ),
+            goal_state: GoalCondition::ProveFormula {
+                formula: "R".to_string(),
+            },
+            witness: None,
+            inventory: Default::default(),
+        };
+        level.inventory.set_count(PlaceablePiece::AndGate, Some(1));
+        level.inventory.set_count(PlaceablePiece::OrGate, Some(1));
+        pack.levels.push(level);
+        pack
+    }
+
+    /// A proof of the level in [`pack`] using `gate` where the AND gate goes
+    pub fn proof(gate: LogicPiece) -> ExportedProof {
+        let pack = pack();
+        let level = &pack.levels[0];
+        let mut solution = level.initial_state.pieces().to_vec();
+        solution.push(gate);
+        ExportedProof::from_solution(level, &solution, 30)
+    }
+
+    pub fn and_gate() -> LogicPiece {
+        LogicPiece::AndIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        }
+    }
+
+    fn server() -> ProofServer {
+        ProofServer::new(ScoreStore::in_memory(), vec![pack()])
+    }
+
+    fn get(path: &str, query: &[(&str, &str)]) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            query: query
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Request::default()
+        }
+    }
+
+    fn post(proof: ExportedProof, api_key: &str, signing_key: &str) -> Request {
+        let submission = ProofSubmission {
+            signature: sign_proof(&proof, signing_key),
+            proof,
+            player_name: Some("Ada".to_string()),
+        };
+        Request {
+            method: "POST".to_string(),
+            path: PROOFS_PATH.to_string(),
+            headers: vec![("Authorization".to_string(), format!("Bearer {}", api_key))],
+            body: serde_json::to_vec(&submission).unwrap(),
+            ..Request::default()
+        }
+    }
+
+    fn submit(player_id: &str, api_key: &str, signing_key: &str) -> Request {
+        let mut proof = proof(and_gate());
+        proof.player_id = player_id.to_string();
+        post(proof, api_key, signing_key)
+    }
+
+    fn body<T: serde::de::DeserializeOwned>(response: &Response) -> T {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[cfg(feature = "z3-verify")]
+    #[test]
+    fn test_submit_then_read_boards() {
+        let server = server();
+
+        let response = server.handle(&submit("local-ada", "key", "key"));
+        assert_eq!(response.status, 200);
+        let answer: ServerResponse = body(&response);
+        assert!(answer.accepted);
+        assert_eq!(answer.points_awarded, store::POINTS_PER_LEVEL);
+        assert_eq!(answer.global_rank, Some(1));
+
+        let response = server.handle(&get(LEADERBOARD_PATH, &[("level_id", "7")]));
+        let entries: Vec<LeaderboardEntry> = body(&response);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].player_name, "Ada");
+        assert_eq!(entries[0].best_time_secs, Some(30));
+
+        let response = server.handle(&get(PLAYER_STATS_PATH, &[("player_id", "local-ada")]));
+        let stats: crate::protocol::PlayerStatsResponse = body(&response);
+        assert_eq!(stats.total_points, store::POINTS_PER_LEVEL);
+
+        let response = server.handle(&get(LEADERBOARD_PATH, &[("offset", "1")]));
+        assert!(body::<Vec<LeaderboardEntry>>(&response).is_empty());
+    }
+
+    #[test]
+    fn test_rejections_say_why() {
+        let server = server();
+        let reason = |request: &Request| {
+            let answer: ServerResponse = body(&server.handle(request));
+            assert!(!answer.accepted);
+            assert_eq!(answer.points_awarded, 0);
+            answer.rejection().map(|r| r.reason)
+        };
+
+        assert_eq!(
+            reason(&submit("local-ada", "key", "forged")),
+            Some(RejectionReason::BadSignature)
+        );
+
+        // Signed properly, but claiming more than the circuit proves
+        let mut tampered = proof(and_gate());
+        tampered.proof_smt2 = "(assert true)\n(check-sat)\n".to_string();
+        assert_eq!(
+            reason(&post(tampered, "key", "key")),
+            Some(RejectionReason::ProofMismatch)
+        );
+
+        let unsolved = proof(LogicPiece::AndIntro {
+            position: (0, 0),
+            orientation: Direction::East,
+        });
+        assert_eq!(
+            reason(&post(unsolved, "key", "key")),
+            Some(RejectionReason::IncompleteCircuit)
+        );
+
+        // The proofs above bound the key to their player, who has no other
+        assert_eq!(
+            reason(&submit("local-bob", "key", "key")),
+            Some(RejectionReason::WrongPlayer)
+        );
+        assert_eq!(
+            reason(&submit("local", "other", "other")),
+            Some(RejectionReason::WrongPlayer)
+        );
+
+        // Nothing rejected was scored
+        let response = server.handle(&get(LEADERBOARD_PATH, &[]));
+        assert!(body::<Vec<LeaderboardEntry>>(&response).is_empty());
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let server = server();
+
+        let mut unsigned = submit("local-ada", "key", "key");
+        unsigned.headers.clear();
+        assert_eq!(server.handle(&unsigned).status, 401);
+
+        let status = |request: &Request| server.handle(request).status;
+        assert_eq!(status(&get(LEADERBOARD_PATH, &[("limit", "ten")])), 400);
+        assert_eq!(status(&get(PLAYER_STATS_PATH, &[])), 400);
+        assert_eq!(
+            status(&get(PLAYER_STATS_PATH, &[("player_id", "local-ada")])),
+            404
+        );
+        assert_eq!(status(&get(PROOFS_PATH, &[])), 405);
+        assert_eq!(status(&get("/api/v2/anything", &[])), 404);
+    }
+
+    #[test]
+    fn test_serves_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Arc::new(server());
+        std::thread::spawn(move || server.serve(listener));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {}?limit=5 HTTP/1.1\r\nHost: {}\r\n\r\n",
+            LEADERBOARD_PATH, addr
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n[]"));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |args: &[&str], env: &[(&str, &str)]| {
+            let env: Vec<(String, String)> = env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            ServerArgs::parse(args.iter().map(|a| a.to_string()), move |name| {
+                env.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+            })
+        };
+
+        let defaults = args(&[], &[]).unwrap();
+        assert_eq!(defaults.addr, DEFAULT_ADDR);
+        assert_eq!(defaults.data_dir, PathBuf::from(DEFAULT_DATA_DIR));
+        assert_eq!(defaults.packs_dir, None);
+
+        let parsed = args(
+            &["--addr=0.0.0.0:9000", "--packs", "/srv/packs"],
+            &[(ADDR_VAR, "127.0.0.1:1"), (DATA_DIR_VAR, "/srv/pow")],
+        )
+        .unwrap();
+        assert_eq!(parsed.addr, "0.0.0.0:9000");
+        assert_eq!(parsed.data_dir, PathBuf::from("/srv/pow"));
+        assert_eq!(parsed.packs_dir, Some(PathBuf::from("/srv/packs")));
+
+        assert!(args(&["--help"], &[]).unwrap().help);
+        assert!(args(&["--addr"], &[]).is_err());
+        assert!(args(&["--port", "80"], &[]).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Accepted proofs, and the boards worked out from them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::levels::{storage, LevelPackError};
+use crate::protocol::{LeaderboardEntry, PlayerStatsResponse, ProofSubmission};
+
+/// Scores file name in the server's data directory
+pub const SCORES_FILE: &str = "scores.json";
+
+/// Points for a player's first accepted proof of a level. Solving a level
+/// again can improve the player's time but scores nothing more.
+pub const POINTS_PER_LEVEL: u32 = 100;
+
+/// One accepted proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofRecord {
+    pub player_id: String,
+    pub player_name: String,
+    pub level_id: u32,
+    pub time_taken_secs: u64,
+    pub points: u32,
+    /// Seconds since the Unix epoch
+    pub submitted_at: u64,
+}
+
+/// Every accepted proof, oldest first, saved after each new one
+#[derive(Debug, Default)]
+pub struct ScoreStore {
+    /// Where the scores are saved, or `None` to keep them in memory
+    path: Option<PathBuf>,
+    records: Vec<ProofRecord>,
+}
+
+/// A player's totals across every level
+struct Standing<'a> {
+    player_id: &'a str,
+    player_name: &'a str,
+    proofs: u32,
+    points: u32,
+    levels: Vec<u32>,
+}
+
+impl ScoreStore {
+    /// Open the scores in `data_dir`, starting empty if there are none
+    pub fn open(data_dir: &Path) -> Result<Self, LevelPackError> {
+        let path = data_dir.join(SCORES_FILE);
+        let records = storage::load(&path)?.unwrap_or_default();
+        Ok(Self {
+            path: Some(path),
+            records,
+        })
+    }
+
+    /// Scores that are never saved
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> &[ProofRecord] {
+        &self.records
+    }
+
+    /// Record an accepted proof, returning the points it scored
+    pub fn record(
+        &mut self,
+        submission: &ProofSubmission,
+        submitted_at: u64,
+    ) -> Result<u32, LevelPackError> {
+        let proof = &submission.proof;
+        let solved_before = self
+            .records
+            .iter()
+            .any(|r| r.player_id == proof.player_id && r.level_id == proof.level_id);
+        let points = if solved_before { 0 } else { POINTS_PER_LEVEL };
+        self.records.push(ProofRecord {
+            player_id: proof.player_id.clone(),
+            player_name: submission
+                .player_name
+                .clone()
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| proof.player_id.clone()),
+            level_id: proof.level_id,
+            time_taken_secs: proof.time_taken_secs,
+            points,
+            submitted_at,
+        });
+        if let Some(path) = &self.path {
+            if let Err(e) = storage::save(path, &self.records) {
+                self.records.pop();
+                return Err(e);
+            }
+        }
+        Ok(points)
+    }
+
+    /// A page of the global board, ranked by points then proofs, or of a
+    /// level's board, ranked by best time
+    pub fn leaderboard(
+        &self,
+        level_id: Option<u32>,
+        offset: usize,
+        limit: usize,
+    ) -> Vec<LeaderboardEntry> {
+        let entries = match level_id {
+            None => self
+                .standings()
+                .into_iter()
+                .map(|s| LeaderboardEntry {
+                    player_name: s.player_name.to_string(),
+                    player_id: Some(s.player_id.to_string()),
+                    steam_id: None,
+                    proofs_completed: s.proofs,
+                    total_points: s.points,
+                    best_time_secs: None,
+                    rank: 0,
+                })
+                .collect(),
+            Some(level_id) => self.level_board(level_id),
+        };
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| LeaderboardEntry {
+                rank: i as u32 + 1,
+                ..entry
+            })
+            .skip(offset)
+            .take(limit)
+            .collect()
+    }
+
+    /// A player's totals, or `None` if they have no accepted proofs
+    pub fn player_stats(&self, player_id: &str) -> Option<PlayerStatsResponse> {
+        let standings = self.standings();
+        let index = standings.iter().position(|s| s.player_id == player_id)?;
+        let standing = &standings[index];
+        Some(PlayerStatsResponse {
+            total_proofs: standing.proofs,
+            total_points: standing.points,
+            global_rank: index as u32 + 1,
+            levels_completed: standing.levels.len() as u32,
+        })
+    }
+
+    /// Every player's totals, best first. Ties go to whoever got there
+    /// first.
+    fn standings(&self) -> Vec<Standing<'_>> {
+        let mut standings: Vec<Standing> = vec![];
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for record in &self.records {
+            let i = *index.entry(record.player_id.as_str()).or_insert_with(|| {
+                standings.push(Standing {
+                    player_id: &record.player_id,
+                    player_name: &record.player_name,
+                    proofs: 0,
+                    points: 0,
+                    levels: vec![],
+                });
+                standings.len() - 1
+            });
+            let standing = &mut standings[i];
+            // Players are shown by the name they last submitted with
+            standing.player_name = &record.player_name;
+            standing.proofs += 1;
+            standing.points += record.points;
+            if !standing.levels.contains(&record.level_id) {
+                standing.levels.push(record.level_id);
+            }
+        }
+        // Stable, so earlier players stay ahead on a tie
+        standings.sort_by(|a, b| b.points.cmp(&a.points).then(b.proofs.cmp(&a.proofs)));
+        standings
+    }
+
+    /// Everyone who solved the level, fastest first, unranked
+    fn level_board(&self, level_id: u32) -> Vec<LeaderboardEntry> {
+        let mut entries: Vec<LeaderboardEntry> = vec![];
+        for record in self.records.iter().filter(|r| r.level_id == level_id) {
+            let existing = entries
+                .iter()
+                .position(|e| e.player_id.as_deref() == Some(record.player_id.as_str()));
+            let Some(i) = existing else {
+                entries.push(LeaderboardEntry {
+                    player_name: record.player_name.clone(),
+                    player_id: Some(record.player_id.clone()),
+                    steam_id: None,
+                    proofs_completed: 1,
+                    total_points: record.points,
+                    best_time_secs: Some(record.time_taken_secs),
+                    rank: 0,
+                });
+                continue;
+            };
+            let entry = &mut entries[i];
+            entry.player_name = record.player_name.clone();
+            entry.proofs_completed += 1;
+            entry.total_points += record.points;
+            entry.best_time_secs = entry.best_time_secs.min(Some(record.time_taken_secs));
+        }
+        entries.sort_by_key(|e| e.best_time_secs);
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification::ExportedProof;
+
+    fn submission(player: &str, level_id: u32, time: u64) -> ProofSubmission {
+        ProofSubmission {
+            proof: ExportedProof {
+                level_id,
+                player_id: format!("local-{}", player.to_lowercase()),
+                proof_smt2: String::new(),
+                proof_isabelle: None,
+                solution_steps: vec![],
+                time_taken_secs: time,
+                solution: vec![],
+            },
+            signature: String::new(),
+            player_name: Some(player.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_boards_rank_players() {
+        let mut store = ScoreStore::in_memory();
+        assert_eq!(store.record(&submission("Ada", 1, 90), 1).unwrap(), 100);
+        assert_eq!(store.record(&submission("Bo", 1, 30), 2).unwrap(), 100);
+        assert_eq!(store.record(&submission("Bo", 2, 50), 3).unwrap(), 100);
+        // Solving again improves the time but scores nothing
+        assert_eq!(store.record(&submission("Ada", 1, 20), 4).unwrap(), 0);
+        assert_eq!(store.record(&submission("Cy", 2, 40), 5).unwrap(), 100);
+
+        let global = store.leaderboard(None, 0, 10);
+        let names: Vec<_> = global.iter().map(|e| e.player_name.as_str()).collect();
+        // Ada and Cy tie on points, but Ada has more proofs
+        assert_eq!(names, ["Bo", "Ada", "Cy"]);
+        assert_eq!(global[0].total_points, 200);
+        assert_eq!(global[1].total_points, 100);
+        assert_eq!(global.iter().map(|e| e.rank).collect::<Vec<_>>(), [1, 2, 3]);
+
+        let level = store.leaderboard(Some(1), 0, 10);
+        assert_eq!(level[0].player_name, "Ada");
+        assert_eq!(level[0].best_time_secs, Some(20));
+        assert_eq!(level[1].player_name, "Bo");
+        assert!(store.leaderboard(Some(9), 0, 10).is_empty());
+
+        let page = store.leaderboard(None, 1, 1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].rank, 2);
+        assert_eq!(page[0].player_name, "Ada");
+
+        let stats = store.player_stats("local-bo").unwrap();
+        assert_eq!(stats.total_proofs, 2);
+        assert_eq!(stats.levels_completed, 2);
+        assert!(store.player_stats("local-nobody").is_none());
+    }
+
+    #[test]
+    fn test_scores_persist() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("pow-scores-{}", nanos));
+
+        let mut store = ScoreStore::open(&dir).unwrap();
+        store.record(&submission("Ada", 1, 90), 1).unwrap();
+        store.record(&submission("", 2, 60), 2).unwrap();
+
+        let reopened = ScoreStore::open(&dir).unwrap();
+        assert_eq!(reopened.records(), store.records());
+        // No name given, so the player id stands in
+        assert_eq!(reopened.records()[1].player_name, "local-");
+    }
+}
@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Checking a submitted proof before it scores.
+//!
+//! The server doesn't take the game's word for a solve. It finds the
+//! level, checks the solution keeps to the level's rules, rebuilds the
+//! proof from the solution's circuit, compares it with the submitted
+//! SMT-LIB2, and runs the verifier on it.
+
+use crate::game::{BoardState, Level, LogicPiece, PlaceablePiece};
+use crate::levels::LevelPack;
+use crate::protocol::{Rejection, RejectionReason};
+use crate::verification::{
+    solution_to_smt, verify_level_solution, ExportedProof, VerificationVerdict, VerifierOptions,
+};
+
+/// The level with this id in the first pack that has one. Proofs only
+/// carry the level id, so a server's packs should not share level ids.
+pub fn find_level(packs: &[LevelPack], level_id: u32) -> Option<&Level> {
+    packs
+        .iter()
+        .flat_map(|pack| &pack.levels)
+        .find(|level| level.id == level_id)
+}
+
+/// Accept `proof` only if its solution proves the level it names
+pub fn check_proof(
+    packs: &[LevelPack],
+    proof: &ExportedProof,
+    options: &VerifierOptions,
+) -> Result<(), Rejection> {
+    let Some(level) = find_level(packs, proof.level_id) else {
+        return Err(Rejection::new(
+            RejectionReason::UnknownLevel,
+            format!("no level with id {} on this server", proof.level_id),
+        ));
+    };
+    if proof.solution.is_empty() {
+        return Err(Rejection::new(
+            RejectionReason::MissingSolution,
+            "the proof has no solution to check",
+        ));
+    }
+    check_rules(level, &proof.solution)?;
+
+    let Some(expected) = solution_to_smt(level, &proof.solution) else {
+        return Err(Rejection::new(
+            RejectionReason::IncompleteCircuit,
+            "some goal is not fed by a complete circuit",
+        ));
+    };
+    if proof.proof_smt2 != expected {
+        return Err(Rejection::new(
+            RejectionReason::ProofMismatch,
+            "the SMT-LIB2 is not what the solution's circuit proves",
+        ));
+    }
+
+    match verify_level_solution(level, &proof.solution, options) {
+        VerificationVerdict::Verified => Ok(()),
+        VerificationVerdict::Rejected => Err(Rejection::new(
+            RejectionReason::NotProved,
+            format!("the circuit does not prove level {}", level.id),
+        )),
+        VerificationVerdict::CannotVerify => Err(Rejection::new(
+            RejectionReason::CannotVerify,
+            "this server has no verifier, so it can't accept proofs",
+        )),
+    }
+}
+
+/// The level's own rules, that everything the player added came from the
+/// level's inventory, and that every piece, the level's own included, fits
+/// on the board the way the game places it
+fn check_rules(level: &Level, solution: &[LogicPiece]) -> Result<(), Rejection> {
+    let broken = |detail: String| Err(Rejection::new(RejectionReason::BrokenRules, detail));
+
+    let errors = crate::game::validation::check_level_rules(level, solution);
+    if !errors.is_empty() {
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        return broken(errors.join("; "));
+    }
+
+    let added = added_pieces(level, solution);
+    for piece in &added {
+        let (x, y) = piece.position();
+        let Some(kind) = PlaceablePiece::of(piece) else {
+            return broken(format!(
+                "the piece at ({}, {}) is not one players can place",
+                x, y
+            ));
+        };
+        let Some(slot) = level.inventory.slot(kind) else {
+            return broken(format!("the level doesn't offer {}", kind.name()));
+        };
+        let used = added
+            .iter()
+            .filter(|p| PlaceablePiece::of(p) == Some(kind))
+            .count() as u32;
+        if slot.count.is_some_and(|count| used > count) {
+            return broken(format!(
+                "{} {} placed but the level offers {}",
+                used,
+                kind.name(),
+                slot.count.unwrap_or_default()
+            ));
+        }
+    }
+
+    // Replay the whole solution onto the level's empty board, so the
+    // level's own pieces are checked wherever they ended up
+    let start = &level.initial_state;
+    let mut board = BoardState::new(start.width, start.height);
+    board.obstacles = start.obstacles.clone();
+    for piece in solution {
+        if !board.place_piece(piece.clone()) {
+            let (x, y) = piece.position();
+            return broken(format!(
+                "the piece at ({}, {}) is off the board or on another piece",
+                x, y
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The pieces in `solution` that the level doesn't start with. Movable
+/// pieces the level starts with count as its own wherever they are.
+fn added_pieces<'a>(level: &Level, solution: &'a [LogicPiece]) -> Vec<&'a LogicPiece> {
+    let board = &level.initial_state;
+    let mut added: Vec<&LogicPiece> = solution.iter().collect();
+    for original in board.pieces() {
+        let movable = board.flags_at(original.position()).movable;
+        let found = added.iter().position(|&p| {
+            p == original
+                || (movable && !matches!(p, LogicPiece::Wire { .. }) && {
+                    let mut moved = original.clone();
+                    moved.set_position(p.position());
+                    moved == *p
+                })
+        });
+        if let Some(i) = found {
+            added.swap_remove(i);
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, PieceFlags};
+    use crate::server::tests::{and_gate, pack, proof};
+
+    fn reason(proof: &ExportedProof) -> Option<RejectionReason> {
+        reason_in(pack(), proof)
+    }
+
+    fn reason_in(pack: LevelPack, proof: &ExportedProof) -> Option<RejectionReason> {
+        check_proof(&[pack], proof, &VerifierOptions::default())
+            .err()
+            .map(|r| r.reason)
+    }
+
+    /// The level in [`pack`] without Q, offering a NOT gate and a wire as
+    /// well, solved by an AND of P and a NOT of P wired from the same P
+    fn contradiction() -> (LevelPack, ExportedProof) {
+        let mut pack = pack();
+        let level = &mut pack.levels[0];
+        level.initial_state.pieces_mut().remove(1);
+        level.inventory.set_count(PlaceablePiece::NotGate, Some(1));
+        level.inventory.set_count(PlaceablePiece::Wire, Some(1));
+        let mut solution = level.initial_state.pieces().to_vec();
+        solution.extend([
+            and_gate(),
+            LogicPiece::NotIntro {
+                position: (3, 4),
+                orientation: Direction::East,
+            },
+            // From the NOT gate's input to P's output
+            LogicPiece::Wire {
+                from: (2, 4),
+                to: (4, 5),
+            },
+        ]);
+        let proof = ExportedProof::from_solution(level, &solution, 30);
+        (pack, proof)
+    }
+
+    #[test]
+    fn test_rejects_what_the_solution_does_not_prove() {
+        let mut unknown = proof(and_gate());
+        unknown.level_id = 99;
+        assert_eq!(reason(&unknown), Some(RejectionReason::UnknownLevel));
+
+        let mut empty = proof(and_gate());
+        empty.solution.clear();
+        assert_eq!(reason(&empty), Some(RejectionReason::MissingSolution));
+
+        // A forged assumption of the goal's formula
+        let mut forged = proof(and_gate());
+        forged.solution.push(LogicPiece::Assumption {
+            formula: "R".to_string(),
+            position: (0, 0),
+        });
+        assert_eq!(reason(&forged), Some(RejectionReason::BrokenRules));
+
+        let mut moved = proof(and_gate());
+        moved.solution.remove(0);
+        assert_eq!(reason(&moved), Some(RejectionReason::BrokenRules));
+
+        let not_offered = proof(LogicPiece::NotIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&not_offered), Some(RejectionReason::BrokenRules));
+
+        let mut two_gates = proof(and_gate());
+        two_gates.solution.push(LogicPiece::AndIntro {
+            position: (7, 7),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&two_gates), Some(RejectionReason::BrokenRules));
+
+        // An OR gate on the top row runs off the board
+        let off_board = proof(LogicPiece::OrIntro {
+            position: (9, 9),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&off_board), Some(RejectionReason::BrokenRules));
+
+        // An OR gate on the AND gate's upper cell, and one on Q
+        let mut stacked = proof(and_gate());
+        stacked.solution.push(LogicPiece::OrIntro {
+            position: (4, 5),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&stacked), Some(RejectionReason::BrokenRules));
+        let on_assumption = proof(LogicPiece::OrIntro {
+            position: (3, 3),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&on_assumption), Some(RejectionReason::BrokenRules));
+
+        // Q is movable, but not onto P
+        let mut movable = pack();
+        movable.levels[0]
+            .initial_state
+            .set_flags((3, 4), PieceFlags::FREE);
+        let mut moved_onto_p = proof(and_gate());
+        moved_onto_p.solution[1].set_position((3, 5));
+        assert_eq!(
+            reason_in(movable, &moved_onto_p),
+            Some(RejectionReason::BrokenRules)
+        );
+
+        let unfed = proof(LogicPiece::AndIntro {
+            position: (6, 6),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&unfed), Some(RejectionReason::IncompleteCircuit));
+
+        let mut tampered = proof(and_gate());
+        tampered.proof_smt2 = "(check-sat)".to_string();
+        assert_eq!(reason(&tampered), Some(RejectionReason::ProofMismatch));
+    }
+
+    /// What the verifier is asked about the circuits below: the checks that
+    /// `test_verifier_decides_complete_circuits` expects to fail are in
+    /// the certificates the server compares, with or without Z3
+    #[test]
+    fn test_certificates_separate_derivation_from_goal() {
+        let or_gate = proof(LogicPiece::OrIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        });
+        assert!(check_rules(&pack().levels[0], &or_gate.solution).is_ok());
+        assert!(or_gate.proof_smt2.contains(
+            "(assert (=> (and P Q) R))\n(assert (or P Q))\n(assert (not R))\n(check-sat)"
+        ));
+
+        let (pack, contradiction) = contradiction();
+        assert!(check_rules(&pack.levels[0], &contradiction.solution).is_ok());
+        assert!(contradiction
+            .proof_smt2
+            .contains("(assert P)\n(assert (not (and P (not P))))\n(check-sat)"));
+    }
+
+    /// An OR gate fed by P and Q carries `(or P Q)`, which doesn't give R,
+    /// and `(and P (not P))` doesn't follow from P
+    #[cfg(feature = "z3-verify")]
+    #[test]
+    fn test_verifier_decides_complete_circuits() {
+        assert_eq!(reason(&proof(and_gate())), None);
+        let or_gate = proof(LogicPiece::OrIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        });
+        assert_eq!(reason(&or_gate), Some(RejectionReason::NotProved));
+
+        let (pack, contradiction) = contradiction();
+        assert_eq!(
+            reason_in(pack, &contradiction),
+            Some(RejectionReason::NotProved)
+        );
+    }
+
+    #[cfg(not(feature = "z3-verify"))]
+    #[test]
+    fn test_without_a_verifier_nothing_is_accepted() {
+        assert_eq!(
+            reason(&proof(and_gate())),
+            Some(RejectionReason::CannotVerify)
+        );
+    }
+}
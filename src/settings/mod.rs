@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Machine-wide settings, kept in a config file.
+//!
+//! Preferences that follow a player, such as UI scale and colour theme,
+//! live in their profile instead (see [`ProfileSettings`]). Settings are
+//! loaded once at startup. The settings screen edits the [`Settings`]
+//! resource directly, and the systems here apply each change live, except
+//! the data directory, which is only read at startup. [`paths`] decides
+//! where the settings file and the data directory are.
+//!
+//! [`ProfileSettings`]: crate::profiles::ProfileSettings
+
+pub mod paths;
+pub mod ui;
+
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PrimaryWindow, VideoModeSelection, WindowMode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::game::InputMap;
+use crate::levels::{storage, LevelPackError};
+use crate::verification::VerifierOptions;
+
+/// Settings file name
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// The public proof server
+pub const DEFAULT_SERVER_URL: &str = "https://api.proofofwork.game";
+
+/// Everything configurable about this installation
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window: WindowSettings,
+    /// Keyboard and gamepad bindings
+    pub bindings: InputMap,
+    pub verifier: VerifierOptions,
+    /// Proof server to submit to and fetch leaderboards from
+    pub server_url: String,
+    /// Directory for packs, profiles and the editor's recovery file, if
+    /// not the platform's usual place. Only read at startup.
+    pub data_dir: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: WindowSettings::default(),
+            bindings: InputMap::default(),
+            verifier: VerifierOptions::default(),
+            server_url: DEFAULT_SERVER_URL.to_string(),
+            data_dir: None,
+        }
+    }
+}
+
+/// How the game's window is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub mode: DisplayMode,
+    /// Size of the window when windowed, in logical pixels
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+/// Whether the window is windowed or fills the screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// A window covering the whole screen
+    Borderless,
+    /// Exclusive fullscreen at the monitor's current video mode
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            DisplayMode::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+impl WindowSettings {
+    /// The primary window as these settings describe it
+    pub fn window(&self, title: &str) -> Window {
+        Window {
+            title: title.into(),
+            resolution: (self.width, self.height).into(),
+            mode: self.mode.window_mode(),
+            resizable: true,
+            ..default()
+        }
+    }
+}
+
+/// Where the settings are saved
+#[derive(Resource, Debug, Clone)]
+pub struct SettingsFile {
+    pub path: PathBuf,
+}
+
+impl SettingsFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Read the settings, falling back to the defaults if there are none
+    /// or they can't be read
+    pub fn load(&self) -> Settings {
+        match storage::load(&self.path) {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(e) => {
+                warn!("Failed to load settings: {}", e);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self, settings: &Settings) -> Result<(), LevelPackError> {
+        storage::save(&self.path, settings)
+    }
+}
+
+/// Resize and re-mode the window when its settings change, leaving any
+/// resizing the player did by hand alone otherwise
+pub fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied: Local<Option<WindowSettings>>,
+) {
+    if *applied == Some(settings.window) {
+        return;
+    }
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+    *applied = Some(settings.window);
+    let mode = settings.window.mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let (width, height) = (settings.window.width as f32, settings.window.height as f32);
+    if window.resolution.width() != width || window.resolution.height() != height {
+        window.resolution.set(width, height);
+    }
+}
+
+/// Use the bindings from the settings for input
+pub fn apply_input_bindings(settings: Res<Settings>, mut map: ResMut<InputMap>) {
+    if settings.is_changed() {
+        map.set_if_neq(settings.bindings.clone());
+    }
+}
+
+/// Write the settings to their file
+pub fn save_settings(settings: Res<Settings>, file: Res<SettingsFile>) {
+    if let Err(e) = file.save(&settings) {
+        warn!("Failed to save settings: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Action, Binding};
+    use crate::verification::VerifierBackend;
+
+    fn temp_file(tag: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("pow-settings-{}-{}", tag, nanos))
+            .join(SETTINGS_FILE)
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let file = SettingsFile::new(temp_file("round_trip"));
+        assert_eq!(file.load(), Settings::default());
+
+        let mut settings = Settings::default();
+        settings.window.mode = DisplayMode::Borderless;
+        settings
+            .bindings
+            .bind(Binding::Key(KeyCode::KeyV), Action::Verify);
+        settings.verifier.backend = VerifierBackend::Disabled;
+        settings.verifier.timeout_ms = 250;
+        settings.server_url = "http://localhost:8080".to_string();
+        settings.data_dir = Some(PathBuf::from("/srv/proof-of-work"));
+        file.save(&settings).unwrap();
+
+        assert_eq!(file.load(), settings);
+    }
+
+    #[test]
+    fn test_partial_settings_file_keeps_defaults() {
+        let path = temp_file("partial");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, r#"{"server_url": "http://classroom:8080"}"#).unwrap();
+
+        let settings = SettingsFile::new(path).load();
+        assert_eq!(settings.server_url, "http://classroom:8080");
+        assert_eq!(settings.window, WindowSettings::default());
+        assert_eq!(settings.bindings, InputMap::default());
+    }
+}
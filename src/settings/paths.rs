@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Where the game keeps its files.
+//!
+//! Each directory comes from, in order: a command-line flag, an
+//! environment variable, the settings file (data directory only), and
+//! finally the platform's usual place: the XDG base directories on Linux,
+//! `~/Library/Application Support` on macOS and `%APPDATA%` on Windows.
+
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+use super::{Settings, SETTINGS_FILE};
+
+/// Overrides the data directory
+pub const DATA_DIR_VAR: &str = "PROOF_OF_WORK_DATA_DIR";
+
+/// Overrides the config directory
+pub const CONFIG_DIR_VAR: &str = "PROOF_OF_WORK_CONFIG_DIR";
+
+/// Overrides the built-in packs directory
+pub const BUILTIN_PACKS_VAR: &str = "PROOF_OF_WORK_BUILTIN_PACKS";
+
+/// Where earlier versions kept everything, relative to the working directory
+pub const LEGACY_DATA_DIR: &str = "./data";
+
+/// Directory name under the platform's data and config directories
+const APP_DIR: &str = "proof-of-work";
+
+pub const USAGE: &str = "\
+Usage: proof-of-work [OPTIONS]
+
+Options:
+  --data-dir <DIR>       Where packs, profiles and progress are saved
+                         [env: PROOF_OF_WORK_DATA_DIR]
+  --config-dir <DIR>     Where settings.json is kept
+                         [env: PROOF_OF_WORK_CONFIG_DIR]
+  --builtin-packs <DIR>  Read-only packs shipped with the game
+                         [env: PROOF_OF_WORK_BUILTIN_PACKS]
+  -h, --help             Print this help";
+
+/// Directories given on the command line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    pub data_dir: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    pub builtin_packs: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl CliArgs {
+    /// Parse the arguments after the program name. Accepts both
+    /// `--flag value` and `--flag=value`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut cli = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let slot = match flag.as_str() {
+                "-h" | "--help" => {
+                    cli.help = true;
+                    continue;
+                }
+                "--data-dir" => &mut cli.data_dir,
+                "--config-dir" => &mut cli.config_dir,
+                "--builtin-packs" => &mut cli.builtin_packs,
+                _ => return Err(format!("unknown argument '{}'", flag)),
+            };
+            let value = inline
+                .or_else(|| args.next())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("{} needs a directory", flag))?;
+            *slot = Some(PathBuf::from(value));
+        }
+        Ok(cli)
+    }
+}
+
+/// The directories in use this run
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct GamePaths {
+    /// Holds the settings file
+    pub config_dir: PathBuf,
+    /// Holds user packs, profiles and the editor's recovery file
+    pub data_dir: PathBuf,
+    /// Read-only packs shipped with the game, if there are any
+    pub builtin_packs_dir: Option<PathBuf>,
+}
+
+impl GamePaths {
+    /// The config directory, which must be known before the settings are
+    /// read
+    pub fn config_dir(cli: &CliArgs, env: impl Fn(&str) -> Option<String>) -> PathBuf {
+        cli.config_dir
+            .clone()
+            .or_else(|| env_path(&env, CONFIG_DIR_VAR))
+            .or_else(|| platform_dir(&env, Kind::Config))
+            .unwrap_or_else(|| PathBuf::from(LEGACY_DATA_DIR))
+    }
+
+    /// Work out every directory from the flags, the environment and the
+    /// settings
+    pub fn resolve(
+        cli: &CliArgs,
+        settings: &Settings,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let data_dir = cli
+            .data_dir
+            .clone()
+            .or_else(|| env_path(&env, DATA_DIR_VAR))
+            .or_else(|| settings.data_dir.clone())
+            .unwrap_or_else(|| default_data_dir(&env));
+        let builtin_packs_dir = cli
+            .builtin_packs
+            .clone()
+            .or_else(|| env_path(&env, BUILTIN_PACKS_VAR))
+            .or_else(|| {
+                builtin_pack_candidates(&env)
+                    .into_iter()
+                    .find(|dir| dir.is_dir())
+            });
+        Self {
+            config_dir: Self::config_dir(cli, &env),
+            data_dir,
+            builtin_packs_dir,
+        }
+    }
+
+    pub fn settings_file(&self) -> PathBuf {
+        self.config_dir.join(SETTINGS_FILE)
+    }
+
+    /// Packs the player made or installed
+    pub fn user_packs_dir(&self) -> PathBuf {
+        self.data_dir.join("packs")
+    }
+
+    pub fn profiles_dir(&self) -> PathBuf {
+        self.data_dir.join("profiles")
+    }
+
+    pub fn editor_autosave_file(&self) -> PathBuf {
+        self.data_dir.join("editor_autosave.json")
+    }
+}
+
+/// The platform's data directory, unless an earlier version already left
+/// the player's profiles in `./data`, which keeps being used so nobody
+/// loses their progress
+fn default_data_dir(env: impl Fn(&str) -> Option<String>) -> PathBuf {
+    let legacy = Path::new(LEGACY_DATA_DIR);
+    match platform_dir(&env, Kind::Data) {
+        Some(dir) if dir.exists() || !legacy.join("profiles").is_dir() => dir,
+        _ => legacy.to_path_buf(),
+    }
+}
+
+/// Places a system-wide install may have put the built-in packs, most
+/// preferred first
+fn builtin_pack_candidates(env: impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    let mut candidates = vec![];
+    if let Some(dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        candidates.push(dir.join("packs"));
+    }
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        let data_dirs = env("XDG_DATA_DIRS")
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        candidates.extend(
+            data_dirs
+                .split(':')
+                .filter(|dir| Path::new(dir).is_absolute())
+                .map(|dir| Path::new(dir).join(APP_DIR).join("packs")),
+        );
+    }
+    candidates
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Data,
+    Config,
+}
+
+/// The platform's directory of this kind for the game
+fn platform_dir(env: impl Fn(&str) -> Option<String>, kind: Kind) -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env_path(&env, "APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        env_path(&env, "HOME")?.join("Library/Application Support")
+    } else {
+        // XDG paths must be absolute; relative ones are ignored
+        let (var, fallback) = match kind {
+            Kind::Data => ("XDG_DATA_HOME", ".local/share"),
+            Kind::Config => ("XDG_CONFIG_HOME", ".config"),
+        };
+        match env_path(&env, var).filter(|dir| dir.is_absolute()) {
+            Some(dir) => dir,
+            None => env_path(&env, "HOME")?.join(fallback),
+        }
+    };
+    Some(base.join(APP_DIR))
+}
+
+fn env_path(env: impl Fn(&str) -> Option<String>, name: &str) -> Option<PathBuf> {
+    env(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn args(args: &[&str]) -> Result<CliArgs, String> {
+        CliArgs::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cli = args(&["--data-dir", "/tmp/pow", "--config-dir=/etc/pow"]).unwrap();
+        assert_eq!(cli.data_dir, Some(PathBuf::from("/tmp/pow")));
+        assert_eq!(cli.config_dir, Some(PathBuf::from("/etc/pow")));
+        assert_eq!(cli.builtin_packs, None);
+        assert!(args(&["--help"]).unwrap().help);
+
+        assert!(args(&["--data-dir"]).is_err());
+        assert!(args(&["--data-dir="]).is_err());
+        assert!(args(&["--fullscreen"]).is_err());
+    }
+
+    #[test]
+    fn test_flag_beats_env_beats_settings() {
+        let settings = Settings {
+            data_dir: Some(PathBuf::from("/from/settings")),
+            ..Default::default()
+        };
+        let vars = env(&[(DATA_DIR_VAR, "/from/env"), (CONFIG_DIR_VAR, "/config/env")]);
+
+        let cli = args(&["--data-dir", "/from/flag"]).unwrap();
+        let paths = GamePaths::resolve(&cli, &settings, &vars);
+        assert_eq!(paths.data_dir, PathBuf::from("/from/flag"));
+        assert_eq!(
+            paths.settings_file(),
+            Path::new("/config/env/settings.json")
+        );
+
+        let paths = GamePaths::resolve(&CliArgs::default(), &settings, &vars);
+        assert_eq!(paths.data_dir, PathBuf::from("/from/env"));
+        assert_eq!(paths.user_packs_dir(), Path::new("/from/env/packs"));
+
+        let paths = GamePaths::resolve(&CliArgs::default(), &settings, env(&[]));
+        assert_eq!(paths.data_dir, PathBuf::from("/from/settings"));
+
+        let cli = args(&["--builtin-packs", "/opt/pow/packs"]).unwrap();
+        let paths = GamePaths::resolve(&cli, &settings, env(&[]));
+        assert_eq!(
+            paths.builtin_packs_dir,
+            Some(PathBuf::from("/opt/pow/packs"))
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_xdg_defaults() {
+        let vars = env(&[("HOME", "/home/ada")]);
+        assert_eq!(
+            platform_dir(&vars, Kind::Data),
+            Some(PathBuf::from("/home/ada/.local/share/proof-of-work"))
+        );
+        assert_eq!(
+            GamePaths::config_dir(&CliArgs::default(), &vars),
+            PathBuf::from("/home/ada/.config/proof-of-work")
+        );
+
+        let vars = env(&[
+            ("HOME", "/home/ada"),
+            ("XDG_DATA_HOME", "/data"),
+            ("XDG_CONFIG_HOME", "relative/config"),
+            ("XDG_DATA_DIRS", "/opt/share:relative"),
+        ]);
+        assert_eq!(
+            platform_dir(&vars, Kind::Data),
+            Some(PathBuf::from("/data/proof-of-work"))
+        );
+        assert_eq!(
+            platform_dir(&vars, Kind::Config),
+            Some(PathBuf::from("/home/ada/.config/proof-of-work"))
+        );
+        let candidates = builtin_pack_candidates(&vars);
+        assert_eq!(
+            candidates.last(),
+            Some(&PathBuf::from("/opt/share/proof-of-work/packs"))
+        );
+    }
+}
@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Settings screen.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::path::PathBuf;
+
+use super::paths::GamePaths;
+use super::{DisplayMode, Settings};
+use crate::game::{Action, ActionState, Binding, InputMap};
+use crate::profiles::{ProfileManager, ProfileSettings, Theme};
+use crate::states::GameState;
+use crate::verification::VerifierBackend;
+
+/// What the settings screen is in the middle of
+#[derive(Debug, Default, Resource)]
+pub struct SettingsScreenState {
+    /// Action waiting for a key or button to bind to it
+    pub listening: Option<Action>,
+    /// Data directory being typed
+    pub data_dir: String,
+}
+
+/// Fill in the text fields from the current settings
+pub fn open_settings(settings: Res<Settings>, mut screen: ResMut<SettingsScreenState>) {
+    screen.listening = None;
+    screen.data_dir = settings
+        .data_dir
+        .as_ref()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+}
+
+/// Render the settings screen
+#[allow(clippy::too_many_arguments)]
+pub fn settings_screen_system(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<Settings>,
+    paths: Res<GamePaths>,
+    mut profiles: ResMut<ProfileManager>,
+    mut screen: ResMut<SettingsScreenState>,
+    mut next_state: ResMut<NextState<GameState>>,
+    actions: Res<ActionState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    if let Some(action) = screen.listening {
+        let pressed = keyboard
+            .get_just_pressed()
+            .next()
+            .map(|&key| Binding::Key(key))
+            .or_else(|| {
+                gamepads
+                    .iter()
+                    .find_map(|pad| pad.get_just_pressed().next())
+                    .map(|&button| Binding::Gamepad(button))
+            });
+        match pressed {
+            Some(Binding::Key(KeyCode::Escape)) => screen.listening = None,
+            Some(binding) => {
+                settings.bindings.bind(binding, action);
+                screen.listening = None;
+            }
+            None => {}
+        }
+    } else if actions.just_pressed(Action::Back) {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
+
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+
+    let mut viewport_ui = egui::Ui::new(
+        ctx.clone(),
+        "settings_viewport".into(),
+        egui::UiBuilder::new()
+            .layer_id(egui::LayerId::background())
+            .max_rect(ctx.viewport_rect()),
+    );
+
+    // Edit a copy so the settings only count as changed when they are
+    let mut edited = settings.clone();
+    let mut player = profiles.active_profile().map(|p| p.settings.clone());
+    let mut back = false;
+
+    egui::CentralPanel::default().show(&mut viewport_ui, |ui| {
+        ui.horizontal(|ui| {
+            back = ui.button("Back").clicked();
+            ui.heading("Settings");
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("Display");
+            display_settings(ui, &mut edited);
+            ui.add_space(10.0);
+
+            if let Some(player) = &mut player {
+                ui.heading("Player");
+                player_settings(ui, player);
+                ui.add_space(10.0);
+            }
+
+            ui.heading("Verifier");
+            egui::ComboBox::new("verifier_backend", "Backend")
+                .selected_text(edited.verifier.backend.name())
+                .show_ui(ui, |ui| {
+                    for backend in VerifierBackend::ALL {
+                        ui.selectable_value(&mut edited.verifier.backend, backend, backend.name());
+                    }
+                });
+            ui.add(
+                egui::Slider::new(&mut edited.verifier.timeout_ms, 100..=60_000)
+                    .logarithmic(true)
+                    .text("Timeout (ms)"),
+            );
+            ui.add_space(10.0);
+
+            ui.heading("Network");
+            ui.horizontal(|ui| {
+                ui.label("Server URL:");
+                ui.text_edit_singleline(&mut edited.server_url);
+            });
+            ui.add_space(10.0);
+
+            ui.heading("Data");
+            ui.horizontal(|ui| {
+                ui.label("Data directory:");
+                if ui.text_edit_singleline(&mut screen.data_dir).changed() {
+                    let dir = screen.data_dir.trim();
+                    edited.data_dir = (!dir.is_empty()).then(|| PathBuf::from(dir));
+                }
+            });
+            ui.label(format!("In use: {}", paths.data_dir.display()));
+            ui.label(
+                egui::RichText::new(
+                    "Leave empty for the default. Takes effect next time the game starts, \
+                     unless --data-dir or PROOF_OF_WORK_DATA_DIR is set.",
+                )
+                .weak(),
+            );
+            ui.add_space(10.0);
+
+            ui.heading("Controls");
+            controls_settings(ui, &mut edited.bindings, &mut screen.listening);
+        });
+    });
+
+    settings.set_if_neq(edited);
+    // Saved to the profile on leaving the screen, not on every slider step
+    if let (Some(index), Some(player)) = (profiles.active, player) {
+        if profiles.profiles[index].settings != player {
+            profiles.profiles[index].settings = player;
+        }
+    }
+    if back {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn display_settings(ui: &mut egui::Ui, settings: &mut Settings) {
+    egui::ComboBox::new("display_mode", "Window mode")
+        .selected_text(settings.window.mode.name())
+        .show_ui(ui, |ui| {
+            for mode in DisplayMode::ALL {
+                ui.selectable_value(&mut settings.window.mode, mode, mode.name());
+            }
+        });
+    ui.add_enabled_ui(settings.window.mode == DisplayMode::Windowed, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Window size:");
+            ui.add(egui::DragValue::new(&mut settings.window.width).range(640..=7680));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut settings.window.height).range(480..=4320));
+        });
+    });
+}
+
+fn player_settings(ui: &mut egui::Ui, player: &mut ProfileSettings) {
+    ui.add(egui::Slider::new(&mut player.ui_scale, 0.5..=2.0).text("UI scale"));
+    egui::ComboBox::new("theme", "Theme")
+        .selected_text(player.theme.name())
+        .show_ui(ui, |ui| {
+            for theme in Theme::ALL {
+                ui.selectable_value(&mut player.theme, theme, theme.name());
+            }
+        });
+    ui.checkbox(
+        &mut player.submit_proofs,
+        "Submit verified proofs to the server",
+    );
+}
+
+fn controls_settings(ui: &mut egui::Ui, bindings: &mut InputMap, listening: &mut Option<Action>) {
+    egui::Grid::new("bindings").striped(true).show(ui, |ui| {
+        for action in Action::all() {
+            ui.label(action.label());
+            if *listening == Some(action) {
+                ui.label("Press a key or button (ESC cancels)");
+            } else {
+                let bound: Vec<String> = bindings.bindings_for(action).map(|b| b.label()).collect();
+                ui.label(if bound.is_empty() {
+                    "Unbound".to_string()
+                } else {
+                    bound.join(", ")
+                });
+            }
+            if ui.small_button("Add binding").clicked() {
+                *listening = Some(action);
+            }
+            if ui.small_button("Clear").clicked() {
+                bindings.clear(action);
+            }
+            ui.end_row();
+        }
+    });
+    if ui.button("Reset controls").clicked() {
+        *bindings = InputMap::default();
+    }
+}
+
+/// Write the active player's settings to their profile
+pub fn save_player_settings(mut profiles: ResMut<ProfileManager>) {
+    let Some(index) = profiles.active else {
+        return;
+    };
+    let settings = profiles.profiles[index].settings.clone();
+    if let Err(e) = profiles.update_settings(index, settings) {
+        warn!("Failed to save profile settings: {}", e);
+    }
+}
+
+/// Scale and theme the UI for the active player
+pub fn apply_profile_settings(
+    mut contexts: EguiContexts,
+    profiles: Res<ProfileManager>,
+    mut applied: Local<Option<ProfileSettings>>,
+) {
+    let settings = profiles
+        .active_profile()
+        .map(|p| p.settings.clone())
+        .unwrap_or_default();
+    if applied.as_ref() == Some(&settings) {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+    ctx.set_zoom_factor(settings.ui_scale);
+    ctx.set_theme(match settings.theme {
+        Theme::Dark => egui::ThemePreference::Dark,
+        Theme::Light => egui::ThemePreference::Light,
+        Theme::System => egui::ThemePreference::System,
+    });
+    *applied = Some(settings);
+}
@@ -0,0 +1,439 @@
+// SPDX-License-Identifier: MPL-2.0
+//! Leaderboard screen: the global board and each level's board from the
+//! proof server, and the active profile's local stats. The local stats
+//! stand in for the online boards when the server can't be reached or the
+//! game was built without networking.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use super::format_duration;
+use crate::game::{Action, ActionState, PlayerStats};
+use crate::levels::LevelPackManager;
+use crate::profiles::ProfileManager;
+use crate::states::GameState;
+
+#[cfg(feature = "network")]
+use crate::network::{
+    leaderboard::{OnlineLeaderboard, OnlineState},
+    LeaderboardEntry, LeaderboardPage, LeaderboardQuery, NetworkClient,
+};
+
+/// Entries on each page of an online board
+#[cfg(feature = "network")]
+pub const PAGE_SIZE: u32 = 20;
+
+/// The boards the screen can show
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LeaderboardTab {
+    #[default]
+    Global,
+    Level,
+    /// The active profile's own stats, kept on this machine
+    Local,
+}
+
+/// Which board the leaderboard screen is showing
+#[derive(Debug, Default, Resource)]
+pub struct LeaderboardScreen {
+    pub tab: LeaderboardTab,
+    /// Level whose board the level tab shows
+    pub level_id: Option<u32>,
+    /// Page of the online board, from zero
+    pub page: u32,
+}
+
+impl LeaderboardScreen {
+    /// The online board to fetch, if the screen is showing one
+    #[cfg(feature = "network")]
+    pub fn query(&self) -> Option<LeaderboardQuery> {
+        let level_id = match self.tab {
+            LeaderboardTab::Global => None,
+            LeaderboardTab::Level => Some(self.level_id?),
+            LeaderboardTab::Local => return None,
+        };
+        Some(LeaderboardQuery {
+            level_id,
+            offset: self.page * PAGE_SIZE,
+            limit: PAGE_SIZE,
+        })
+    }
+}
+
+/// Start on the first page, with the level tab at the level last played
+pub fn open_leaderboard(
+    mut screen: ResMut<LeaderboardScreen>,
+    pack_manager: Res<LevelPackManager>,
+    #[cfg(feature = "network")] mut online: ResMut<OnlineLeaderboard>,
+) {
+    screen.page = 0;
+    if let Some(level) = pack_manager.current_level() {
+        screen.level_id = Some(level.id);
+    }
+    // Boards change while the player is away, so fetch them afresh
+    #[cfg(feature = "network")]
+    online.refresh();
+}
+
+/// Fetch the board the screen is showing and pick up finished fetches
+#[cfg(feature = "network")]
+pub fn fetch_leaderboard(
+    screen: Res<LeaderboardScreen>,
+    client: Option<Res<NetworkClient>>,
+    profiles: Res<ProfileManager>,
+    mut online: ResMut<OnlineLeaderboard>,
+) {
+    if let (Some(client), Some(query)) = (client, screen.query()) {
+        let player_id = profiles
+            .active_profile()
+            .map(|p| p.player_id.as_str())
+            .unwrap_or_default();
+        online.request(&client, query, player_id);
+    }
+    online.poll();
+}
+
+/// Render the leaderboard screen
+#[allow(clippy::too_many_arguments)]
+pub fn leaderboard_screen_system(
+    mut contexts: EguiContexts,
+    mut screen: ResMut<LeaderboardScreen>,
+    stats: Res<PlayerStats>,
+    pack_manager: Res<LevelPackManager>,
+    profiles: Res<ProfileManager>,
+    mut next_state: ResMut<NextState<GameState>>,
+    actions: Res<ActionState>,
+    #[cfg(feature = "network")] mut online: ResMut<OnlineLeaderboard>,
+) {
+    if actions.just_pressed(Action::Back) {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
+
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+
+    let mut viewport_ui = egui::Ui::new(
+        ctx.clone(),
+        "leaderboard_viewport".into(),
+        egui::UiBuilder::new()
+            .layer_id(egui::LayerId::background())
+            .max_rect(ctx.viewport_rect()),
+    );
+
+    egui::CentralPanel::default().show(&mut viewport_ui, |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("< Back").clicked() {
+                next_state.set(GameState::MainMenu);
+            }
+            ui.heading("Leaderboard");
+            if let Some(profile) = profiles.active_profile() {
+                ui.separator();
+                ui.label(&profile.name);
+            }
+        });
+        ui.horizontal(|ui| {
+            let before = screen.tab;
+            ui.selectable_value(&mut screen.tab, LeaderboardTab::Global, "Global");
+            ui.selectable_value(&mut screen.tab, LeaderboardTab::Level, "By level");
+            ui.selectable_value(&mut screen.tab, LeaderboardTab::Local, "Your stats");
+            if screen.tab != before {
+                screen.page = 0;
+            }
+        });
+        ui.separator();
+
+        if screen.tab == LeaderboardTab::Level {
+            level_picker(ui, &mut screen, &pack_manager);
+            ui.add_space(10.0);
+        }
+
+        if screen.tab == LeaderboardTab::Local {
+            local_stats(ui, &stats, &pack_manager);
+            return;
+        }
+
+        #[cfg(feature = "network")]
+        {
+            let player_id = profiles.active_profile().map(|p| p.player_id.as_str());
+            online_board(
+                ui,
+                &mut screen,
+                &mut online,
+                player_id,
+                &stats,
+                &pack_manager,
+            );
+        }
+        #[cfg(not(feature = "network"))]
+        {
+            ui.label(
+                egui::RichText::new(
+                    "Online leaderboards aren't part of this build. Showing your stats instead.",
+                )
+                .weak(),
+            );
+            ui.add_space(10.0);
+            local_stats(ui, &stats, &pack_manager);
+        }
+    });
+}
+
+/// Choose the level whose board is shown
+fn level_picker(
+    ui: &mut egui::Ui,
+    screen: &mut LeaderboardScreen,
+    pack_manager: &LevelPackManager,
+) {
+    // Proofs only name the level, so levels sharing an id share a board
+    let mut levels: Vec<(u32, String)> = vec![];
+    for pack in &pack_manager.packs {
+        for level in &pack.levels {
+            if !levels.iter().any(|(id, _)| *id == level.id) {
+                levels.push((level.id, format!("{} / {}", pack.name, level.name)));
+            }
+        }
+    }
+    let selected = levels
+        .iter()
+        .find(|(id, _)| Some(*id) == screen.level_id)
+        .map(|(_, name)| name.clone())
+        .unwrap_or_else(|| "Choose a level".to_string());
+    let before = screen.level_id;
+    egui::ComboBox::new("leaderboard_level", "Level")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (id, name) in levels {
+                ui.selectable_value(&mut screen.level_id, Some(id), name);
+            }
+        });
+    if screen.level_id != before {
+        screen.page = 0;
+    }
+}
+
+/// The online board, or the local stats if it couldn't be fetched
+#[cfg(feature = "network")]
+fn online_board(
+    ui: &mut egui::Ui,
+    screen: &mut LeaderboardScreen,
+    online: &mut OnlineLeaderboard,
+    player_id: Option<&str>,
+    stats: &PlayerStats,
+    pack_manager: &LevelPackManager,
+) {
+    if screen.query().is_none() {
+        ui.label("Choose a level to see its board.");
+        return;
+    }
+    match online.state.clone() {
+        OnlineState::Idle | OnlineState::Loading => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Fetching the leaderboard...");
+            });
+        }
+        OnlineState::Failed(error) => {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Couldn't reach the server: {}", error),
+                );
+                if ui.button("Retry").clicked() {
+                    online.refresh();
+                }
+            });
+            ui.label(egui::RichText::new("Showing your stats instead.").weak());
+            ui.add_space(10.0);
+            local_stats(ui, stats, pack_manager);
+        }
+        OnlineState::Loaded(page) => board_page(ui, screen, &page, player_id),
+    }
+}
+
+/// One page of an online board, with the player's own standing above it
+#[cfg(feature = "network")]
+fn board_page(
+    ui: &mut egui::Ui,
+    screen: &mut LeaderboardScreen,
+    page: &LeaderboardPage,
+    player_id: Option<&str>,
+) {
+    if let Some(me) = &page.player {
+        ui.label(format!(
+            "You: rank #{} with {} points from {} proofs, {} levels completed",
+            me.global_rank, me.total_points, me.total_proofs, me.levels_completed
+        ));
+        ui.add_space(10.0);
+    }
+
+    let by_level = page.query.level_id.is_some();
+    egui::ScrollArea::vertical()
+        .max_height(ui.available_height() - 40.0)
+        .show(ui, |ui| {
+            egui::Grid::new("leaderboard_entries")
+                .num_columns(4)
+                .striped(true)
+                .spacing([30.0, 4.0])
+                .show(ui, |ui| {
+                    let third = if by_level { "Best time" } else { "Proofs" };
+                    for heading in ["Rank", "Player", third, "Points"] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+
+                    for entry in &page.entries {
+                        entry_row(ui, entry, by_level, player_id);
+                    }
+                });
+            if page.entries.is_empty() {
+                ui.label("Nobody is on this board yet.");
+            }
+        });
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(screen.page > 0, egui::Button::new("< Previous"))
+            .clicked()
+        {
+            screen.page -= 1;
+        }
+        ui.label(format!("Page {}", screen.page + 1));
+        // A short page is the last one
+        let more = page.entries.len() as u32 >= page.query.limit;
+        if ui.add_enabled(more, egui::Button::new("Next >")).clicked() {
+            screen.page += 1;
+        }
+    });
+}
+
+#[cfg(feature = "network")]
+fn entry_row(ui: &mut egui::Ui, entry: &LeaderboardEntry, by_level: bool, player_id: Option<&str>) {
+    let is_player = player_id.is_some() && entry.player_id.as_deref() == player_id;
+    let text = |text: String| {
+        let text = egui::RichText::new(text);
+        if is_player {
+            text.strong().color(egui::Color32::LIGHT_GREEN)
+        } else {
+            text
+        }
+    };
+    ui.label(text(format!("#{}", entry.rank)));
+    ui.label(text(if is_player {
+        format!("{} (you)", entry.player_name)
+    } else {
+        entry.player_name.clone()
+    }));
+    ui.label(text(if by_level {
+        entry
+            .best_time_secs
+            .map(format_duration)
+            .unwrap_or_default()
+    } else {
+        entry.proofs_completed.to_string()
+    }));
+    ui.label(text(entry.total_points.to_string()));
+    ui.end_row();
+}
+
+/// Totals, streaks and per-level history for the active profile
+fn local_stats(ui: &mut egui::Ui, stats: &PlayerStats, pack_manager: &LevelPackManager) {
+    let today = crate::game::stats::current_day();
+
+    egui::Grid::new("stats_totals")
+        .num_columns(2)
+        .spacing([40.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Proofs completed");
+            ui.label(stats.proofs_completed.to_string());
+            ui.end_row();
+            ui.label("Time played");
+            ui.label(format_duration(stats.total_playtime_secs));
+            ui.end_row();
+            ui.label("Daily streak");
+            ui.label(format!("{} day(s)", stats.current_streak(today)));
+            ui.end_row();
+            ui.label("Best streak");
+            ui.label(format!("{} day(s)", stats.best_streak_days));
+            ui.end_row();
+        });
+
+    ui.add_space(20.0);
+    ui.heading("Levels");
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("stats_levels")
+            .num_columns(8)
+            .striped(true)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                for heading in [
+                    "Level",
+                    "Attempts",
+                    "Failed",
+                    "Solved",
+                    "Best",
+                    "Median",
+                    "Slowest",
+                    "Fewest pieces",
+                ] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                let time = |t: Option<u64>| t.map(format_duration).unwrap_or_default();
+                for pack in &pack_manager.packs {
+                    for level in &pack.levels {
+                        let Some(history) = stats.level_history(&pack.id, level.id) else {
+                            continue;
+                        };
+                        ui.label(format!("{} / {}", pack.name, level.name));
+                        ui.label(history.attempts.to_string());
+                        ui.label(history.verification_failures.to_string());
+                        ui.label(history.solves().to_string());
+                        ui.label(time(history.best_time()));
+                        ui.label(time(history.median_time()));
+                        ui.label(time(history.worst_time()));
+                        ui.label(
+                            history
+                                .fewest_pieces()
+                                .map(|n| n.to_string())
+                                .unwrap_or_default(),
+                        );
+                        ui.end_row();
+                    }
+                }
+            });
+    });
+
+    if stats.levels.is_empty() {
+        ui.label("No levels played yet.");
+    }
+}
+
+#[cfg(all(test, feature = "network"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_follows_tab_and_page() {
+        let mut screen = LeaderboardScreen::default();
+        assert_eq!(screen.query(), Some(LeaderboardQuery::default()));
+
+        screen.tab = LeaderboardTab::Level;
+        assert_eq!(screen.query(), None);
+        screen.level_id = Some(4);
+        screen.page = 2;
+        assert_eq!(
+            screen.query(),
+            Some(LeaderboardQuery {
+                level_id: Some(4),
+                offset: 40,
+                limit: PAGE_SIZE,
+            })
+        );
+
+        screen.tab = LeaderboardTab::Local;
+        assert_eq!(screen.query(), None);
+    }
+}
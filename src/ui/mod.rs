@@ -1,9 +1,16 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod leaderboard;
+
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 
-use crate::game::{CurrentLevel, PlaceablePiece, PlayerStats, SelectedPieceType};
+use crate::editor::{EditorState, TestPlaySession};
+use crate::game::signal;
+use crate::game::{
+    fit_label, Action, ActionState, CurrentLevel, LiveCircuit, LogicPiece, PlaceablePiece,
+    PlayerPlaced, PlayerStats, ResetLevelEvent, SelectedPieceType, SolvedLevel,
+};
 use crate::states::GameState;
 
 /// Main menu system - renders the start screen
@@ -66,6 +73,30 @@ pub fn main_menu_system(mut contexts: EguiContexts, mut next_state: ResMut<NextS
                 next_state.set(GameState::Editor);
             }
 
+            ui.add_space(15.0);
+
+            if ui
+                .add_sized(
+                    [200.0, 40.0],
+                    egui::Button::new(egui::RichText::new("Leaderboard").size(18.0)),
+                )
+                .clicked()
+            {
+                next_state.set(GameState::Leaderboard);
+            }
+
+            ui.add_space(15.0);
+
+            if ui
+                .add_sized(
+                    [200.0, 40.0],
+                    egui::Button::new(egui::RichText::new("Settings").size(18.0)),
+                )
+                .clicked()
+            {
+                next_state.set(GameState::Settings);
+            }
+
             ui.add_space(20.0);
 
             ui.label(egui::RichText::new("Connect assumptions to prove the goal").size(14.0));
@@ -85,6 +116,125 @@ pub fn main_menu_system(mut contexts: EguiContexts, mut next_state: ResMut<NextS
     });
 }
 
+/// Label each gate and goal with the formula it currently carries, just
+/// above its tile.
+pub fn circuit_labels(
+    mut contexts: EguiContexts,
+    live: Res<LiveCircuit>,
+    pieces: Query<(&LogicPiece, &Sprite, &GlobalTransform)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("circuit_labels"),
+    ));
+
+    for (entity, formula) in live.entities.iter().zip(&live.state.signals) {
+        let Some(formula) = formula else { continue };
+        let Ok((piece, sprite, transform)) = pieces.get(*entity) else {
+            continue;
+        };
+        if matches!(
+            piece,
+            LogicPiece::Assumption { .. } | LogicPiece::Wire { .. }
+        ) {
+            continue;
+        }
+        let size = sprite.custom_size.unwrap_or(Vec2::ZERO);
+        let top = transform.translation() + Vec3::Y * size.max_element() / 2.0;
+        let Ok(anchor) = camera.world_to_viewport(camera_transform, top) else {
+            continue;
+        };
+
+        let galley = painter.layout_no_wrap(
+            signal::pretty(formula),
+            egui::FontId::monospace(12.0),
+            egui::Color32::WHITE,
+        );
+        let rect = egui::Align2::CENTER_BOTTOM
+            .anchor_size(egui::pos2(anchor.x, anchor.y), galley.size())
+            .expand(3.0);
+        painter.rect_filled(rect, 3.0, egui::Color32::from_black_alpha(190));
+        painter.galley(
+            rect.min + egui::vec2(3.0, 3.0),
+            galley,
+            egui::Color32::WHITE,
+        );
+    }
+}
+
+/// Draw each piece's label on its tile: a glyph for its kind, plus the
+/// formula or bound variable. Labels too long for the tile are cut short,
+/// and hovering the tile shows them in full.
+pub fn piece_labels(
+    mut contexts: EguiContexts,
+    pieces: Query<(&LogicPiece, &Sprite, &GlobalTransform)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("piece_labels"),
+    ));
+    let font = egui::FontId::monospace(13.0);
+    let char_width = painter
+        .layout_no_wrap("M".to_string(), font.clone(), egui::Color32::BLACK)
+        .size()
+        .x
+        .max(1.0);
+    let hover = ctx.pointer_hover_pos();
+    let mut tooltip = None;
+
+    for (piece, sprite, transform) in &pieces {
+        if matches!(piece, LogicPiece::Wire { .. }) {
+            continue;
+        }
+        let half = sprite.custom_size.unwrap_or(Vec2::ZERO).extend(0.0) / 2.0;
+        let corners = [-half, half]
+            .map(|c| camera.world_to_viewport(camera_transform, transform.transform_point(c)));
+        let [Ok(a), Ok(b)] = corners else { continue };
+        let rect = egui::Rect::from_two_pos(egui::pos2(a.x, a.y), egui::pos2(b.x, b.y));
+
+        let label = piece.board_label();
+        let max_chars = ((rect.width() - 8.0) / char_width).max(0.0) as usize;
+        let (text, cut) = fit_label(&label, max_chars);
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            text,
+            font.clone(),
+            egui::Color32::BLACK,
+        );
+        if let Some(pos) = hover.filter(|&p| cut && rect.contains(p)) {
+            tooltip = Some((pos, label));
+        }
+    }
+
+    if let Some((pos, label)) = tooltip {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Tooltip,
+            egui::Id::new("piece_label_tooltip"),
+        ));
+        let galley = painter.layout_no_wrap(label, font, egui::Color32::WHITE);
+        let rect = egui::Align2::LEFT_BOTTOM
+            .anchor_size(pos + egui::vec2(12.0, -4.0), galley.size())
+            .expand(4.0);
+        painter.rect_filled(rect, 3.0, egui::Color32::from_black_alpha(220));
+        painter.galley(
+            rect.min + egui::vec2(4.0, 4.0),
+            galley,
+            egui::Color32::WHITE,
+        );
+    }
+}
+
 /// Handle menu input (keyboard shortcuts)
 pub fn handle_menu_input(
     keyboard: Res<ButtonInput<KeyCode>>,
@@ -96,17 +246,28 @@ pub fn handle_menu_input(
 }
 
 /// Game HUD - shows level info, piece palette, and controls
+#[allow(clippy::too_many_arguments)]
 pub fn update_hud(
     mut contexts: EguiContexts,
     level_query: Query<&CurrentLevel>,
     stats: Res<PlayerStats>,
     mut selected: ResMut<SelectedPieceType>,
     mut next_state: ResMut<NextState<GameState>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    test_play: Option<Res<TestPlaySession>>,
+    placed: Query<&LogicPiece, With<PlayerPlaced>>,
+    mut reset_events: MessageWriter<ResetLevelEvent>,
+    live: Res<LiveCircuit>,
 ) {
-    // ESC to return to menu
-    if keyboard.just_pressed(KeyCode::Escape) {
-        next_state.set(GameState::MainMenu);
+    let testing = test_play.is_some_and(|t| t.active);
+
+    // ESC to return to menu (or back to the editor when test playing)
+    if actions.just_pressed(Action::Back) {
+        next_state.set(if testing {
+            GameState::Editor
+        } else {
+            GameState::MainMenu
+        });
         return;
     }
 
@@ -123,6 +284,10 @@ pub fn update_hud(
     // Top panel - level info
     egui::Panel::top("hud_top").show(&mut viewport_ui, |ui| {
         ui.horizontal(|ui| {
+            if testing {
+                ui.colored_label(egui::Color32::YELLOW, "TEST PLAY");
+                ui.separator();
+            }
             if let Ok(level) = level_query.single() {
                 ui.heading(&level.0.name);
                 ui.separator();
@@ -130,6 +295,22 @@ pub fn update_hud(
             }
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 ui.label(format!("Proofs: {}", stats.proofs_completed));
+                ui.separator();
+                if live.state.goal_reached() {
+                    ui.colored_label(egui::Color32::LIGHT_GREEN, "Goal reached - SPACE to verify");
+                } else {
+                    ui.label(format!(
+                        "Goals fed: {}/{}",
+                        live.state.goals_fed, live.state.goals
+                    ));
+                }
+                ui.separator();
+                let elapsed = stats.elapsed_secs();
+                ui.label(format!("Time: {}:{:02}", elapsed / 60, elapsed % 60));
+                ui.separator();
+                if ui.button("Reset level").clicked() {
+                    reset_events.write(ResetLevelEvent);
+                }
             });
         });
     });
@@ -141,53 +322,49 @@ pub fn update_hud(
             ui.heading("Pieces");
             ui.separator();
 
-            ui.label("Click to select, then click on grid to place:");
+            ui.label("Click or press 1-9 to select, then place on the grid:");
             ui.add_space(10.0);
 
-            let and_selected = selected.piece_type == Some(PlaceablePiece::AndGate);
-            #[allow(deprecated)]
-            if ui
-                .add_sized(
-                    [130.0, 40.0],
-                    egui::Button::selectable(
-                        and_selected,
-                        egui::RichText::new("AND Gate").size(16.0),
-                    ),
-                )
-                .clicked()
-            {
-                selected.piece_type = if and_selected {
-                    None
-                } else {
-                    Some(PlaceablePiece::AndGate)
-                };
-            }
-
-            let or_selected = selected.piece_type == Some(PlaceablePiece::OrGate);
-            #[allow(deprecated)]
-            if ui
-                .add_sized(
-                    [130.0, 40.0],
-                    egui::Button::selectable(
-                        or_selected,
-                        egui::RichText::new("OR Gate").size(16.0),
-                    ),
-                )
-                .clicked()
-            {
-                selected.piece_type = if or_selected {
-                    None
-                } else {
-                    Some(PlaceablePiece::OrGate)
+            // Palette generated from the level's inventory
+            let inventory = level_query
+                .single()
+                .map(|level| level.0.inventory.clone())
+                .unwrap_or_default();
+            for slot in inventory.palette() {
+                let remaining = inventory.remaining(slot.kind, placed.iter());
+                let is_selected = selected.piece_type == Some(slot.kind);
+                let label = match remaining {
+                    Some(n) => format!("{} ({})", slot.kind.name(), n),
+                    None => slot.kind.name().to_string(),
                 };
+                #[allow(deprecated)]
+                if ui
+                    .add_enabled(
+                        remaining != Some(0) || is_selected,
+                        egui::Button::selectable(
+                            is_selected,
+                            egui::RichText::new(label).size(16.0),
+                        )
+                        .min_size(egui::vec2(130.0, 40.0)),
+                    )
+                    .clicked()
+                {
+                    selected.piece_type = if is_selected { None } else { Some(slot.kind) };
+                }
             }
+            let wire_selected = selected.piece_type == Some(PlaceablePiece::Wire);
 
             ui.add_space(20.0);
             ui.separator();
 
-            if let Some(piece) = &selected.piece_type {
-                ui.label(format!("Selected: {:?}", piece));
-                ui.label("Right-click grid to place");
+            if wire_selected {
+                ui.label("Selected: Wire");
+                ui.label("Right-drag between pieces to draw,");
+                ui.label("or press Enter at each end");
+                ui.label("Right-drag a wire end to reroute");
+            } else if let Some(piece) = &selected.piece_type {
+                ui.label(format!("Selected: {}", piece.name()));
+                ui.label("Right-click or Enter to place");
             } else {
                 ui.label("No piece selected");
             }
@@ -222,9 +399,23 @@ pub fn update_hud(
             ui.separator();
             ui.label("Left Click: Select piece");
             ui.separator();
-            ui.label("Right Click: Place selected piece");
+            ui.label("Right Click / Enter: Place selected piece");
+            ui.separator();
+            ui.label("1-9 / [ ]: Choose piece");
             ui.separator();
-            ui.label("ESC: Menu");
+            ui.label("Delete: Remove selected piece");
+            ui.separator();
+            ui.label("R / Scroll: Rotate selected gate");
+            ui.separator();
+            ui.label("Ctrl+Scroll / Middle-drag: Zoom / Pan, Home: Fit");
+            ui.separator();
+            ui.label("Ctrl+Z / Ctrl+Y: Undo / Redo");
+            ui.separator();
+            ui.label(if testing {
+                "ESC: Back to editor"
+            } else {
+                "ESC: Menu"
+            });
         });
     });
 }
@@ -233,7 +424,10 @@ pub fn update_hud(
 pub fn show_completion_screen(
     mut contexts: EguiContexts,
     stats: Res<PlayerStats>,
-    level_query: Query<&CurrentLevel>,
+    solved: Option<Res<SolvedLevel>>,
+    test_play: Option<Res<TestPlaySession>>,
+    mut editor: Option<ResMut<EditorState>>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
     let Ok(ctx) = contexts.ctx_mut() else { return };
 
@@ -245,6 +439,12 @@ pub fn show_completion_screen(
             .max_rect(ctx.viewport_rect()),
     );
 
+    let test_solution = test_play
+        .as_ref()
+        .filter(|t| t.active)
+        .and_then(|t| t.solution.clone());
+    let testing = test_play.is_some_and(|t| t.active);
+
     egui::CentralPanel::default().show(&mut viewport_ui, |ui| {
         ui.vertical_centered(|ui| {
             ui.add_space(150.0);
@@ -252,8 +452,8 @@ pub fn show_completion_screen(
             ui.heading(egui::RichText::new("PROOF VERIFIED!").size(48.0).strong());
             ui.add_space(20.0);
 
-            if let Ok(level) = level_query.single() {
-                ui.label(egui::RichText::new(format!("Level: {}", level.0.name)).size(20.0));
+            if let Some(solved) = &solved {
+                ui.label(egui::RichText::new(format!("Level: {}", solved.level.name)).size(20.0));
             }
 
             ui.add_space(10.0);
@@ -278,8 +478,30 @@ pub fn show_completion_screen(
 
             ui.add_space(40.0);
 
-            ui.label("Press ENTER to continue");
-            ui.label("Press ESC for menu");
+            if testing {
+                // Offer the verified board as the level's solvability witness
+                if let (Some(solution), Some(editor)) = (test_solution, editor.as_mut()) {
+                    let saved = editor.level.witness.as_ref() == Some(&solution);
+                    if ui
+                        .add_enabled(!saved, egui::Button::new("Save solution as level witness"))
+                        .clicked()
+                    {
+                        editor.set_witness(solution);
+                    }
+                    if saved {
+                        ui.label("Witness saved to the level (save the level to keep it)");
+                    }
+                }
+                ui.add_space(10.0);
+                if ui.button("Back to editor").clicked() {
+                    next_state.set(GameState::Editor);
+                }
+                ui.label("Press ENTER to test again");
+                ui.label("Press ESC to return to the editor");
+            } else {
+                ui.label("Press ENTER to continue");
+                ui.label("Press ESC for menu");
+            }
         });
     });
 }
@@ -288,12 +510,22 @@ pub fn show_completion_screen(
 pub fn handle_completion_input(
     keyboard: Res<ButtonInput<KeyCode>>,
     mut next_state: ResMut<NextState<GameState>>,
+    test_play: Option<Res<TestPlaySession>>,
 ) {
     if keyboard.just_pressed(KeyCode::Enter) || keyboard.just_pressed(KeyCode::Space) {
         // Go to next level (for now, replay)
         next_state.set(GameState::Playing);
     }
     if keyboard.just_pressed(KeyCode::Escape) {
-        next_state.set(GameState::MainMenu);
+        if test_play.is_some_and(|t| t.active) {
+            next_state.set(GameState::Editor);
+        } else {
+            next_state.set(GameState::MainMenu);
+        }
     }
 }
+
+/// Format seconds as minutes and seconds
+fn format_duration(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::game::{BoardState, Level, LogicPiece};
+use crate::game::graph::ConnectionGraph;
+use crate::game::signal;
+use crate::game::{BoardState, GoalCondition, Level, LogicPiece};
 
 #[cfg(feature = "z3-verify")]
 pub mod z3_integration;
@@ -16,6 +18,9 @@ pub struct ExportedProof {
     pub proof_isabelle: Option<String>,
     pub solution_steps: Vec<String>,
     pub time_taken_secs: u64,
+    /// Every piece on the solved board, so the proof can be checked again
+    #[serde(default)]
+    pub solution: Vec<LogicPiece>,
 }
 
 impl ExportedProof {
@@ -27,8 +32,36 @@ pub fn from_level(level: &Level, solution_time: u64) -> Self {
             proof_isabelle: None,
             solution_steps: vec![],
             time_taken_secs: solution_time,
+            solution: vec![],
         }
     }
+
+    /// A proof of `level` by the pieces on the solved board, with the
+    /// proof obligations they discharge as its SMT-LIB2
+    pub fn from_solution(level: &Level, pieces: &[LogicPiece], solution_time: u64) -> Self {
+        Self {
+            proof_smt2: solution_to_smt(level, pieces)
+                .unwrap_or_else(|| board_to_smt(&level.initial_state)),
+            solution: pieces.to_vec(),
+            ..Self::from_level(level, solution_time)
+        }
+    }
+}
+
+/// SMT-LIB2 certificate for a solution: each goal's proof obligations,
+/// which are all unsatisfiable when the goal is proved. `None` if some goal
+/// is not fed by a complete circuit.
+pub fn solution_to_smt(level: &Level, pieces: &[LogicPiece]) -> Option<String> {
+    let mut smt = String::from("; Proof of Work - Generated Proof\n");
+    for obligation in proof_obligations(level, pieces)? {
+        let (x, y) = obligation.goal;
+        for script in obligation.to_smt(level) {
+            smt.push_str(&format!("; Goal at ({}, {})\n(push)\n", x, y));
+            smt.push_str(&script);
+            smt.push_str("(check-sat)\n(pop)\n");
+        }
+    }
+    Some(smt)
 }
 
 /// Convert board state to SMT-LIB2 format
@@ -38,26 +71,20 @@ pub fn board_to_smt(board: &BoardState) -> String {
 
     // Declare boolean constants for each formula
     let mut formulas: Vec<String> = Vec::new();
-    for piece in &board.pieces {
+    for piece in board.pieces() {
         match piece {
-            LogicPiece::Assumption { formula, .. } => {
-                if !formulas.contains(formula) {
-                    smt.push_str(&format!("(declare-const {} Bool)\n", formula));
-                    formulas.push(formula.clone());
-                }
-            }
-            LogicPiece::Goal { formula, .. } => {
-                if !formulas.contains(formula) {
-                    smt.push_str(&format!("(declare-const {} Bool)\n", formula));
-                    formulas.push(formula.clone());
-                }
+            LogicPiece::Assumption { formula, .. } | LogicPiece::Goal { formula, .. }
+                if !formulas.contains(formula) =>
+            {
+                smt.push_str(&format!("(declare-const {} Bool)\n", formula));
+                formulas.push(formula.clone());
             }
             _ => {}
         }
     }
 
     // Assert assumptions
-    for piece in &board.pieces {
+    for piece in board.pieces() {
         if let LogicPiece::Assumption { formula, .. } = piece {
             smt.push_str(&format!("(assert {})\n", formula));
         }
@@ -67,11 +94,110 @@ pub fn board_to_smt(board: &BoardState) -> String {
     smt
 }
 
-/// Check if two positions are adjacent (within 2 grid units)
-fn is_adjacent(a: (u32, u32), b: (u32, u32)) -> bool {
-    let dx = (a.0 as i32 - b.0 as i32).abs();
-    let dy = (a.1 as i32 - b.1 as i32).abs();
-    dx <= 2 && dy <= 2 && (dx + dy) > 0
+/// What a solution claims for one goal: the formula its circuit carries
+/// into the goal's input port, the formula the goal needs, and the
+/// assumptions the circuit starts from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofObligation {
+    pub goal: (u32, u32),
+    /// SMT-LIB2 formulas of the assumption pieces that feed the goal
+    pub hypotheses: Vec<String>,
+    /// SMT-LIB2 term built by following the circuit back to assumptions
+    pub carried: String,
+    /// SMT-LIB2 term the goal must follow from
+    pub target: String,
+}
+
+impl ProofObligation {
+    /// The SMT-LIB2 scripts that must both be unsatisfiable for the goal
+    /// to be proved: [`Self::derivation_smt`], then
+    /// [`Self::entailment_smt`].
+    pub fn to_smt(&self, level: &Level) -> [String; 2] {
+        [self.derivation_smt(), self.entailment_smt(level)]
+    }
+
+    /// Unsatisfiable exactly when the hypotheses entail the carried
+    /// formula, i.e. the circuit really derives what it carries. A circuit
+    /// carrying a contradiction, like `(and P (not P))`, fails here.
+    pub fn derivation_smt(&self) -> String {
+        let texts: Vec<&String> = self.hypotheses.iter().chain([&self.carried]).collect();
+        let mut smt = declarations(&texts);
+        for hypothesis in &self.hypotheses {
+            smt.push_str(&format!("(assert {})\n", hypothesis));
+        }
+        smt.push_str(&format!("(assert (not {}))\n", self.carried));
+        smt
+    }
+
+    /// Unsatisfiable exactly when the level's theorem and the carried
+    /// formula entail the target, i.e. the circuit's formula is what gives
+    /// the goal. The hypotheses are left out, so wiring the right
+    /// assumptions through the wrong gate proves nothing.
+    pub fn entailment_smt(&self, level: &Level) -> String {
+        let mut smt = declarations(&[&level.theorem, &self.carried, &self.target]);
+        smt.push_str(&level.theorem);
+        smt.push_str(&format!(
+            "\n(assert {})\n(assert (not {}))\n",
+            self.carried, self.target
+        ));
+        smt
+    }
+}
+
+/// A Bool declaration for each atom of `texts`, in order of appearance.
+fn declarations(texts: &[&String]) -> String {
+    let mut atoms: Vec<&str> = Vec::new();
+    for text in texts {
+        for atom in crate::game::validation::formula_atoms(text) {
+            if !atoms.contains(&atom) {
+                atoms.push(atom);
+            }
+        }
+    }
+    atoms
+        .iter()
+        .map(|atom| format!("(declare-const {} Bool)\n", atom))
+        .collect()
+}
+
+/// Read the proof a solution's circuit builds for each goal, using port
+/// semantics: an AND gate carries `(and left right)` and needs both
+/// inputs, OR carries the disjunction of whichever inputs are fed, IMPLIES
+/// carries `(=> antecedent consequent)`, NOT carries `(not in)`, and
+/// quantifier pieces pass their body through (see [`signal::derive`]).
+/// Goals prove the level's `ProveFormula` goal when it has one, else their
+/// own formula.
+///
+/// Returns `None` if there are no goals or some goal is not fed by a
+/// complete circuit.
+pub fn proof_obligations(level: &Level, pieces: &[LogicPiece]) -> Option<Vec<ProofObligation>> {
+    let graph = ConnectionGraph::from_pieces(pieces);
+    let derived = signal::derive(&graph);
+    let mut obligations = Vec::new();
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let LogicPiece::Goal { formula, position } = node else {
+            continue;
+        };
+        let target = match &level.goal_state {
+            GoalCondition::ProveFormula { formula } => formula.clone(),
+            _ => formula.clone(),
+        };
+        let mut hypotheses: Vec<String> = Vec::new();
+        for (j, feeds) in graph.feeding(i).into_iter().enumerate() {
+            if let LogicPiece::Assumption { formula, .. } = graph.nodes[j] {
+                if feeds && !hypotheses.contains(formula) {
+                    hypotheses.push(formula.clone());
+                }
+            }
+        }
+        obligations.push(ProofObligation {
+            goal: *position,
+            hypotheses,
+            carried: signal::arriving(&graph, &derived, i, "in")?,
+            target,
+        });
+    }
+    (!obligations.is_empty()).then_some(obligations)
 }
 
 /// Outcome of `verify_level_solution`. Tri-valued so the mock (no-Z3) path
@@ -95,106 +221,97 @@ pub enum VerificationVerdict {
     CannotVerify,
 }
 
-/// Verify that the puzzle solution is correct
-/// For the vertical slice: check if pieces form a valid proof
-//
-// PROOF-OBLIGATION I1 (OWED): verification soundness — a `Verified` verdict
-// must imply the existence of a `VerifiedSolution` certificate (adjacency
-// witness + SMT entailment). This function returns a verdict; the certificate
-// is not constructed/returned. The refinement obligation is to surface the
-// certificate type the seam already defines.
-// See: src/abi/ProofOfWork/ABI/Invariants.idr I1
-#[cfg(feature = "z3-verify")]
-pub fn verify_level_solution(_level: &Level, pieces: &[LogicPiece]) -> VerificationVerdict {
-    use z3::ast::Bool;
-    use z3::Solver;
-
-    let solver = Solver::new();
+/// Which verifier checks solutions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum VerifierBackend {
+    /// The Z3 SMT solver, when the build includes it
+    #[default]
+    Z3,
+    /// No verifier: every solution is reported as unverifiable
+    Disabled,
+}
 
-    // Collect assumptions and goals
-    let mut assumptions: Vec<(&str, (u32, u32))> = Vec::new();
-    let mut goals: Vec<(&str, (u32, u32))> = Vec::new();
-    let mut and_gates: Vec<(u32, u32)> = Vec::new();
-    let mut or_gates: Vec<(u32, u32)> = Vec::new();
+impl VerifierBackend {
+    pub const ALL: [VerifierBackend; 2] = [VerifierBackend::Z3, VerifierBackend::Disabled];
 
-    for piece in pieces {
-        match piece {
-            LogicPiece::Assumption { formula, position } => {
-                assumptions.push((formula, *position));
-            }
-            LogicPiece::Goal { formula, position } => {
-                goals.push((formula, *position));
-            }
-            LogicPiece::AndIntro { position } => {
-                and_gates.push(*position);
-            }
-            LogicPiece::OrIntro { position } => {
-                or_gates.push(*position);
-            }
-            _ => {}
+    pub fn name(&self) -> &'static str {
+        match self {
+            VerifierBackend::Z3 => "Z3",
+            VerifierBackend::Disabled => "Disabled",
         }
     }
+}
 
-    // For the "P AND Q => R" puzzle:
-    // Need an AND gate that connects P and Q, and that gate connects to R
-    for and_pos in &and_gates {
-        let mut p_connected = false;
-        let mut q_connected = false;
-        let mut goal_connected = false;
-
-        for (formula, pos) in &assumptions {
-            if is_adjacent(*pos, *and_pos) {
-                if *formula == "P" {
-                    p_connected = true;
-                }
-                if *formula == "Q" {
-                    q_connected = true;
-                }
-            }
-        }
+/// How solutions are verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct VerifierOptions {
+    pub backend: VerifierBackend,
+    /// Time the solver may spend on each proof obligation, in milliseconds.
+    /// An obligation that times out is rejected.
+    pub timeout_ms: u32,
+}
 
-        for (_formula, pos) in &goals {
-            if is_adjacent(*and_pos, *pos) {
-                goal_connected = true;
-            }
+impl Default for VerifierOptions {
+    fn default() -> Self {
+        Self {
+            backend: VerifierBackend::Z3,
+            timeout_ms: 5000,
         }
+    }
+}
 
-        // If AND gate connects P, Q, and R - verify with Z3
-        if p_connected && q_connected && goal_connected {
-            // Create Z3 proof
-            let p = Bool::new_const("P");
-            let q = Bool::new_const("Q");
-            let r = Bool::new_const("R");
-
-            // Assert P and Q are true (assumptions)
-            solver.assert(&p);
-            solver.assert(&q);
-
-            // We want to prove R, given (P AND Q) => R
-            // Assert the implication as an axiom
-            let p_and_q = Bool::and(&[&p, &q]);
-            let implication = p_and_q.implies(&r);
-            solver.assert(&implication);
-
-            // Try to prove R is true
-            // We check if NOT R leads to UNSAT
-            solver.push();
-            solver.assert(&r.not());
-
-            match solver.check() {
-                z3::SatResult::Unsat => {
-                    // R must be true! Proof verified.
-                    return VerificationVerdict::Verified;
-                }
-                _ => {
-                    solver.pop(1);
-                }
-            }
-        }
+/// Verify that the puzzle solution is correct with the chosen backend
+pub fn verify_level_solution(
+    level: &Level,
+    pieces: &[LogicPiece],
+    options: &VerifierOptions,
+) -> VerificationVerdict {
+    match options.backend {
+        VerifierBackend::Z3 => verify_with_z3(level, pieces, options.timeout_ms),
+        VerifierBackend::Disabled => VerificationVerdict::CannotVerify,
     }
+}
+
+/// Check that the pieces form a valid proof of the level's goal
+//
+// PROOF-OBLIGATION I1 (OWED): verification soundness — a `Verified` verdict
+// must imply the existence of a `VerifiedSolution` certificate: for every
+// goal, port paths from the assumptions it uses, the SMT entailment of the
+// carried formula from those assumptions, and of the target from the
+// theorem and the carried formula. This function returns a verdict; the certificate is not
+// constructed/returned. The refinement obligation is to surface the
+// certificate type the seam already defines.
+// See: src/abi/ProofOfWork/ABI/Invariants.idr I1
+#[cfg(feature = "z3-verify")]
+fn verify_with_z3(level: &Level, pieces: &[LogicPiece], timeout_ms: u32) -> VerificationVerdict {
+    use z3::{Params, Solver};
 
-    // No valid configuration found
-    VerificationVerdict::Rejected
+    // Solutions that tamper with locked pieces or build on obstacles are
+    // rejected before any SMT work
+    if !crate::game::validation::check_level_rules(level, pieces).is_empty() {
+        return VerificationVerdict::Rejected;
+    }
+
+    // Every goal must be fed by a complete circuit...
+    let Some(obligations) = proof_obligations(level, pieces) else {
+        return VerificationVerdict::Rejected;
+    };
+
+    // ...whose formula follows from the assumptions feeding the goal, and
+    // with the level's theorem gives the goal: each negation must be
+    // unsatisfiable
+    for script in obligations.iter().flat_map(|o| o.to_smt(level)) {
+        let solver = Solver::new();
+        let mut params = Params::new();
+        params.set_u32("timeout", timeout_ms);
+        solver.set_params(&params);
+        solver.from_string(script);
+        if solver.check() != z3::SatResult::Unsat {
+            return VerificationVerdict::Rejected;
+        }
+    }
+    VerificationVerdict::Verified
 }
 
 /// Mock verification stub when Z3 is not available.
@@ -211,7 +328,7 @@ pub fn verify_level_solution(_level: &Level, pieces: &[LogicPiece]) -> Verificat
 // Callers must handle [CannotVerify] as a non-winning verdict and surface
 // the demo-build status to the player.
 #[cfg(not(feature = "z3-verify"))]
-pub fn verify_level_solution(_level: &Level, _pieces: &[LogicPiece]) -> VerificationVerdict {
+fn verify_with_z3(_level: &Level, _pieces: &[LogicPiece], _timeout_ms: u32) -> VerificationVerdict {
     tracing::warn!(
         "Verification skipped: this is a demo build (no `z3-verify` feature). \
          Rebuild with `cargo run --features z3-verify` to verify solutions."
@@ -222,14 +339,7 @@ pub fn verify_level_solution(_level: &Level, _pieces: &[LogicPiece]) -> Verifica
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn test_adjacency() {
-        assert!(is_adjacent((2, 5), (3, 5))); // Same row, adjacent
-        assert!(is_adjacent((2, 5), (4, 5))); // Same row, 2 apart
-        assert!(!is_adjacent((2, 5), (5, 5))); // Too far
-        assert!(is_adjacent((2, 5), (3, 6))); // Diagonal
-    }
+    use crate::game::Direction;
 
     fn test_level() -> Level {
         Level {
@@ -237,14 +347,12 @@ fn test_level() -> Level {
             name: "Test".to_string(),
             description: "Test level".to_string(),
             theorem: "(assert (=> (and P Q) R))".to_string(),
-            initial_state: BoardState {
-                width: 10,
-                height: 10,
-                pieces: vec![],
-            },
+            initial_state: BoardState::new(10, 10),
             goal_state: crate::game::GoalCondition::ProveFormula {
                 formula: "R".to_string(),
             },
+            witness: None,
+            inventory: Default::default(),
         }
     }
 
@@ -252,17 +360,20 @@ fn pieces_disconnected() -> Vec<LogicPiece> {
         vec![
             LogicPiece::Assumption {
                 formula: "P".to_string(),
-                position: (2, 5),
+                position: (3, 5),
             },
             LogicPiece::Assumption {
                 formula: "Q".to_string(),
-                position: (2, 3),
+                position: (3, 4),
             },
             LogicPiece::Goal {
                 formula: "R".to_string(),
                 position: (8, 4),
             },
-            LogicPiece::AndIntro { position: (4, 4) }, // Adjacent to P, Q, but not R (8,4)
+            LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            }, // Fed by P and Q, but feeds nothing
         ]
     }
 
@@ -270,33 +381,174 @@ fn pieces_valid() -> Vec<LogicPiece> {
         vec![
             LogicPiece::Assumption {
                 formula: "P".to_string(),
-                position: (2, 5),
+                position: (3, 5),
             },
             LogicPiece::Assumption {
                 formula: "Q".to_string(),
-                position: (2, 3),
+                position: (3, 4),
             },
             LogicPiece::Goal {
                 formula: "R".to_string(),
                 position: (5, 4),
             },
-            LogicPiece::AndIntro { position: (3, 4) }, // Adjacent to P(2,5), Q(2,3), and R(5,4)
+            // P and Q face its inputs, its output faces R's input
+            LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            },
         ]
     }
 
+    #[test]
+    fn test_proof_obligations_follow_ports() {
+        let level = test_level();
+        assert_eq!(proof_obligations(&level, &pieces_disconnected()), None);
+        let obligations = proof_obligations(&level, &pieces_valid()).unwrap();
+        assert_eq!(
+            obligations,
+            vec![ProofObligation {
+                goal: (5, 4),
+                hypotheses: vec!["P".to_string(), "Q".to_string()],
+                carried: "(and P Q)".to_string(),
+                target: "R".to_string(),
+            }]
+        );
+        assert_eq!(
+            obligations[0].to_smt(&level),
+            [
+                "(declare-const P Bool)\n(declare-const Q Bool)\n\
+                 (assert P)\n(assert Q)\n(assert (not (and P Q)))\n"
+                    .to_string(),
+                "(declare-const P Bool)\n(declare-const Q Bool)\n(declare-const R Bool)\n\
+                 (assert (=> (and P Q) R))\n(assert (and P Q))\n(assert (not R))\n"
+                    .to_string(),
+            ]
+        );
+
+        // An OR fed on one side carries that side
+        let mut pieces = pieces_valid();
+        pieces[3] = LogicPiece::OrIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        pieces.remove(1);
+        assert_eq!(
+            proof_obligations(&level, &pieces).unwrap()[0].carried,
+            "P".to_string()
+        );
+    }
+
+    /// P with its own negation, `(and P (not P))`: the circuit derives
+    /// nothing from P, so the contradiction it carries is never assumed
+    fn pieces_contradiction() -> Vec<LogicPiece> {
+        vec![
+            LogicPiece::Assumption {
+                formula: "P".to_string(),
+                position: (3, 5),
+            },
+            LogicPiece::Assumption {
+                formula: "P".to_string(),
+                position: (2, 4),
+            },
+            LogicPiece::NotIntro {
+                position: (3, 4),
+                orientation: Direction::East,
+            },
+            LogicPiece::AndIntro {
+                position: (4, 4),
+                orientation: Direction::East,
+            },
+            LogicPiece::Goal {
+                formula: "R".to_string(),
+                position: (5, 4),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_carried_formula_is_not_assumed() {
+        let level = test_level();
+        let obligations = proof_obligations(&level, &pieces_contradiction()).unwrap();
+        assert_eq!(obligations[0].carried, "(and P (not P))");
+        assert_eq!(obligations[0].hypotheses, vec!["P".to_string()]);
+        let [derivation, entailment] = obligations[0].to_smt(&level);
+        assert!(derivation.ends_with("(assert P)\n(assert (not (and P (not P))))\n"));
+        assert!(!entailment.contains("(assert P)"));
+    }
+
+    #[test]
+    fn test_hypotheses_are_not_assumed_for_the_goal() {
+        // Both assumptions through an OR: derivable, but the theorem needs
+        // both, so only the goal check can tell it apart from the AND
+        let level = test_level();
+        let mut pieces = pieces_valid();
+        pieces[3] = LogicPiece::OrIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        let obligation = &proof_obligations(&level, &pieces).unwrap()[0];
+        assert_eq!(obligation.carried, "(or P Q)");
+        assert_eq!(
+            obligation.entailment_smt(&level),
+            "(declare-const P Bool)\n(declare-const Q Bool)\n(declare-const R Bool)\n\
+             (assert (=> (and P Q) R))\n(assert (or P Q))\n(assert (not R))\n"
+        );
+    }
+
     /// Under `--features z3-verify`, the Z3 path discriminates Verified
-    /// vs Rejected based on adjacency + SMT entailment.
+    /// vs Rejected based on the port circuit + SMT entailment.
     #[cfg(feature = "z3-verify")]
     #[test]
     fn test_verification_z3() {
         assert_eq!(
-            verify_level_solution(&test_level(), &pieces_disconnected()),
+            verify_level_solution(
+                &test_level(),
+                &pieces_disconnected(),
+                &VerifierOptions::default()
+            ),
             VerificationVerdict::Rejected
         );
         assert_eq!(
-            verify_level_solution(&test_level(), &pieces_valid()),
+            verify_level_solution(&test_level(), &pieces_valid(), &VerifierOptions::default()),
             VerificationVerdict::Verified
         );
+        let mut pieces = pieces_valid();
+        pieces[3] = LogicPiece::OrIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        };
+        assert_eq!(
+            verify_level_solution(&test_level(), &pieces, &VerifierOptions::default()),
+            VerificationVerdict::Rejected
+        );
+    }
+
+    /// A circuit that carries a contradiction into the goal proves nothing
+    #[cfg(feature = "z3-verify")]
+    #[test]
+    fn test_verification_rejects_contradiction() {
+        assert_eq!(
+            verify_level_solution(
+                &test_level(),
+                &pieces_contradiction(),
+                &VerifierOptions::default()
+            ),
+            VerificationVerdict::Rejected
+        );
+    }
+
+    /// Level rules are checked before the SMT step: a solution built on an
+    /// obstacle is rejected even if it would otherwise verify.
+    #[cfg(feature = "z3-verify")]
+    #[test]
+    fn test_verification_respects_obstacles() {
+        let mut level = test_level();
+        // Under the upper cell of the AND gate
+        level.initial_state.set_obstacle((4, 5), true);
+        assert_eq!(
+            verify_level_solution(&level, &pieces_valid(), &VerifierOptions::default()),
+            VerificationVerdict::Rejected
+        );
     }
 
     /// Without `z3-verify`, the mock returns `CannotVerify` for every
@@ -308,11 +560,27 @@ fn test_verification_z3() {
     #[test]
     fn test_mock_never_accepts() {
         assert_eq!(
-            verify_level_solution(&test_level(), &pieces_disconnected()),
+            verify_level_solution(
+                &test_level(),
+                &pieces_disconnected(),
+                &VerifierOptions::default()
+            ),
+            VerificationVerdict::CannotVerify
+        );
+        assert_eq!(
+            verify_level_solution(&test_level(), &pieces_valid(), &VerifierOptions::default()),
             VerificationVerdict::CannotVerify
         );
+    }
+
+    #[test]
+    fn test_disabled_backend_never_accepts() {
+        let options = VerifierOptions {
+            backend: VerifierBackend::Disabled,
+            ..Default::default()
+        };
         assert_eq!(
-            verify_level_solution(&test_level(), &pieces_valid()),
+            verify_level_solution(&test_level(), &pieces_valid(), &options),
             VerificationVerdict::CannotVerify
         );
     }
@@ -8,7 +8,7 @@
 //! - Performance: large boards, many pieces
 //! - Internationalization: Unicode and special characters
 
-use proof_of_work::{BoardState, LogicPiece};
+use proof_of_work::{BoardState, Direction, LogicPiece};
 
 // ============================================================================
 // Security: Input Validation
@@ -74,14 +74,14 @@ fn aspect_html_injection_in_formula() {
         formula: stored, ..
     }) = board.piece_at(2, 2)
     {
-        assert_eq!(stored, formula);
+        assert_eq!(stored, &formula);
     }
 }
 
 #[test]
 fn aspect_special_characters_in_formula() {
     // Special characters should be preserved
-    let formulas = vec![
+    let formulas = [
         "P∧Q∨R".to_string(),
         "¬P→Q".to_string(),
         "∀x.P(x)".to_string(),
@@ -108,7 +108,10 @@ fn aspect_special_characters_in_formula() {
 fn aspect_piece_at_origin() {
     let mut board = BoardState::new(5, 5);
 
-    let piece = LogicPiece::AndIntro { position: (0, 0) };
+    let piece = LogicPiece::AndIntro {
+        position: (0, 0),
+        orientation: Direction::East,
+    };
     assert!(board.place_piece(piece));
 
     assert!(board.is_occupied(0, 0));
@@ -121,8 +124,16 @@ fn aspect_piece_at_max_coordinates() {
     let height = 100u32;
     let mut board = BoardState::new(width, height);
 
-    let piece = LogicPiece::OrIntro {
+    // A two-cell gate anchored on the top row would stick out
+    let overhanging = LogicPiece::OrIntro {
         position: (width - 1, height - 1),
+        orientation: Direction::East,
+    };
+    assert!(!board.place_piece(overhanging));
+
+    let piece = LogicPiece::OrIntro {
+        position: (width - 1, height - 2),
+        orientation: Direction::East,
     };
     assert!(board.place_piece(piece));
 
@@ -139,6 +150,7 @@ fn aspect_piece_just_outside_bounds() {
     // Try to place at width (out of bounds)
     let piece = LogicPiece::AndIntro {
         position: (width, height),
+        orientation: Direction::East,
     };
     let placed = board.place_piece(piece);
 
@@ -151,15 +163,24 @@ fn aspect_1x1_board_edge_case() {
     let mut board = BoardState::new(1, 1);
 
     // Only position (0, 0) exists
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (0, 0) }));
+    assert!(board.place_piece(LogicPiece::NotIntro {
+        position: (0, 0),
+        orientation: Direction::East
+    }));
     assert_eq!(board.piece_count(), 1);
 
     // Cannot place another
-    let result = board.place_piece(LogicPiece::OrIntro { position: (0, 0) });
+    let result = board.place_piece(LogicPiece::NotIntro {
+        position: (0, 0),
+        orientation: Direction::East,
+    });
     assert!(!result, "Position occupied");
 
     // Cannot place at (1, 0) or (0, 1)
-    let result2 = board.place_piece(LogicPiece::AndIntro { position: (1, 0) });
+    let result2 = board.place_piece(LogicPiece::AndIntro {
+        position: (1, 0),
+        orientation: Direction::East,
+    });
     assert!(!result2, "Out of bounds");
 }
 
@@ -169,6 +190,7 @@ fn aspect_max_u32_coordinates_out_of_bounds() {
 
     let piece = LogicPiece::AndIntro {
         position: (u32::MAX, u32::MAX),
+        orientation: Direction::East,
     };
     let placed = board.place_piece(piece);
 
@@ -206,8 +228,14 @@ fn aspect_move_to_occupied_position_no_panic() {
     let mut board = BoardState::new(10, 10);
 
     // Place two pieces
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (3, 3) }));
-    assert!(board.place_piece(LogicPiece::OrIntro { position: (5, 5) }));
+    assert!(board.place_piece(LogicPiece::AndIntro {
+        position: (3, 3),
+        orientation: Direction::East
+    }));
+    assert!(board.place_piece(LogicPiece::OrIntro {
+        position: (5, 5),
+        orientation: Direction::East
+    }));
 
     // Try to move first piece to second's location
     let moved = board.move_piece((3, 3), (5, 5));
@@ -234,11 +262,17 @@ fn aspect_query_empty_board_no_panic() {
 fn aspect_repeated_place_same_position_idempotent() {
     let mut board = BoardState::new(10, 10);
 
-    let piece1 = LogicPiece::AndIntro { position: (5, 5) };
+    let piece1 = LogicPiece::AndIntro {
+        position: (5, 5),
+        orientation: Direction::East,
+    };
     assert!(board.place_piece(piece1));
 
     // Try to place again at same position
-    let piece2 = LogicPiece::AndIntro { position: (5, 5) };
+    let piece2 = LogicPiece::AndIntro {
+        position: (5, 5),
+        orientation: Direction::East,
+    };
     let result = board.place_piece(piece2);
 
     assert!(!result, "Second placement should fail");
@@ -265,14 +299,28 @@ fn aspect_large_board_with_1000_pieces() {
     // Place 1000 pieces (board can fit 10000, so well within capacity)
     let mut placed_count = 0;
     for i in 0..1000 {
-        let x = (i % 100) as u32;
-        let y = (i / 100) as u32;
+        // A 10x10 grid, ten cells apart so two-cell gates stay clear;
+        // each grid cell is tried ten times
+        let x = (i % 10) as u32 * 10;
+        let y = (i / 10 % 10) as u32 * 10;
 
         let piece = match i % 4 {
-            0 => LogicPiece::AndIntro { position: (x, y) },
-            1 => LogicPiece::OrIntro { position: (x, y) },
-            2 => LogicPiece::NotIntro { position: (x, y) },
-            _ => LogicPiece::ImpliesIntro { position: (x, y) },
+            0 => LogicPiece::AndIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+            1 => LogicPiece::OrIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+            2 => LogicPiece::NotIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+            _ => LogicPiece::ImpliesIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
         };
 
         if board.place_piece(piece) {
@@ -287,13 +335,53 @@ fn aspect_large_board_with_1000_pieces() {
     assert_eq!(board.piece_count(), placed_count);
 }
 
+#[test]
+fn aspect_large_board_with_1000_two_cell_gates() {
+    let mut board = BoardState::new(100, 100);
+
+    // Place 1000 pieces, each on a free cell
+    let mut placed_count = 0;
+    for i in 0..1000 {
+        // Every other row, so two-cell gates don't reach the next one
+        let x = (i % 100) as u32;
+        let y = (i / 100) as u32 * 2;
+
+        let piece = match i % 4 {
+            0 => LogicPiece::AndIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+            1 => LogicPiece::OrIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+            2 => LogicPiece::NotIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+            _ => LogicPiece::ImpliesIntro {
+                position: (x, y),
+                orientation: Direction::East,
+            },
+        };
+
+        if board.place_piece(piece) {
+            placed_count += 1;
+        }
+    }
+
+    assert_eq!(placed_count, 1000, "Should place all 1000 pieces");
+    assert_eq!(board.piece_count(), placed_count);
+}
+
 #[test]
 fn aspect_spatial_query_on_large_board() {
     let mut board = BoardState::new(200, 200);
 
     // Place piece in center
     assert!(board.place_piece(LogicPiece::AndIntro {
-        position: (100, 100)
+        position: (100, 100),
+        orientation: Direction::East,
     }));
 
     // Query with small radius
@@ -317,7 +405,10 @@ fn aspect_remove_multiple_pieces_performance() {
     for i in 0..100 {
         let x = (i % 50) as u32;
         let y = (i / 50) as u32;
-        let piece = LogicPiece::AndIntro { position: (x, y) };
+        let piece = LogicPiece::NotIntro {
+            position: (x, y),
+            orientation: Direction::East,
+        };
         let _ = board.place_piece(piece);
     }
 
@@ -353,7 +444,7 @@ fn aspect_chinese_characters_in_formula() {
         formula: stored, ..
     }) = board.piece_at(1, 1)
     {
-        assert_eq!(stored, formula);
+        assert_eq!(stored, &formula);
     }
 }
 
@@ -373,7 +464,7 @@ fn aspect_arabic_characters_in_formula() {
         formula: stored, ..
     }) = board.piece_at(2, 2)
     {
-        assert_eq!(stored, formula);
+        assert_eq!(stored, &formula);
     }
 }
 
@@ -393,7 +484,7 @@ fn aspect_emoji_in_formula() {
         formula: stored, ..
     }) = board.piece_at(3, 3)
     {
-        assert_eq!(stored, formula);
+        assert_eq!(stored, &formula);
     }
 }
 
@@ -413,14 +504,14 @@ fn aspect_mixed_scripts_in_formula() {
         formula: stored, ..
     }) = board.piece_at(4, 4)
     {
-        assert_eq!(stored, formula);
+        assert_eq!(stored, &formula);
     }
 }
 
 #[test]
 fn aspect_whitespace_variants_in_formula() {
     // Test various whitespace characters
-    let formulas = vec![
+    let formulas = [
         "P Q".to_string(),        // Regular space
         "P\tQ".to_string(),       // Tab
         "P\nQ".to_string(),       // Newline
@@ -457,21 +548,36 @@ fn aspect_all_piece_variants_placeable() {
             formula: "Q".into(),
             position: (2, 2),
         },
-        LogicPiece::AndIntro { position: (3, 3) },
-        LogicPiece::OrIntro { position: (4, 4) },
-        LogicPiece::ImpliesIntro { position: (5, 5) },
-        LogicPiece::NotIntro { position: (6, 6) },
+        LogicPiece::AndIntro {
+            position: (3, 3),
+            orientation: Direction::East,
+        },
+        LogicPiece::OrIntro {
+            position: (4, 4),
+            orientation: Direction::East,
+        },
+        LogicPiece::ImpliesIntro {
+            position: (5, 5),
+            orientation: Direction::East,
+        },
+        LogicPiece::NotIntro {
+            position: (6, 6),
+            orientation: Direction::East,
+        },
         LogicPiece::ForallIntro {
             position: (7, 7),
             variable: "x".into(),
+            orientation: Direction::East,
         },
         LogicPiece::ExistsIntro {
             position: (8, 8),
             variable: "y".into(),
+            orientation: Direction::East,
         },
+        // A wire covers its `from` cell, so it starts off the other pieces
         LogicPiece::Wire {
-            from: (1, 1),
-            to: (3, 3),
+            from: (10, 1),
+            to: (12, 3),
         },
     ];
 
@@ -498,9 +604,8 @@ fn aspect_wire_pieces_with_same_endpoints() {
     // Both wires have same endpoints but are different piece instances
     assert!(board.place_piece(wire1));
 
-    // Try to place second wire - should fail (same endpoints = same logical position)
+    // Try to place second wire - should fail (a wire covers its `from` cell)
     let result = board.place_piece(wire2);
-    // Note: Depending on implementation, this might succeed (wires can coexist)
-    // or fail (logical uniqueness). We just verify no panic.
-    assert!(board.piece_count() >= 1);
+    assert!(!result, "Second wire starts on the first");
+    assert_eq!(board.piece_count(), 1);
 }
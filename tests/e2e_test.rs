@@ -8,7 +8,7 @@
 //! - SMT-LIB2 proof export
 //! - Verification of valid and invalid proofs
 
-use proof_of_work::{BoardState, GoalCondition, Level, LogicPiece};
+use proof_of_work::{BoardState, Direction, GoalCondition, Level, LogicPiece};
 
 // ============================================================================
 // Full Puzzle Flow Tests
@@ -72,7 +72,10 @@ fn e2e_puzzle_with_logic_gates() {
     assert!(board.place_piece(q));
 
     // Place AND gate
-    let and_gate = LogicPiece::AndIntro { position: (5, 6) };
+    let and_gate = LogicPiece::AndIntro {
+        position: (5, 6),
+        orientation: Direction::East,
+    };
     assert!(board.place_piece(and_gate));
 
     // Place goal
@@ -94,7 +97,10 @@ fn e2e_puzzle_with_logic_gates() {
 fn e2e_place_and_remove_pieces() {
     let mut board = BoardState::new(10, 10);
 
-    let piece = LogicPiece::AndIntro { position: (5, 5) };
+    let piece = LogicPiece::AndIntro {
+        position: (5, 5),
+        orientation: Direction::East,
+    };
     assert!(board.place_piece(piece));
     assert_eq!(board.piece_count(), 1);
 
@@ -117,7 +123,10 @@ fn e2e_move_piece_on_board() {
     let mut board = BoardState::new(15, 15);
 
     // Place piece at (5, 5)
-    let piece = LogicPiece::AndIntro { position: (5, 5) };
+    let piece = LogicPiece::AndIntro {
+        position: (5, 5),
+        orientation: Direction::East,
+    };
     assert!(board.place_piece(piece));
     assert!(board.is_occupied(5, 5));
 
@@ -147,6 +156,8 @@ fn e2e_create_level_with_initial_state() {
         goal_state: GoalCondition::ProveFormula {
             formula: "Q".into(),
         },
+        witness: None,
+        inventory: Default::default(),
     };
 
     assert_eq!(level.id, 1);
@@ -175,6 +186,8 @@ fn e2e_level_with_pieces() {
         goal_state: GoalCondition::ProveFormula {
             formula: "P".into(),
         },
+        witness: None,
+        inventory: Default::default(),
     };
 
     assert_eq!(level.initial_state.piece_count(), 1);
@@ -193,6 +206,8 @@ fn e2e_level_board_modification() {
         goal_state: GoalCondition::ProveFormula {
             formula: "Q".into(),
         },
+        witness: None,
+        inventory: Default::default(),
     };
 
     // Modify the level's board state by placing pieces
@@ -223,7 +238,10 @@ fn e2e_editor_place_pieces_save_cycle() {
             formula: "Y".into(),
             position: (2, 3),
         },
-        LogicPiece::AndIntro { position: (7, 5) },
+        LogicPiece::AndIntro {
+            position: (7, 5),
+            orientation: Direction::East,
+        },
         LogicPiece::Goal {
             formula: "X ∧ Y".into(),
             position: (12, 5),
@@ -320,8 +338,14 @@ fn e2e_complex_proof_structure() {
     }));
 
     // Logic gates
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (7, 8) }));
-    assert!(board.place_piece(LogicPiece::OrIntro { position: (12, 8) }));
+    assert!(board.place_piece(LogicPiece::AndIntro {
+        position: (7, 8),
+        orientation: Direction::East
+    }));
+    assert!(board.place_piece(LogicPiece::OrIntro {
+        position: (12, 8),
+        orientation: Direction::East
+    }));
 
     // Wires connecting pieces
     assert!(board.place_piece(LogicPiece::Wire {
@@ -359,7 +383,10 @@ fn e2e_valid_proof_structure() {
         formula: "Q".into(),
         position: (2, 4),
     };
-    let and_gate = LogicPiece::AndIntro { position: (6, 5) };
+    let and_gate = LogicPiece::AndIntro {
+        position: (6, 5),
+        orientation: Direction::East,
+    };
     let goal = LogicPiece::Goal {
         formula: "P ∧ Q".into(),
         position: (10, 5),
@@ -437,7 +464,10 @@ fn e2e_wrong_gate_types_are_detectable() {
     }));
 
     // Place NOT gate instead of AND (wrong gate type)
-    assert!(board.place_piece(LogicPiece::NotIntro { position: (6, 5) }));
+    assert!(board.place_piece(LogicPiece::NotIntro {
+        position: (6, 5),
+        orientation: Direction::East
+    }));
 
     // Place goal
     assert!(board.place_piece(LogicPiece::Goal {
@@ -462,8 +492,7 @@ fn e2e_missing_assumptions() {
 
     // Only goal is on board, no assumptions
     assert_eq!(board.piece_count(), 1);
-    let pieces = board.pieces_near(2, 5, 10);
-    assert!(pieces.is_empty(), "No assumptions placed");
+    assert!(board.assumptions().is_empty(), "No assumptions placed");
 }
 
 #[test]
@@ -476,7 +505,10 @@ fn e2e_disconnected_pieces() {
         position: (1, 1),
     }));
 
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (7, 7) }));
+    assert!(board.place_piece(LogicPiece::AndIntro {
+        position: (7, 7),
+        orientation: Direction::East
+    }));
 
     assert!(board.place_piece(LogicPiece::Goal {
         formula: "Y".into(),
@@ -499,15 +531,22 @@ fn e2e_pieces_at_board_boundaries() {
     let mut board = BoardState::new(width, height);
 
     // Place pieces at all four corners
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (0, 0) }));
+    assert!(board.place_piece(LogicPiece::AndIntro {
+        position: (0, 0),
+        orientation: Direction::East
+    }));
     assert!(board.place_piece(LogicPiece::OrIntro {
         position: (width - 1, 0),
+        orientation: Direction::East,
     }));
+    // Two-cell gates reach the top row from the row below it
     assert!(board.place_piece(LogicPiece::AndIntro {
-        position: (0, height - 1),
+        position: (0, height - 2),
+        orientation: Direction::East,
     }));
     assert!(board.place_piece(LogicPiece::OrIntro {
-        position: (width - 1, height - 1),
+        position: (width - 1, height - 2),
+        orientation: Direction::East,
     }));
 
     assert_eq!(board.piece_count(), 4);
@@ -521,16 +560,62 @@ fn e2e_pieces_at_board_boundaries() {
 fn e2e_small_board_operations() {
     let mut board = BoardState::new(2, 2);
 
-    // 2x2 board has 4 positions
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (0, 0) }));
-    assert!(board.place_piece(LogicPiece::OrIntro { position: (1, 0) }));
-    assert!(board.place_piece(LogicPiece::AndIntro { position: (0, 1) }));
-    assert!(board.place_piece(LogicPiece::OrIntro { position: (1, 1) }));
+    // 2x2 board has 4 positions, one per single-cell gate
+    assert!(board.place_piece(LogicPiece::NotIntro {
+        position: (0, 0),
+        orientation: Direction::East
+    }));
+    assert!(board.place_piece(LogicPiece::NotIntro {
+        position: (1, 0),
+        orientation: Direction::East
+    }));
+    assert!(board.place_piece(LogicPiece::NotIntro {
+        position: (0, 1),
+        orientation: Direction::East
+    }));
+    assert!(board.place_piece(LogicPiece::NotIntro {
+        position: (1, 1),
+        orientation: Direction::East
+    }));
 
     assert_eq!(board.piece_count(), 4, "All 4 positions filled");
 
     // Cannot place more
-    let result = board.place_piece(LogicPiece::AndIntro { position: (0, 0) });
+    let result = board.place_piece(LogicPiece::AndIntro {
+        position: (0, 0),
+        orientation: Direction::East,
+    });
+    assert!(!result, "All positions occupied");
+}
+
+#[test]
+fn e2e_small_board_two_cell_gates() {
+    let mut board = BoardState::new(2, 2);
+
+    // 2x2 board has 4 positions, and each two-cell gate fills a column
+    assert!(board.place_piece(LogicPiece::AndIntro {
+        position: (0, 0),
+        orientation: Direction::East
+    }));
+    assert!(board.place_piece(LogicPiece::OrIntro {
+        position: (1, 0),
+        orientation: Direction::East
+    }));
+    assert!(!board.place_piece(LogicPiece::NotIntro {
+        position: (0, 1),
+        orientation: Direction::East
+    }));
+
+    assert_eq!(board.piece_count(), 2);
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        assert!(board.is_occupied(x, y), "All 4 positions filled");
+    }
+
+    // Cannot place more
+    let result = board.place_piece(LogicPiece::AndIntro {
+        position: (0, 0),
+        orientation: Direction::East,
+    });
     assert!(!result, "All positions occupied");
 }
 
@@ -544,6 +629,7 @@ fn e2e_large_board_with_many_pieces() {
         for y in (0..50).step_by(2) {
             let piece = LogicPiece::AndIntro {
                 position: (x as u32, y as u32),
+                orientation: Direction::East,
             };
             if board.place_piece(piece) {
                 count += 1;
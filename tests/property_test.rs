@@ -6,8 +6,11 @@
 //! - Piece placement idempotency and state changes
 //! - Spatial query consistency
 //! - Formula preservation
+//! - Connectivity validation agrees with the verifier
 
-use proof_of_work::{BoardState, LogicPiece};
+use proof_of_work::game::validation::{self, GateSide, ValidationError};
+use proof_of_work::verification::{self, VerificationVerdict, VerifierOptions};
+use proof_of_work::{BoardState, Direction, GoalCondition, Level, LogicPiece};
 use proptest::prelude::*;
 
 // ============================================================================
@@ -19,11 +22,6 @@ fn board_dimension_strategy() -> impl Strategy<Value = (u32, u32)> {
     (1u32..100, 1u32..100)
 }
 
-/// Strategy to generate positions within a given board.
-fn position_strategy(width: u32, height: u32) -> impl Strategy<Value = (u32, u32)> {
-    (0u32..width, 0u32..height)
-}
-
 /// Strategy to generate formula strings (alphanumeric + operators).
 fn formula_strategy() -> impl Strategy<Value = String> {
     r"[A-Z][A-Z0-9]*( (AND|OR|IMPLIES|NOT) [A-Z][A-Z0-9]*)*".prop_map(|s| s.to_string())
@@ -73,7 +71,7 @@ fn prop_place_piece_increases_count(
         let mut board = BoardState::new(width, height);
         let initial_count = board.piece_count();
 
-        let piece = LogicPiece::AndIntro { position: (px, py) };
+        let piece = LogicPiece::NotIntro { position: (px, py), orientation: Direction::East };
         let placed = board.place_piece(piece);
 
         prop_assert!(placed, "Piece should be placed in empty position");
@@ -93,7 +91,7 @@ fn prop_place_remove_idempotent(
         let mut board = BoardState::new(width, height);
         let initial_count = board.piece_count();
 
-        let piece = LogicPiece::AndIntro { position: (px, py) };
+        let piece = LogicPiece::NotIntro { position: (px, py), orientation: Direction::East };
         board.place_piece(piece);
 
         let removed = board.remove_piece(px, py);
@@ -114,11 +112,11 @@ fn prop_no_overlapping_pieces(
 
         let mut board = BoardState::new(width, height);
 
-        let piece1 = LogicPiece::AndIntro { position: (px, py) };
+        let piece1 = LogicPiece::NotIntro { position: (px, py), orientation: Direction::East };
         let placed1 = board.place_piece(piece1);
         prop_assert!(placed1);
 
-        let piece2 = LogicPiece::OrIntro { position: (px, py) };
+        let piece2 = LogicPiece::OrIntro { position: (px, py), orientation: Direction::East };
         let placed2 = board.place_piece(piece2);
         prop_assert!(!placed2, "Should not place piece at occupied position");
         prop_assert_eq!(board.piece_count(), 1);
@@ -141,7 +139,7 @@ fn prop_pieces_near_radius_zero(
         let (px, py) = (px % width, py % height);
 
         let mut board = BoardState::new(width, height);
-        let piece = LogicPiece::AndIntro { position: (px, py) };
+        let piece = LogicPiece::NotIntro { position: (px, py), orientation: Direction::East };
         board.place_piece(piece);
 
         let near = board.pieces_near(px, py, 0);
@@ -154,23 +152,22 @@ fn prop_pieces_near_respects_radius(
         (width, height) in board_dimension_strategy(),
         radius in 0u32..50,
     ) {
-        let width = (width.max(1)).min(100) as u32;
-        let height = (height.max(1)).min(100) as u32;
+        let width = width.clamp(1, 100);
+        let height = height.clamp(1, 100);
 
         let mut board = BoardState::new(width, height);
 
         // Place piece at (1, 1)
-        let piece = LogicPiece::AndIntro { position: (1, 1) };
+        let piece = LogicPiece::AndIntro { position: (1, 1), orientation: Direction::East };
         board.place_piece(piece);
 
         // Query from origin
         let near = board.pieces_near(0, 0, radius);
 
-        if near.len() > 0 {
+        if !near.is_empty() {
             let (qx, qy) = near[0].position();
-            let dx = (qx as i32 - 0).unsigned_abs();
-            let dy = (qy as i32 - 0).unsigned_abs();
-            prop_assert!(dx <= radius && dy <= radius,
+            // Distances from the origin are just the coordinates
+            prop_assert!(qx <= radius && qy <= radius,
                 "Returned pieces must be within radius");
         }
     }
@@ -202,7 +199,7 @@ fn prop_single_piece_accessible(
         let (px, py) = (px % width, py % height);
 
         let mut board = BoardState::new(width, height);
-        let piece = LogicPiece::AndIntro { position: (px, py) };
+        let piece = LogicPiece::NotIntro { position: (px, py), orientation: Direction::East };
         board.place_piece(piece);
 
         let retrieved = board.piece_at(px, py);
@@ -219,10 +216,7 @@ fn prop_single_piece_accessible(
     #[test]
     fn prop_assumption_stores_formula(
         formula in formula_strategy(),
-        (width, height) in board_dimension_strategy(),
     ) {
-        let width = width.max(1);
-        let height = height.max(1);
         let piece = LogicPiece::Assumption {
             formula: formula.clone(),
             position: (0, 0),
@@ -239,10 +233,7 @@ fn prop_assumption_stores_formula(
     #[test]
     fn prop_goal_stores_formula(
         formula in formula_strategy(),
-        (width, height) in board_dimension_strategy(),
     ) {
-        let width = width.max(1);
-        let height = height.max(1);
         let piece = LogicPiece::Goal {
             formula: formula.clone(),
             position: (0, 0),
@@ -297,9 +288,165 @@ fn prop_cannot_place_out_of_bounds(
         // Try to place piece at boundary + 1
         let piece = LogicPiece::AndIntro {
             position: (width + 10, height + 10),
+            orientation: Direction::East,
         };
 
         let placed = board.place_piece(piece);
         prop_assert!(!placed, "Cannot place piece outside bounds");
     }
 }
+
+// ============================================================================
+// Property: Connectivity validation agrees with the verifier
+// ============================================================================
+
+fn and_puzzle_level() -> Level {
+    Level {
+        id: 1,
+        name: "Property".to_string(),
+        description: String::new(),
+        theorem: "(assert (=> (and P Q) R))".to_string(),
+        initial_state: BoardState::new(10, 10),
+        goal_state: GoalCondition::ProveFormula {
+            formula: "R".to_string(),
+        },
+        witness: None,
+        inventory: Default::default(),
+    }
+}
+
+fn is_connectivity_error(error: &ValidationError) -> bool {
+    matches!(
+        error,
+        ValidationError::DisconnectedGate { .. } | ValidationError::UnreachableGoal { .. }
+    )
+}
+
+/// Strategy to generate a solved "P AND Q => R" circuit at a random
+/// place on a 10x10 board: P and Q feed an AND gate whose output runs
+/// along a wire of random length into the goal.
+fn connected_and_puzzle_strategy() -> impl Strategy<Value = Vec<LogicPiece>> {
+    (1u32..=3)
+        .prop_flat_map(|len| (Just(len), 0u32..=(6 - len), 0u32..9))
+        .prop_map(|(len, x, y)| and_circuit(x, y, len, x + len + 3))
+}
+
+/// Strategy to generate the circuit above with its link to the goal cut,
+/// either by leaving out the wire or by leaving a gap before the goal.
+fn cut_and_puzzle_strategy() -> impl Strategy<Value = Vec<LogicPiece>> {
+    (1u32..=3)
+        .prop_flat_map(|len| (Just(len), 0u32..=(5 - len), 0u32..9, any::<bool>()))
+        .prop_map(|(len, x, y, drop_wire)| {
+            if drop_wire {
+                let mut pieces = and_circuit(x, y, len, x + len + 3);
+                pieces.retain(|p| !matches!(p, LogicPiece::Wire { .. }));
+                pieces
+            } else {
+                and_circuit(x, y, len, x + len + 4)
+            }
+        })
+}
+
+/// Strategy to generate the connected circuit above with P or Q left
+/// out, so one input port of the AND gate has nothing attached.
+fn half_wired_and_puzzle_strategy() -> impl Strategy<Value = Vec<LogicPiece>> {
+    connected_and_puzzle_strategy()
+        .prop_flat_map(|pieces| (Just(pieces), 0usize..2))
+        .prop_map(|(mut pieces, missing)| {
+            pieces.remove(missing);
+            pieces
+        })
+}
+
+/// P at (x, y + 1) and Q at (x, y) into an AND gate, then a wire `len`
+/// cells long from the gate's output, and the goal at column `goal_x`.
+fn and_circuit(x: u32, y: u32, len: u32, goal_x: u32) -> Vec<LogicPiece> {
+    vec![
+        LogicPiece::Assumption {
+            formula: "P".to_string(),
+            position: (x, y + 1),
+        },
+        LogicPiece::Assumption {
+            formula: "Q".to_string(),
+            position: (x, y),
+        },
+        LogicPiece::AndIntro {
+            position: (x + 1, y),
+            orientation: Direction::East,
+        },
+        LogicPiece::Wire {
+            from: (x + 2, y),
+            to: (x + 2 + len, y),
+        },
+        LogicPiece::Goal {
+            formula: "R".to_string(),
+            position: (goal_x, y),
+        },
+    ]
+}
+
+proptest! {
+    /// Property: A circuit that feeds the goal has no disconnected gate or
+    /// unreachable goal, and the verifier never rejects it.
+    #[test]
+    fn prop_verified_solution_is_connected(pieces in connected_and_puzzle_strategy()) {
+        let level = and_puzzle_level();
+        prop_assert!(verification::proof_obligations(&level, &pieces).is_some());
+
+        let board = BoardState::with_pieces(10, 10, pieces.clone());
+        let result = validation::validate_board(&board);
+        prop_assert!(
+            !result.errors.iter().any(is_connectivity_error),
+            "connected board reported {:?}",
+            result.errors
+        );
+
+        let verdict = verification::verify_level_solution(&level, &pieces, &VerifierOptions::default());
+        prop_assert_ne!(verdict, VerificationVerdict::Rejected);
+    }
+
+    /// Property: A board whose goal is unreachable is never verified.
+    #[test]
+    fn prop_unreachable_goal_never_verified(pieces in cut_and_puzzle_strategy()) {
+        let board = BoardState::with_pieces(10, 10, pieces.clone());
+        let errors = validation::check_connectivity(&board);
+        prop_assert!(
+            errors
+                .iter()
+                .any(|e| matches!(e, ValidationError::UnreachableGoal { .. })),
+            "cut board reported {:?}",
+            errors
+        );
+
+        let level = and_puzzle_level();
+        prop_assert!(verification::proof_obligations(&level, &pieces).is_none());
+        let verdict = verification::verify_level_solution(&level, &pieces, &VerifierOptions::default());
+        prop_assert_ne!(verdict, VerificationVerdict::Verified);
+    }
+
+    /// Property: An AND gate with an input port left empty is reported as
+    /// disconnected, and the verifier never accepts the circuit it feeds.
+    #[test]
+    fn prop_half_wired_gate_never_verified(pieces in half_wired_and_puzzle_strategy()) {
+        let gate = pieces
+            .iter()
+            .find(|p| matches!(p, LogicPiece::AndIntro { .. }))
+            .map(LogicPiece::position)
+            .unwrap();
+        let board = BoardState::with_pieces(10, 10, pieces.clone());
+        let errors = validation::check_connectivity(&board);
+        prop_assert!(
+            errors.contains(&ValidationError::DisconnectedGate {
+                position: gate,
+                side: GateSide::Input,
+            }),
+            "half-wired board reported {:?}",
+            errors
+        );
+
+        let level = and_puzzle_level();
+        prop_assert!(verification::proof_obligations(&level, &pieces).is_none());
+        let verdict = verification::verify_level_solution(&level, &pieces, &VerifierOptions::default());
+        prop_assert_ne!(verdict, VerificationVerdict::Verified);
+    }
+}